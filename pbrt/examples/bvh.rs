@@ -9,7 +9,7 @@ use cgmath::{ Deg, Matrix4 };
 use pbrt::prelude::*;
 
 use pbrt::aggregate::{ BvhAccel, SplitMethod };
-use pbrt::camera::{ OrthographicCamera, PerspectiveCamera };
+use pbrt::camera::{ Aperture, OrthographicCamera, PerspectiveCamera };
 use pbrt::film::Film;
 use pbrt::filter::TriangleFilter;
 use pbrt::integrator::{ Integrator, DirectLightingIntegrator, NormalIntegrator, WhittedIntegrator };
@@ -17,6 +17,7 @@ use pbrt::integrator::LightStrategy;
 use pbrt::light::{ Light, PointLight };
 use pbrt::material::{ Material, MatteMaterial };
 use pbrt::math::{ AnimatedTransform, Transform };
+use pbrt::medium::MediumInterface;
 use pbrt::primitive::{ Primitive, GeometricPrimitive };
 use pbrt::sampler::StratifiedSampler;
 use pbrt::scene::Scene;
@@ -24,7 +25,7 @@ use pbrt::shape::{ ShapeData, Cylinder, Sphere };
 use pbrt::spectrum::{ SpectrumType, Spectrum as PbrtSpectrum };
 use pbrt::texture::ConstantTexture;
 
-fn ring(transform: Arc<Transform>, material: Box<impl Material + Clone + Send + Sync + 'static>, radius: Float, height: Float) -> (Arc<Primitive + Send>, Arc<Primitive + Send>) {
+fn ring(transform: Arc<Transform>, material: Arc<dyn Material + Send + Sync>, radius: Float, height: Float) -> (Arc<Primitive + Send>, Arc<Primitive + Send>) {
     let data = ShapeData::new(transform.clone(), false);
     let sphere = Sphere::new(radius, -height, height, Deg(float(360.0)), data);
     let sphere = Arc::new(sphere);
@@ -33,7 +34,7 @@ fn ring(transform: Arc<Transform>, material: Box<impl Material + Clone + Send +
         shape: sphere,
         material: Some(material.clone()),
         area_light: None,
-        medium_interface: None,
+        medium_interface: MediumInterface::default(),
     };
     let primitive0 = Arc::new(primitive0);
 
@@ -45,7 +46,7 @@ fn ring(transform: Arc<Transform>, material: Box<impl Material + Clone + Send +
         shape: sphere,
         material: Some(material),
         area_light: None,
-        medium_interface: None,
+        medium_interface: MediumInterface::default(),
     };
     let primitive1 = Arc::new(primitive1);
 
@@ -87,13 +88,13 @@ fn main() {
 
             MatteMaterial::new(kd, sigma, None)
         };
-        let material = Box::new(material);
+        let material = Arc::new(material);
 
         let primitive = GeometricPrimitive {
             shape: sphere,
             material: Some(material),
             area_light: None,
-            medium_interface: None,
+            medium_interface: MediumInterface::default(),
         };
         Arc::new(primitive)
     };
@@ -123,7 +124,7 @@ fn main() {
 
         MatteMaterial::new(kd, sigma, None)
     };
-    let material = Box::new(material);
+    let material = Arc::new(material);
 
     let sphere_2 = ring(transform, material, float(1.0), float(0.05));
 
@@ -152,7 +153,7 @@ fn main() {
 
         MatteMaterial::new(kd, sigma, None)
     };
-    let material = Box::new(material);
+    let material = Arc::new(material);
 
     let sphere_3 = ring(transform, material, float(0.8), float(0.05));
 
@@ -183,7 +184,7 @@ fn main() {
                 float(1.0),
             ], SpectrumType::Illumination) * float(10.0),
             transform);
-        Box::new(light)
+        Arc::new(light)
     };
 
     let camera = {
@@ -235,6 +236,7 @@ fn main() {
             float(1.0),
             float(0.0),
             float(1.0),
+            Aperture::Circular,
             Deg(float(45.0)),
             film,
             None
@@ -257,5 +259,5 @@ fn main() {
     let mut integrator = DirectLightingIntegrator::new(2, LightStrategy::UniformSampleAll, camera, sampler);
 //    let mut integrator = NormalIntegrator::new(camera, sampler);
 
-    integrator.render(scene);
+    integrator.render(scene).unwrap();
 }
@@ -0,0 +1,30 @@
+#![feature(nll)]
+extern crate pbrt;
+
+use std::env;
+use std::process;
+
+use pbrt::parser;
+
+fn main() {
+    let path = match env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: render_pbrt <scene.pbrt>");
+            process::exit(1);
+        },
+    };
+
+    let (scene, mut integrator) = match parser::parse_file(&path) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        },
+    };
+
+    if let Err(err) = integrator.render(scene) {
+        eprintln!("couldn't write image: {}", err);
+        process::exit(1);
+    }
+}
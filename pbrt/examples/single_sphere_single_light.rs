@@ -8,7 +8,7 @@ use cgmath::{ Deg, Matrix4 };
 
 use pbrt::prelude::*;
 
-use pbrt::camera::PerspectiveCamera;
+use pbrt::camera::{ Aperture, PerspectiveCamera };
 use pbrt::film::Film;
 use pbrt::filter::TriangleFilter;
 use pbrt::integrator::{ Integrator, DirectLightingIntegrator, WhittedIntegrator };
@@ -16,6 +16,7 @@ use pbrt::integrator::LightStrategy;
 use pbrt::light::{ Light, PointLight };
 use pbrt::material::MatteMaterial;
 use pbrt::math::{ AnimatedTransform, Transform };
+use pbrt::medium::MediumInterface;
 use pbrt::primitive::GeometricPrimitive;
 use pbrt::sampler::StratifiedSampler;
 use pbrt::scene::Scene;
@@ -57,13 +58,13 @@ fn main() {
 
         MatteMaterial::new(kd, sigma, None)
     };
-    let material = Box::new(material);
+    let material = Arc::new(material);
 
     let primitive = GeometricPrimitive {
         shape: sphere,
         material: Some(material),
         area_light: None,
-        medium_interface: None,
+        medium_interface: MediumInterface::default(),
     };
     let primitive = Arc::new(primitive);
 
@@ -82,7 +83,7 @@ fn main() {
                 float(1.0),
             ], SpectrumType::Illumination) * float(10.0),
             transform);
-        Box::new(light)
+        Arc::new(light)
     };
 
     let camera = {
@@ -134,6 +135,7 @@ fn main() {
             float(1.0),
             float(0.0),
             float(1.0),
+            Aperture::Circular,
             Deg(float(45.0)),
             film,
             None
@@ -155,5 +157,5 @@ fn main() {
 //    let mut integrator = WhittedIntegrator::new(2, camera, sampler);
     let mut integrator = DirectLightingIntegrator::new(2, LightStrategy::UniformSampleOne, camera, sampler);
 
-    integrator.render(scene);
+    integrator.render(scene).unwrap();
 }
@@ -7,7 +7,7 @@ use std::sync::{ Arc, Mutex };
 use cgmath::{ Deg, Matrix4 };
 
 use pbrt::aggregate::{ BvhAccel, SplitMethod };
-use pbrt::camera::{ OrthographicCamera, PerspectiveCamera };
+use pbrt::camera::{ Aperture, OrthographicCamera, PerspectiveCamera };
 use pbrt::film::Film;
 use pbrt::filter::TriangleFilter;
 use pbrt::integrator::{ Integrator, DirectLightingIntegrator, NormalIntegrator, WhittedIntegrator };
@@ -15,6 +15,7 @@ use pbrt::integrator::LightStrategy;
 use pbrt::light::{ Light, PointLight };
 use pbrt::material::{ Material, MatteMaterial };
 use pbrt::math::{ AnimatedTransform, Transform };
+use pbrt::medium::MediumInterface;
 use pbrt::primitive::{ Primitive, GeometricPrimitive };
 use pbrt::sampler::StratifiedSampler;
 use pbrt::scene::Scene;
@@ -27,7 +28,7 @@ use pbrt::camera::*;
 use pbrt::sampler::*;
 use pbrt::shape::*;
 
-fn ring(transform: Arc<Transform>, material: Box<impl Material + Clone + Send + Sync + 'static>, radius: Float, height: Float) -> (Arc<Primitive + Send>, Arc<Primitive + Send>) {
+fn ring(transform: Arc<Transform>, material: Arc<dyn Material + Send + Sync>, radius: Float, height: Float) -> (Arc<Primitive + Send>, Arc<Primitive + Send>) {
     let data = ShapeData::new(transform.clone(), false);
     let sphere = Sphere::new(radius, -height, height, Deg(float(360.0)), data);
     let sphere = Arc::new(sphere);
@@ -36,7 +37,7 @@ fn ring(transform: Arc<Transform>, material: Box<impl Material + Clone + Send +
         shape: sphere,
         material: Some(material.clone()),
         area_light: None,
-        medium_interface: None,
+        medium_interface: MediumInterface::default(),
     };
     let primitive0 = Arc::new(primitive0);
 
@@ -48,7 +49,7 @@ fn ring(transform: Arc<Transform>, material: Box<impl Material + Clone + Send +
         shape: sphere,
         material: Some(material),
         area_light: None,
-        medium_interface: None,
+        medium_interface: MediumInterface::default(),
     };
     let primitive1 = Arc::new(primitive1);
 
@@ -92,13 +93,13 @@ fn main() {
 
             MatteMaterial::new(kd, sigma, None)
         };
-        let material = Box::new(material);
+        let material = Arc::new(material);
 
         let primitive = GeometricPrimitive {
             shape: sphere,
             material: Some(material),
             area_light: None,
-            medium_interface: None,
+            medium_interface: MediumInterface::default(),
         };
         Arc::new(primitive)
     };
@@ -128,7 +129,7 @@ fn main() {
 
         MatteMaterial::new(kd, sigma, None)
     };
-    let material = Box::new(material);
+    let material = Arc::new(material);
 
     let sphere_2 = ring(transform, material, float(1.0), float(0.05));
 
@@ -157,7 +158,7 @@ fn main() {
 
         MatteMaterial::new(kd, sigma, None)
     };
-    let material = Box::new(material);
+    let material = Arc::new(material);
 
     let sphere_3 = ring(transform, material, float(0.8), float(0.05));
 
@@ -221,6 +222,7 @@ fn main() {
             float(1.0),
             float(0.0),
             float(1.0),
+            Aperture::Circular,
             Deg(float(45.0)),
             film,
             None
@@ -123,7 +123,6 @@ impl BvhAccel {
         }
 
         // build BVH tree from primitive_info
-        let arena = ();
         let mut total = 0;
         let mut ordered_primitives = Vec::with_capacity(primitives.len());
 
@@ -133,7 +132,6 @@ impl BvhAccel {
                 &primitive_info,
                 &mut total,
                 &mut ordered_primitives,
-                &arena
             )
         } else {
             recursive_build(
@@ -143,7 +141,6 @@ impl BvhAccel {
                 0..primitives.len(),
                 &mut total,
                 &mut ordered_primitives,
-                &arena
             )
         };
 
@@ -318,7 +315,7 @@ fn flatten_bvh_tree(nodes: &mut Vec<Option<LinearBvhNode>>, node: Arc<BvhBuildNo
     my_offset
 }
 
-fn hlbvh_build(primitives: &'a [Arc<dyn Primitive + Send>], primitive_info: &[BvhPrimitiveInfo], total_nodes: &mut usize, ordered_primitives: &'a mut Vec<Arc<dyn Primitive + Send>>, arena: &()) -> Arc<BvhBuildNode> {
+fn hlbvh_build(primitives: &'a [Arc<dyn Primitive + Send>], primitive_info: &[BvhPrimitiveInfo], total_nodes: &mut usize, ordered_primitives: &'a mut Vec<Arc<dyn Primitive + Send>>) -> Arc<BvhBuildNode> {
     // compute bounding box of all primitive centroids
     let mut bounds = Bounds3f::empty();
     for p in primitive_info.iter() {
@@ -472,7 +469,7 @@ fn hlbvh_build(primitives: &'a [Arc<dyn Primitive + Send>], primitive_info: &[Bv
     let op = op.into_inner().unwrap();
     *ordered_primitives = op.clone().into_iter().map(|p| p.unwrap()).collect();
 
-    fn build_upper_sah(treelet_roots: &mut [Arc<BvhBuildNode>], start: usize, end: usize, total_nodes: &mut usize, arena: &()) -> Arc<BvhBuildNode> {
+    fn build_upper_sah(treelet_roots: &mut [Arc<BvhBuildNode>], start: usize, end: usize, total_nodes: &mut usize) -> Arc<BvhBuildNode> {
         assert!(start < end);
 
         let n_nodes = end - start;
@@ -583,8 +580,8 @@ fn hlbvh_build(primitives: &'a [Arc<dyn Primitive + Send>], primitive_info: &[Bv
 
         let node = BvhBuildNode::init_interior(
             dim,
-            build_upper_sah(treelet_roots, start, mid, total_nodes, arena),
-            build_upper_sah(treelet_roots, mid, end, total_nodes, arena),
+            build_upper_sah(treelet_roots, start, mid, total_nodes),
+            build_upper_sah(treelet_roots, mid, end, total_nodes),
         );
 
         Arc::new(node)
@@ -596,10 +593,10 @@ fn hlbvh_build(primitives: &'a [Arc<dyn Primitive + Send>], primitive_info: &[Bv
         .collect::<Vec<_>>();
 
     let len = finished_treelets.len();
-    build_upper_sah(&mut finished_treelets, 0, len, total_nodes, arena)
+    build_upper_sah(&mut finished_treelets, 0, len, total_nodes)
 }
 
-fn recursive_build(primitives: &'a [Arc<dyn Primitive + Send>], primitive_info: &[BvhPrimitiveInfo], split_method: SplitMethod, range: Range<usize>, total_nodes: &mut usize, ordered_primitives: &'a mut Vec<Arc<dyn Primitive + Send>>, arena: &()) -> Arc<BvhBuildNode> {
+fn recursive_build(primitives: &'a [Arc<dyn Primitive + Send>], primitive_info: &[BvhPrimitiveInfo], split_method: SplitMethod, range: Range<usize>, total_nodes: &mut usize, ordered_primitives: &'a mut Vec<Arc<dyn Primitive + Send>>) -> Arc<BvhBuildNode> {
     assert!(!primitive_info.is_empty());
 
     let Range { start, end } = range;
@@ -755,8 +752,8 @@ fn recursive_build(primitives: &'a [Arc<dyn Primitive + Send>], primitive_info:
             }
 
             Arc::new(BvhBuildNode::init_interior(dim,
-                recursive_build(primitives, &primitive_info, split_method, (range.start)..mid, total_nodes, ordered_primitives, arena),
-                recursive_build(primitives, &primitive_info, split_method, mid..(range.end), total_nodes, ordered_primitives, arena),
+                recursive_build(primitives, &primitive_info, split_method, (range.start)..mid, total_nodes, ordered_primitives),
+                recursive_build(primitives, &primitive_info, split_method, mid..(range.end), total_nodes, ordered_primitives),
             ))
         }
     }
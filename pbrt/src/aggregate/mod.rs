@@ -4,6 +4,7 @@ use crate::primitive::Primitive;
 use crate::interaction::SurfaceInteraction;
 use crate::light::Light;
 use crate::material::Material;
+use crate::memory::MemoryArena;
 
 mod bvh;
 pub use self::bvh::{ BvhAccel, SplitMethod };
@@ -19,7 +20,7 @@ default impl<T> Primitive for T where T: Aggregate {
         panic!("Aggregate::get_material should never be called")
     }
 
-    default fn compute_scattering_functions(&'a self, _: SurfaceInteraction<'a>, _: &(), _: TransportMode, _: bool) -> SurfaceInteraction<'a> {
+    default fn compute_scattering_functions(&'a self, _: SurfaceInteraction<'a>, _: &'a MemoryArena, _: TransportMode, _: bool) -> SurfaceInteraction<'a> {
         panic!("Aggregate::compute_scattering_functions should never be called");
     }
 }
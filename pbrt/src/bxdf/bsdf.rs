@@ -1,5 +1,4 @@
 use std::cmp::min;
-use std::sync::Arc;
 use cgmath::prelude::*;
 use crate::prelude::*;
 use crate::interaction::SurfaceInteraction;
@@ -7,17 +6,22 @@ use super::{ Bxdf, BxdfType };
 use crate::interaction::Sample;
 use crate::sampler::ONE_MINUS_EPSILON;
 
+pub const MAX_BXDFS: usize = 8;
+
+/// The `Bxdf`s are borrowed from the `MemoryArena`
+/// that the `Bsdf` was allocated in.
 #[derive(Clone, Debug)]
-pub struct Bsdf {
+pub struct Bsdf<'a> {
     pub eta: Float,
     n_s: Normal,
     n_g: Normal,
     ss: Vector3f,
     ts: Vector3f,
-    bxdfs: Vec<Arc<dyn Bxdf>>,
+    n_bxdfs: usize,
+    bxdfs: [Option<&'a dyn Bxdf>; MAX_BXDFS],
 }
 
-impl Bsdf {
+impl Bsdf<'a> {
     pub fn new(si: &SurfaceInteraction<'_>, eta: Option<Float>) -> Self {
         let eta = eta.unwrap_or_else(|| float(1.0));
 
@@ -27,18 +31,25 @@ impl Bsdf {
             n_g: si.n.unwrap(),
             ss: si.shading.dpdu.normalize(),
             ts: (*si.shading.n).cross(si.shading.dpdu.normalize()),
-            bxdfs: vec![],
+            n_bxdfs: 0,
+            bxdfs: [None; MAX_BXDFS],
         }
     }
 
-    pub fn add(&mut self, bxdf: Arc<dyn Bxdf>) {
-        self.bxdfs.push(bxdf);
+    pub fn add(&mut self, bxdf: &'a dyn Bxdf) {
+        assert!(self.n_bxdfs < MAX_BXDFS, "Bsdf can't have more than {} Bxdfs", MAX_BXDFS);
+        self.bxdfs[self.n_bxdfs] = Some(bxdf);
+        self.n_bxdfs += 1;
+    }
+
+    fn bxdfs(&self) -> impl Iterator<Item = &'a dyn Bxdf> + '_ {
+        self.bxdfs[..self.n_bxdfs].iter().filter_map(|b| *b)
     }
 
     pub fn num_components(&self, flags: BxdfType) -> usize {
         let mut num = 0;
 
-        for bxdf in &self.bxdfs {
+        for bxdf in self.bxdfs() {
             if flags.contains(bxdf.ty()) {
                 num += 1;
             }
@@ -75,7 +86,7 @@ impl Bsdf {
 
         let mut f = Spectrum::new(0.0);
 
-        for bxdf in &self.bxdfs {
+        for bxdf in self.bxdfs() {
             if flags.contains(bxdf.ty()) &&
                 ((reflect && bxdf.ty().contains(BxdfType::Reflection)) ||
                 (!reflect && bxdf.ty().contains(BxdfType::Transmission))) {
@@ -98,11 +109,10 @@ impl Bsdf {
             matching - 1,
         );
 
-        let bxdfs = self.bxdfs.iter()
+        let bxdf = self.bxdfs()
             .filter(|b| ty.contains(b.ty()))
-            .enumerate()
-            .collect::<Vec<_>>();
-        let (chosen_idx, bxdf) = bxdfs[comp];
+            .nth(comp)
+            .unwrap();
 
         // u[0] is no longer uniformly distributed
         // but we can a uniformly distributed one back
@@ -129,8 +139,8 @@ impl Bsdf {
         let wi_world = self.local_to_world(sample.wi);
 
         if !bxdf.ty().contains(BxdfType::Specular) && matching > 1 {
-            for (idx, bxdf) in &bxdfs {
-                if *idx == chosen_idx {
+            for (idx, bxdf) in self.bxdfs().filter(|b| ty.contains(b.ty())).enumerate() {
+                if idx == comp {
                     continue;
                 }
 
@@ -149,7 +159,7 @@ impl Bsdf {
 
             let mut f = Spectrum::new(0.0);
 
-            for (_, bxdf) in &bxdfs {
+            for bxdf in self.bxdfs() {
                 if ty.contains(bxdf.ty()) &&
                     ((reflect && bxdf.ty().contains(BxdfType::Reflection)) ||
                      (!reflect && bxdf.ty().contains(BxdfType::Transmission))) {
@@ -164,7 +174,7 @@ impl Bsdf {
     }
 
     pub fn pdf(&self, wo_w: Vector3f, wi_w: Vector3f, flags: BxdfType) -> Float {
-        if self.bxdfs.is_empty() {
+        if self.n_bxdfs == 0 {
             return float(0.0);
         }
 
@@ -178,7 +188,7 @@ impl Bsdf {
         let mut pdf = float(0.0);
         let mut matching = 0;
 
-        for bxdf in &self.bxdfs {
+        for bxdf in self.bxdfs() {
             if flags.contains(bxdf.ty()) {
                 matching += 1;
                 pdf += bxdf.pdf(wo, wi);
@@ -195,7 +205,7 @@ impl Bsdf {
     pub fn rho(&self, wo: Option<Vector3f>, n_samples: i32, samples: &[Point2f], flags: BxdfType) -> Spectrum {
         let mut f = Spectrum::new(0.0);
 
-        for bxdf in &self.bxdfs {
+        for bxdf in self.bxdfs() {
             if flags.contains(bxdf.ty()) {
                 f += bxdf.rho(wo, n_samples, samples);
             }
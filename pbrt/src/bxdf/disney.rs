@@ -0,0 +1,329 @@
+//! The lobes of Burley's principled BSDF, as used by `DisneyMaterial`.
+
+use std::cmp::max;
+use num;
+use cgmath::prelude::*;
+use crate::prelude::*;
+use super::*;
+use super::fresnel::dielectric;
+use super::utils::*;
+
+/// `(1 - cos_theta)^5`, the weight of the Schlick approximation to the Fresnel reflectance.
+fn schlick_weight(cos_theta: Float) -> Float {
+    let m = num::clamp(float(1.0) - cos_theta, float(0.0), float(1.0));
+    m.powi(5)
+}
+
+/// The Schlick approximation to the Fresnel reflectance, given the reflectance at normal incidence.
+fn fr_schlick(r0: Spectrum, cos_theta: Float) -> Spectrum {
+    r0.lerp(Spectrum::new(1.0), schlick_weight(cos_theta))
+}
+
+/// The reflectance at normal incidence of a dielectric with the index of refraction `eta`.
+pub fn schlick_r0_from_eta(eta: Float) -> Float {
+    ((eta - float(1.0)) / (eta + float(1.0))).powi(2)
+}
+
+/// The generalized Trowbridge-Reitz distribution with `gamma = 1`, used by the clearcoat.
+fn gtr1(cos_theta: Float, alpha: Float) -> Float {
+    let alpha_2 = alpha * alpha;
+    (alpha_2 - float(1.0)) / (Float::pi() * alpha_2.ln() * (float(1.0) + (alpha_2 - float(1.0)) * cos_theta * cos_theta))
+}
+
+/// The Smith masking term for GGX.
+fn smith_g_ggx(cos_theta: Float, alpha: Float) -> Float {
+    let alpha_2 = alpha * alpha;
+    let cos_theta_2 = cos_theta * cos_theta;
+    float(1.0) / (cos_theta + (alpha_2 + cos_theta_2 - alpha_2 * cos_theta_2).sqrt())
+}
+
+/// The normalized half vector of `wo` and `wi`, or `None` if they point in opposite directions.
+fn half_vector(wo: Vector3f, wi: Vector3f) -> Option<Vector3f> {
+    let wh = wi + wo;
+    if wh.magnitude2() == 0.0 {
+        None
+    } else {
+        Some(wh.normalize())
+    }
+}
+
+/// Lambertian reflection that falls off at grazing angles,
+/// the retro-reflection of rough surfaces is added by `DisneyRetro`.
+#[derive(Debug)]
+pub struct DisneyDiffuse {
+    r: Spectrum,
+}
+
+impl DisneyDiffuse {
+    pub fn new(r: Spectrum) -> Self {
+        Self { r }
+    }
+}
+
+impl Bxdf for DisneyDiffuse {
+    fn ty(&self) -> BxdfType {
+        BxdfType::Reflection | BxdfType::Diffuse
+    }
+
+    fn f(&self, wo: Vector3f, wi: Vector3f) -> Spectrum {
+        let fo = schlick_weight(cos_theta_abs(wo));
+        let fi = schlick_weight(cos_theta_abs(wi));
+
+        self.r * Float::frac_1_pi() * (float(1.0) - fo / float(2.0)) * (float(1.0) - fi / float(2.0))
+    }
+
+    fn rho(&self, _: Option<Vector3f>, _: i32, _: &[Point2f]) -> Spectrum {
+        self.r
+    }
+}
+
+/// An approximation of subsurface scattering for thin surfaces, based on the Hanrahan-Krueger BRDF.
+#[derive(Debug)]
+pub struct DisneyFakeSs {
+    r: Spectrum,
+    roughness: Float,
+}
+
+impl DisneyFakeSs {
+    pub fn new(r: Spectrum, roughness: Float) -> Self {
+        Self { r, roughness }
+    }
+}
+
+impl Bxdf for DisneyFakeSs {
+    fn ty(&self) -> BxdfType {
+        BxdfType::Reflection | BxdfType::Diffuse
+    }
+
+    fn f(&self, wo: Vector3f, wi: Vector3f) -> Spectrum {
+        let wh = match half_vector(wo, wi) {
+            Some(wh) => wh,
+            None => return Spectrum::new(0.0),
+        };
+        let cos_theta_d = wi.dot(wh);
+
+        // fss90 is used to "flatten" the retro-reflection based on roughness
+        let fss90 = cos_theta_d * cos_theta_d * self.roughness;
+        let fo = schlick_weight(cos_theta_abs(wo));
+        let fi = schlick_weight(cos_theta_abs(wi));
+        let fss = lerp(fo, float(1.0), fss90) * lerp(fi, float(1.0), fss90);
+
+        // 1.25 scale is used to (roughly) preserve albedo
+        let ss = float(1.25) * (fss * (float(1.0) / (cos_theta_abs(wo) + cos_theta_abs(wi)) - float(0.5)) + float(0.5));
+
+        self.r * Float::frac_1_pi() * ss
+    }
+
+    fn rho(&self, _: Option<Vector3f>, _: i32, _: &[Point2f]) -> Spectrum {
+        self.r
+    }
+}
+
+/// The retro-reflection of rough diffuse surfaces.
+#[derive(Debug)]
+pub struct DisneyRetro {
+    r: Spectrum,
+    roughness: Float,
+}
+
+impl DisneyRetro {
+    pub fn new(r: Spectrum, roughness: Float) -> Self {
+        Self { r, roughness }
+    }
+}
+
+impl Bxdf for DisneyRetro {
+    fn ty(&self) -> BxdfType {
+        BxdfType::Reflection | BxdfType::Diffuse
+    }
+
+    fn f(&self, wo: Vector3f, wi: Vector3f) -> Spectrum {
+        let wh = match half_vector(wo, wi) {
+            Some(wh) => wh,
+            None => return Spectrum::new(0.0),
+        };
+        let cos_theta_d = wi.dot(wh);
+
+        let fo = schlick_weight(cos_theta_abs(wo));
+        let fi = schlick_weight(cos_theta_abs(wi));
+        let rr = float(2.0) * self.roughness * cos_theta_d * cos_theta_d;
+
+        // Burley 2015, eq (4)
+        self.r * Float::frac_1_pi() * rr * (fo + fi + fo * fi * (rr - float(1.0)))
+    }
+
+    fn rho(&self, _: Option<Vector3f>, _: i32, _: &[Point2f]) -> Spectrum {
+        self.r
+    }
+}
+
+/// The extra reflection at grazing angles of cloth.
+#[derive(Debug)]
+pub struct DisneySheen {
+    r: Spectrum,
+}
+
+impl DisneySheen {
+    pub fn new(r: Spectrum) -> Self {
+        Self { r }
+    }
+}
+
+impl Bxdf for DisneySheen {
+    fn ty(&self) -> BxdfType {
+        BxdfType::Reflection | BxdfType::Diffuse
+    }
+
+    fn f(&self, wo: Vector3f, wi: Vector3f) -> Spectrum {
+        let wh = match half_vector(wo, wi) {
+            Some(wh) => wh,
+            None => return Spectrum::new(0.0),
+        };
+        let cos_theta_d = wi.dot(wh);
+
+        self.r * schlick_weight(cos_theta_d)
+    }
+
+    fn rho(&self, _: Option<Vector3f>, _: i32, _: &[Point2f]) -> Spectrum {
+        self.r
+    }
+}
+
+/// A second, glossy specular lobe for a clear coating, which is always isotropic and uncolored.
+#[derive(Debug)]
+pub struct DisneyClearcoat {
+    weight: Float,
+    gloss: Float,
+}
+
+impl DisneyClearcoat {
+    /// `gloss` is the alpha of the `GTR1` distribution.
+    pub fn new(weight: Float, gloss: Float) -> Self {
+        Self { weight, gloss }
+    }
+}
+
+impl Bxdf for DisneyClearcoat {
+    fn ty(&self) -> BxdfType {
+        BxdfType::Reflection | BxdfType::Glossy
+    }
+
+    fn f(&self, wo: Vector3f, wi: Vector3f) -> Spectrum {
+        let wh = match half_vector(wo, wi) {
+            Some(wh) => wh,
+            None => return Spectrum::new(0.0),
+        };
+
+        // clearcoat has ior = 1.5 hardcoded -> F0 = 0.04, and a fixed GGX alpha of 0.25 for G
+        let dr = gtr1(cos_theta_abs(wh), self.gloss);
+        let fr = fr_schlick(Spectrum::new(0.04), wo.dot(wh));
+        let gr = smith_g_ggx(cos_theta_abs(wo), float(0.25)) * smith_g_ggx(cos_theta_abs(wi), float(0.25));
+
+        fr * self.weight * gr * dr / float(4.0)
+    }
+
+    fn sample_f(&self, wo: Vector3f, u: Point2f) -> Option<Sample> {
+        if wo.z == 0.0 {
+            return None;
+        }
+
+        // sample the GTR1 distribution of microfacet normals
+        let alpha_2 = self.gloss * self.gloss;
+        let cos_theta = max(float(0.0), (float(1.0) - alpha_2.powf(float(1.0) - u[0])) / (float(1.0) - alpha_2)).sqrt();
+        let sin_theta = max(float(0.0), float(1.0) - cos_theta * cos_theta).sqrt();
+        let phi = float(2.0) * Float::pi() * u[1];
+
+        let wh = spherical_direction(sin_theta, cos_theta, phi);
+        let wh = if same_hemisphere(wo, wh) { wh } else { -wh };
+
+        let wi = reflect(wo, wh);
+        if !same_hemisphere(wo, wi) {
+            return None;
+        }
+
+        Some(Sample {
+            wi,
+            pdf: self.pdf(wo, wi),
+            li: self.f(wo, wi),
+            ty: Some(self.ty()),
+        })
+    }
+
+    fn pdf(&self, wo: Vector3f, wi: Vector3f) -> Float {
+        if !same_hemisphere(wo, wi) {
+            return float(0.0);
+        }
+
+        let wh = match half_vector(wo, wi) {
+            Some(wh) => wh,
+            None => return float(0.0),
+        };
+
+        // the sampling routine samples wh exactly from the GTR1 distribution
+        let dr = gtr1(cos_theta_abs(wh), self.gloss);
+        dr * cos_theta_abs(wh) / (float(4.0) * wo.dot(wh))
+    }
+}
+
+/// Blends between dielectric reflectance and the Schlick approximation
+/// for metals, whose color is given by the reflectance at normal incidence.
+#[derive(Debug)]
+pub struct DisneyFresnel {
+    r0: Spectrum,
+    metallic: Float,
+    eta: Float,
+}
+
+impl DisneyFresnel {
+    pub fn new(r0: Spectrum, metallic: Float, eta: Float) -> Self {
+        Self { r0, metallic, eta }
+    }
+}
+
+impl Fresnel for DisneyFresnel {
+    fn evaluate(&self, cos_theta_i: Float) -> Spectrum {
+        Spectrum::new(dielectric(cos_theta_i, float(1.0), self.eta))
+            .lerp(fr_schlick(self.r0, cos_theta_i), self.metallic)
+    }
+}
+
+/// Trowbridge-Reitz with the masking-shadowing term of the Disney BRDF,
+/// which treats masking and shadowing as uncorrelated.
+#[derive(Copy, Clone, Debug)]
+pub struct DisneyMicrofacetDistribution {
+    distribution: TrowbridgeReitzDistribution,
+}
+
+impl DisneyMicrofacetDistribution {
+    pub fn new(alpha_x: Float, alpha_y: Float) -> Self {
+        Self {
+            distribution: TrowbridgeReitzDistribution::new(alpha_x, alpha_y, true),
+        }
+    }
+}
+
+impl MicrofacetDistribution for DisneyMicrofacetDistribution {
+    fn d(&self, wh: Vector3f) -> Float {
+        self.distribution.d(wh)
+    }
+
+    fn lambda(&self, w: Vector3f) -> Float {
+        self.distribution.lambda(w)
+    }
+
+    fn sample_wh(&self, wo: Vector3f, u: Point2f) -> Vector3f {
+        self.distribution.sample_wh(wo, u)
+    }
+
+    fn sample_visible_area(&self) -> bool {
+        self.distribution.sample_visible_area()
+    }
+
+    fn g(&self, wo: Vector3f, wi: Vector3f) -> Float {
+        self.g1(wo) * self.g1(wi)
+    }
+}
+
+fn lerp(t: Float, a: Float, b: Float) -> Float {
+    (float(1.0) - t) * a + t * b
+}
@@ -54,7 +54,9 @@ impl Fresnel for FresnelNoOp {
     }
 }
 
-fn dielectric(cos_theta_i: Float, mut eta_i: Float, mut eta_t: Float) -> Float {
+/// The fraction of unpolarized light that is reflected at the boundary between two dielectrics,
+/// where `cos_theta_i` is negative when the light comes from the `eta_t` side.
+pub(super) fn dielectric(cos_theta_i: Float, mut eta_i: Float, mut eta_t: Float) -> Float {
     let mut cos_theta_i = num::clamp(cos_theta_i, float(-1.0), float(1.0));
 
     let entering = cos_theta_i > 0.0;
@@ -64,7 +66,7 @@ fn dielectric(cos_theta_i: Float, mut eta_i: Float, mut eta_t: Float) -> Float {
     }
 
     // compute cos_theta_t using Snell's Law
-    let sin_theta_i = max(float(0.0), float(1.0) - cos_theta_i.powi(2));
+    let sin_theta_i = max(float(0.0), float(1.0) - cos_theta_i.powi(2)).sqrt();
     let sin_theta_t = eta_i / eta_t * sin_theta_i;
 
     // handle total internal reflection
@@ -72,7 +74,7 @@ fn dielectric(cos_theta_i: Float, mut eta_i: Float, mut eta_t: Float) -> Float {
         return float(1.0)
     }
 
-    let cos_theta_t = max(float(0.0), float(1.0) - sin_theta_t.powi(2));
+    let cos_theta_t = max(float(0.0), float(1.0) - sin_theta_t.powi(2)).sqrt();
 
     let r_parr = ((eta_t * cos_theta_i) - (eta_i * cos_theta_t)) /
                  ((eta_t * cos_theta_i) + (eta_i * cos_theta_t));
@@ -0,0 +1,99 @@
+use std::cmp::{ max, min };
+use cgmath::prelude::*;
+use crate::prelude::*;
+use crate::sampler::ONE_MINUS_EPSILON;
+use crate::sampling::utils::cosine_sample_hemisphere;
+use super::*;
+use super::utils::*;
+
+/// The Ashikhmin-Shirley model of a glossy coating over a diffuse base,
+/// where less light reaches the base at grazing angles.
+#[derive(Debug)]
+pub struct FresnelBlend<D: MicrofacetDistribution> {
+    rd: Spectrum,
+    rs: Spectrum,
+    distribution: D,
+}
+
+impl<D: MicrofacetDistribution> FresnelBlend<D> {
+    /// `rd` is the reflectance of the base, and `rs` the reflectance of the coating at normal incidence.
+    pub fn new(rd: Spectrum, rs: Spectrum, distribution: D) -> Self {
+        Self { rd, rs, distribution }
+    }
+
+    /// The Schlick approximation to the Fresnel reflectance of the coating.
+    fn schlick_fresnel(&self, cos_theta: Float) -> Spectrum {
+        self.rs + (Spectrum::new(1.0) - self.rs) * (float(1.0) - cos_theta).powi(5)
+    }
+}
+
+impl<D: MicrofacetDistribution> Bxdf for FresnelBlend<D> {
+    fn ty(&self) -> BxdfType {
+        BxdfType::Reflection | BxdfType::Glossy
+    }
+
+    fn f(&self, wo: Vector3f, wi: Vector3f) -> Spectrum {
+        let pow_5 = |v: Float| v.powi(5);
+
+        let diffuse = self.rd * (Spectrum::new(1.0) - self.rs) * (
+            float(28.0) / (float(23.0) * Float::pi()) *
+            (float(1.0) - pow_5(float(1.0) - float(0.5) * cos_theta_abs(wi))) *
+            (float(1.0) - pow_5(float(1.0) - float(0.5) * cos_theta_abs(wo)))
+        );
+
+        let wh = wi + wo;
+        if wh.magnitude2() == 0.0 {
+            return Spectrum::new(0.0);
+        }
+        let wh = wh.normalize();
+
+        let specular = self.schlick_fresnel(wi.dot(wh)) * (
+            self.distribution.d(wh) /
+            (float(4.0) * wi.dot(wh).abs() * max(cos_theta_abs(wi), cos_theta_abs(wo)))
+        );
+
+        diffuse + specular
+    }
+
+    fn sample_f(&self, wo: Vector3f, u: Point2f) -> Option<Sample> {
+        // choose between the diffuse and glossy terms with the first sample, and remap it
+        let wi = if u[0] < 0.5 {
+            let u = Point2f::new(min(float(2.0) * u[0], float(ONE_MINUS_EPSILON)), u[1]);
+
+            // cosine-sample the hemisphere, flipping the direction if necessary
+            let mut wi = cosine_sample_hemisphere(u);
+            if wo.z < 0.0 {
+                wi.z *= float(-1.0);
+            }
+            wi
+        } else {
+            let u = Point2f::new(min(float(2.0) * (u[0] - float(0.5)), float(ONE_MINUS_EPSILON)), u[1]);
+
+            // sample microfacet orientation wh and reflected direction wi
+            let wh = self.distribution.sample_wh(wo, u);
+            let wi = reflect(wo, wh);
+            if !same_hemisphere(wo, wi) {
+                return None;
+            }
+            wi
+        };
+
+        Some(Sample {
+            wi,
+            pdf: self.pdf(wo, wi),
+            li: self.f(wo, wi),
+            ty: Some(self.ty()),
+        })
+    }
+
+    fn pdf(&self, wo: Vector3f, wi: Vector3f) -> Float {
+        if !same_hemisphere(wo, wi) {
+            return float(0.0);
+        }
+
+        let wh = (wo + wi).normalize();
+        let pdf_wh = self.distribution.pdf(wo, wh);
+
+        float(0.5) * (cos_theta_abs(wi) * Float::frac_1_pi() + pdf_wh / (float(4.0) * wo.dot(wh)))
+    }
+}
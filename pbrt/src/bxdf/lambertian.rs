@@ -1,5 +1,7 @@
 use crate::prelude::*;
+use crate::sampling::utils::cosine_sample_hemisphere;
 use super::*;
+use super::utils::*;
 
 #[derive(Debug)]
 pub struct LambertianReflection {
@@ -27,3 +29,54 @@ impl Bxdf for LambertianReflection {
         self.r
     }
 }
+
+#[derive(Debug)]
+pub struct LambertianTransmission {
+    t: Spectrum,
+}
+
+impl LambertianTransmission {
+    pub fn new(t: Spectrum) -> Self {
+        Self {
+            t,
+        }
+    }
+}
+
+impl Bxdf for LambertianTransmission {
+    fn ty(&self) -> BxdfType {
+        BxdfType::Transmission | BxdfType::Diffuse
+    }
+
+    fn f(&self, _: Vector3f, _: Vector3f) -> Spectrum {
+        self.t * Float::frac_1_pi()
+    }
+
+    fn sample_f(&self, wo: Vector3f, u: Point2f) -> Option<Sample> {
+        let mut wi = cosine_sample_hemisphere(u);
+
+        // transmit to the other side of the surface
+        if wo.z > 0.0 {
+            wi.z *= float(-1.0);
+        }
+
+        Some(Sample {
+            wi,
+            pdf: self.pdf(wo, wi),
+            li: self.f(wo, wi),
+            ty: Some(self.ty()),
+        })
+    }
+
+    fn pdf(&self, wo: Vector3f, wi: Vector3f) -> Float {
+        if same_hemisphere(wo, wi) {
+            float(0.0)
+        } else {
+            cos_theta_abs(wi) * Float::frac_1_pi()
+        }
+    }
+
+    fn rho(&self, _: Option<Vector3f>, _: i32, _: &[Point2f]) -> Spectrum {
+        self.t
+    }
+}
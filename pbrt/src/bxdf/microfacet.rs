@@ -0,0 +1,590 @@
+use std::cmp::{ max, min };
+use std::fmt::Debug;
+use cgmath::prelude::*;
+use crate::prelude::*;
+use crate::math::*;
+use super::*;
+use super::utils::*;
+
+pub trait MicrofacetDistribution: Debug {
+    /// The differential area of microfacets with the surface normal `wh`.
+    fn d(&self, wh: Vector3f) -> Float;
+
+    /// The invisible masked microfacet area per visible microfacet area.
+    fn lambda(&self, w: Vector3f) -> Float;
+
+    /// Samples a microfacet normal, from either the full distribution
+    /// or from only the normals that are visible from `wo`.
+    fn sample_wh(&self, wo: Vector3f, u: Point2f) -> Vector3f;
+
+    fn sample_visible_area(&self) -> bool;
+
+    /// The fraction of microfacets that are visible from `w`.
+    fn g1(&self, w: Vector3f) -> Float {
+        float(1.0) / (float(1.0) + self.lambda(w))
+    }
+
+    /// The fraction of microfacets that are visible from both `wo` and `wi`.
+    fn g(&self, wo: Vector3f, wi: Vector3f) -> Float {
+        float(1.0) / (float(1.0) + self.lambda(wo) + self.lambda(wi))
+    }
+
+    fn pdf(&self, wo: Vector3f, wh: Vector3f) -> Float {
+        if self.sample_visible_area() {
+            if cos_theta(wo) == 0.0 {
+                return float(0.0);
+            }
+
+            self.d(wh) * self.g1(wo) * wo.dot(wh).abs() / cos_theta_abs(wo)
+        } else {
+            self.d(wh) * cos_theta_abs(wh)
+        }
+    }
+}
+
+/// Maps a user-friendly roughness in [0, 1] to the alpha parameter of the distributions.
+#[cfg_attr(feature = "cargo-clippy", allow(unreadable_literal))]
+pub fn roughness_to_alpha(roughness: Float) -> Float {
+    let roughness = max(roughness, float(1e-3));
+    let x = roughness.ln();
+
+    float(1.62142) +
+        float(0.819955) * x +
+        float(0.1734) * x.powi(2) +
+        float(0.0171201) * x.powi(3) +
+        float(0.000640711) * x.powi(4)
+}
+
+/// `tan^2(theta)` that is infinite rather than undefined at grazing angles.
+fn tan_2_theta_safe(w: Vector3f) -> Float {
+    if cos_2_theta(w) == 0.0 {
+        Float::infinity()
+    } else {
+        tan_2_theta(w)
+    }
+}
+
+/// Samples `phi` for an anisotropic distribution,
+/// returning it with the `cos_2_phi / alpha_x^2 + sin_2_phi / alpha_y^2` term.
+fn sample_anisotropic_phi(alpha_x: Float, alpha_y: Float, u: Float) -> (Float, Float) {
+    let mut phi = (alpha_y / alpha_x * (float(2.0) * Float::pi() * u + Float::frac_pi_2()).tan()).atan();
+    if u > 0.5 {
+        phi += Float::pi();
+    }
+
+    let term = phi.cos().powi(2) / alpha_x.powi(2) + phi.sin().powi(2) / alpha_y.powi(2);
+
+    (phi, term)
+}
+
+/// Samples a visible normal by stretching `wi` so that the distribution is isotropic with `alpha = 1`,
+/// sampling the slopes with `sample_11` and unstretching them.
+fn sample_visible(wi: Vector3f, alpha_x: Float, alpha_y: Float, u: Point2f, sample_11: fn(Float, Float, Float) -> (Float, Float)) -> Vector3f {
+    // stretch wi
+    let wi_stretched = Vector3f::new(alpha_x * wi.x, alpha_y * wi.y, wi.z).normalize();
+
+    // simulate P22_{wi}(x_slope, y_slope, 1, 1)
+    let (slope_x, slope_y) = sample_11(cos_theta(wi_stretched), u[0], u[1]);
+
+    // rotate
+    let cos_phi = cos_phi(wi_stretched);
+    let sin_phi = sin_phi(wi_stretched);
+    let (slope_x, slope_y) = (
+        cos_phi * slope_x - sin_phi * slope_y,
+        sin_phi * slope_x + cos_phi * slope_y,
+    );
+
+    // unstretch
+    let slope_x = alpha_x * slope_x;
+    let slope_y = alpha_y * slope_y;
+
+    Vector3f::new(-slope_x, -slope_y, float(1.0)).normalize()
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct BeckmannDistribution {
+    alpha_x: Float,
+    alpha_y: Float,
+    sample_visible_area: bool,
+}
+
+impl BeckmannDistribution {
+    pub fn new(alpha_x: Float, alpha_y: Float, sample_visible_area: bool) -> Self {
+        Self { alpha_x, alpha_y, sample_visible_area }
+    }
+
+    pub fn roughness_to_alpha(roughness: Float) -> Float {
+        roughness_to_alpha(roughness)
+    }
+}
+
+#[cfg_attr(feature = "cargo-clippy", allow(unreadable_literal))]
+fn beckmann_sample_11(cos_theta_i: Float, u1: Float, u2: Float) -> (Float, Float) {
+    // special case (normal incidence)
+    if cos_theta_i > 0.9999 {
+        let r = (-(float(1.0) - u1).ln()).sqrt();
+        let phi = float(2.0) * Float::pi() * u2;
+        return (r * phi.cos(), r * phi.sin());
+    }
+
+    // the original inversion routine from the paper contained discontinuities,
+    // this uses a fit of the CDF and newton iterations instead
+    let sin_theta_i = max(float(0.0), float(1.0) - cos_theta_i.powi(2)).sqrt();
+    let tan_theta_i = sin_theta_i / cos_theta_i;
+    let cot_theta_i = float(1.0) / tan_theta_i;
+
+    // search interval -- everything is parameterized in the erf() domain
+    let mut a = float(-1.0);
+    let mut c = erf(cot_theta_i);
+    let sample_x = max(u1, float(1e-6));
+
+    // start with a good initial guess
+    let theta_i = cos_theta_i.acos();
+    let fit = float(1.0) + theta_i * (float(-0.876) + theta_i * (float(0.4265) - float(0.0594) * theta_i));
+    let mut b = c - (float(1.0) + c) * (float(1.0) - sample_x).powf(fit);
+
+    // normalization factor for the CDF
+    let sqrt_pi_inv = float(1.0) / Float::pi().sqrt();
+    let normalization = float(1.0) /
+        (float(1.0) + c + sqrt_pi_inv * tan_theta_i * (-cot_theta_i * cot_theta_i).exp());
+
+    for _ in 1..10 {
+        // bisection criterion -- the oddly-looking boolean expression
+        // is intentional to check for NaNs at little additional cost
+        if !(b >= a && b <= c) {
+            b = float(0.5) * (a + c);
+        }
+
+        // evaluate the CDF and its derivative
+        let inv_erf = erf_inv(b);
+        let value = normalization * (float(1.0) + b + sqrt_pi_inv * tan_theta_i * (-inv_erf * inv_erf).exp()) - sample_x;
+        let derivative = normalization * (float(1.0) - inv_erf * tan_theta_i);
+
+        if value.abs() < 1e-5 {
+            break;
+        }
+
+        // update bisection intervals
+        if value > 0.0 {
+            c = b;
+        } else {
+            a = b;
+        }
+
+        b -= value / derivative;
+    }
+
+    // now convert back into a slope value
+    let slope_x = erf_inv(b);
+
+    // simulate Y component
+    let slope_y = erf_inv(float(2.0) * max(u2, float(1e-6)) - float(1.0));
+
+    (slope_x, slope_y)
+}
+
+impl MicrofacetDistribution for BeckmannDistribution {
+    fn d(&self, wh: Vector3f) -> Float {
+        let tan_2_theta = tan_2_theta_safe(wh);
+        if tan_2_theta.is_infinite() {
+            return float(0.0);
+        }
+
+        let cos_4_theta = cos_2_theta(wh).powi(2);
+
+        (-tan_2_theta * (cos_2_phi(wh) / self.alpha_x.powi(2) + sin_2_phi(wh) / self.alpha_y.powi(2))).exp() /
+            (Float::pi() * self.alpha_x * self.alpha_y * cos_4_theta)
+    }
+
+    fn lambda(&self, w: Vector3f) -> Float {
+        let tan_2_theta = tan_2_theta_safe(w);
+        if tan_2_theta.is_infinite() {
+            return float(0.0);
+        }
+
+        // compute alpha for direction w
+        let alpha = (cos_2_phi(w) * self.alpha_x.powi(2) + sin_2_phi(w) * self.alpha_y.powi(2)).sqrt();
+        if tan_2_theta == 0.0 {
+            return float(0.0);
+        }
+
+        let a = float(1.0) / (alpha * tan_2_theta.sqrt());
+        if a >= 1.6 {
+            return float(0.0);
+        }
+
+        (float(1.0) - float(1.259) * a + float(0.396) * a * a) /
+            (float(3.535) * a + float(2.181) * a * a)
+    }
+
+    fn sample_wh(&self, wo: Vector3f, u: Point2f) -> Vector3f {
+        if !self.sample_visible_area {
+            // sample full distribution of normals
+            let log_sample = (float(1.0) - u[0]).ln();
+
+            let (phi, tan_2_theta) = if self.alpha_x == self.alpha_y {
+                (u[1] * float(2.0) * Float::pi(), -self.alpha_x.powi(2) * log_sample)
+            } else {
+                let (phi, term) = sample_anisotropic_phi(self.alpha_x, self.alpha_y, u[1]);
+                (phi, -log_sample / term)
+            };
+
+            // map sampled angles to normal direction
+            let cos_theta = float(1.0) / (float(1.0) + tan_2_theta).sqrt();
+            let sin_theta = max(float(0.0), float(1.0) - cos_theta.powi(2)).sqrt();
+            let wh = spherical_direction(sin_theta, cos_theta, phi);
+
+            if same_hemisphere(wo, wh) { wh } else { -wh }
+        } else {
+            // sample visible area of normals
+            let flip = wo.z < 0.0;
+            let wo = if flip { -wo } else { wo };
+            let wh = sample_visible(wo, self.alpha_x, self.alpha_y, u, beckmann_sample_11);
+
+            if flip { -wh } else { wh }
+        }
+    }
+
+    fn sample_visible_area(&self) -> bool {
+        self.sample_visible_area
+    }
+}
+
+/// Also known as GGX.
+#[derive(Copy, Clone, Debug)]
+pub struct TrowbridgeReitzDistribution {
+    alpha_x: Float,
+    alpha_y: Float,
+    sample_visible_area: bool,
+}
+
+impl TrowbridgeReitzDistribution {
+    pub fn new(alpha_x: Float, alpha_y: Float, sample_visible_area: bool) -> Self {
+        Self { alpha_x, alpha_y, sample_visible_area }
+    }
+
+    pub fn roughness_to_alpha(roughness: Float) -> Float {
+        roughness_to_alpha(roughness)
+    }
+}
+
+#[cfg_attr(feature = "cargo-clippy", allow(unreadable_literal))]
+fn trowbridge_reitz_sample_11(cos_theta: Float, u1: Float, u2: Float) -> (Float, Float) {
+    // special case (normal incidence)
+    if cos_theta > 0.9999 {
+        let r = (u1 / (float(1.0) - u1)).sqrt();
+        let phi = float(2.0) * Float::pi() * u2;
+        return (r * phi.cos(), r * phi.sin());
+    }
+
+    let sin_theta = max(float(0.0), float(1.0) - cos_theta.powi(2)).sqrt();
+    let tan_theta = sin_theta / cos_theta;
+    let cot_theta = float(1.0) / tan_theta;
+    let g1 = float(2.0) / (float(1.0) + (float(1.0) + float(1.0) / (cot_theta * cot_theta)).sqrt());
+
+    // sample slope_x
+    let a = float(2.0) * u1 / g1 - float(1.0);
+    let tmp = min(float(1.0) / (a * a - float(1.0)), float(1e10));
+    let b = tan_theta;
+    let d = max(b * b * tmp * tmp - (a * a - b * b) * tmp, float(0.0)).sqrt();
+    let slope_x_1 = b * tmp - d;
+    let slope_x_2 = b * tmp + d;
+    let slope_x = if a < 0.0 || slope_x_2 > float(1.0) / tan_theta {
+        slope_x_1
+    } else {
+        slope_x_2
+    };
+
+    // sample slope_y
+    let (s, u2) = if u2 > 0.5 {
+        (float(1.0), float(2.0) * (u2 - float(0.5)))
+    } else {
+        (float(-1.0), float(2.0) * (float(0.5) - u2))
+    };
+
+    let z = (u2 * (u2 * (u2 * float(0.27385) - float(0.73369)) + float(0.46341))) /
+        (u2 * (u2 * (u2 * float(0.093073) + float(0.309420)) - float(1.000000)) + float(0.597999));
+    let slope_y = s * z * (float(1.0) + slope_x * slope_x).sqrt();
+
+    (slope_x, slope_y)
+}
+
+impl MicrofacetDistribution for TrowbridgeReitzDistribution {
+    fn d(&self, wh: Vector3f) -> Float {
+        let tan_2_theta = tan_2_theta_safe(wh);
+        if tan_2_theta.is_infinite() {
+            return float(0.0);
+        }
+
+        let cos_4_theta = cos_2_theta(wh).powi(2);
+        let e = (cos_2_phi(wh) / self.alpha_x.powi(2) + sin_2_phi(wh) / self.alpha_y.powi(2)) * tan_2_theta;
+
+        float(1.0) / (Float::pi() * self.alpha_x * self.alpha_y * cos_4_theta * (float(1.0) + e).powi(2))
+    }
+
+    fn lambda(&self, w: Vector3f) -> Float {
+        let tan_2_theta = tan_2_theta_safe(w);
+        if tan_2_theta.is_infinite() {
+            return float(0.0);
+        }
+
+        // compute alpha for direction w
+        let alpha_2 = cos_2_phi(w) * self.alpha_x.powi(2) + sin_2_phi(w) * self.alpha_y.powi(2);
+        let alpha_2_tan_2_theta = alpha_2 * tan_2_theta;
+
+        (float(-1.0) + (float(1.0) + alpha_2_tan_2_theta).sqrt()) / float(2.0)
+    }
+
+    fn sample_wh(&self, wo: Vector3f, u: Point2f) -> Vector3f {
+        if !self.sample_visible_area {
+            let (phi, tan_2_theta) = if self.alpha_x == self.alpha_y {
+                let tan_2_theta = self.alpha_x.powi(2) * u[0] / (float(1.0) - u[0]);
+                (float(2.0) * Float::pi() * u[1], tan_2_theta)
+            } else {
+                let (phi, term) = sample_anisotropic_phi(self.alpha_x, self.alpha_y, u[1]);
+                let alpha_2 = float(1.0) / term;
+                (phi, alpha_2 * u[0] / (float(1.0) - u[0]))
+            };
+
+            let cos_theta = float(1.0) / (float(1.0) + tan_2_theta).sqrt();
+            let sin_theta = max(float(0.0), float(1.0) - cos_theta.powi(2)).sqrt();
+            let wh = spherical_direction(sin_theta, cos_theta, phi);
+
+            if same_hemisphere(wo, wh) { wh } else { -wh }
+        } else {
+            let flip = wo.z < 0.0;
+            let wo = if flip { -wo } else { wo };
+            let wh = sample_visible(wo, self.alpha_x, self.alpha_y, u, trowbridge_reitz_sample_11);
+
+            if flip { -wh } else { wh }
+        }
+    }
+
+    fn sample_visible_area(&self) -> bool {
+        self.sample_visible_area
+    }
+}
+
+#[derive(Debug)]
+pub struct MicrofacetReflection<D: MicrofacetDistribution, F: Fresnel> {
+    r: Spectrum,
+    distribution: D,
+    fresnel: F,
+}
+
+impl<D: MicrofacetDistribution, F: Fresnel> MicrofacetReflection<D, F> {
+    pub fn new(r: Spectrum, distribution: D, fresnel: F) -> Self {
+        Self { r, distribution, fresnel }
+    }
+}
+
+impl<D: MicrofacetDistribution, F: Fresnel> Bxdf for MicrofacetReflection<D, F> {
+    fn ty(&self) -> BxdfType {
+        BxdfType::Reflection | BxdfType::Glossy
+    }
+
+    fn f(&self, wo: Vector3f, wi: Vector3f) -> Spectrum {
+        let cos_theta_o = cos_theta_abs(wo);
+        let cos_theta_i = cos_theta_abs(wi);
+        let wh = wi + wo;
+
+        // handle degenerate cases for microfacet reflection
+        if cos_theta_i == 0.0 || cos_theta_o == 0.0 {
+            return Spectrum::new(0.0);
+        }
+
+        if wh.magnitude2() == 0.0 {
+            return Spectrum::new(0.0);
+        }
+
+        let wh = wh.normalize();
+        // wo and wi can both be below the surface, the Fresnel term needs wh on the outside
+        let wh_outside = Normal::from(wh).face_forward(Normal::new(0.0, 0.0, 1.0));
+        let f = self.fresnel.evaluate(wi.dot(*wh_outside));
+
+        self.r * self.distribution.d(wh) * self.distribution.g(wo, wi) * f /
+            (float(4.0) * cos_theta_i * cos_theta_o)
+    }
+
+    fn sample_f(&self, wo: Vector3f, u: Point2f) -> Option<Sample> {
+        // sample microfacet orientation wh and reflected direction wi
+        if wo.z == 0.0 {
+            return None;
+        }
+
+        let wh = self.distribution.sample_wh(wo, u);
+        if wo.dot(wh) <= 0.0 {
+            return None;
+        }
+
+        let wi = reflect(wo, wh);
+        if !same_hemisphere(wo, wi) {
+            return None;
+        }
+
+        // compute pdf of wi for microfacet reflection
+        let pdf = self.distribution.pdf(wo, wh) / (float(4.0) * wo.dot(wh));
+
+        Some(Sample {
+            wi,
+            pdf,
+            li: self.f(wo, wi),
+            ty: Some(self.ty()),
+        })
+    }
+
+    fn pdf(&self, wo: Vector3f, wi: Vector3f) -> Float {
+        if !same_hemisphere(wo, wi) {
+            return float(0.0);
+        }
+
+        let wh = wo + wi;
+        if wh.magnitude2() == 0.0 {
+            return float(0.0);
+        }
+
+        let wh = wh.normalize();
+        if wo.dot(wh) == 0.0 {
+            return float(0.0);
+        }
+
+        self.distribution.pdf(wo, wh) / (float(4.0) * wo.dot(wh))
+    }
+}
+
+#[derive(Debug)]
+pub struct MicrofacetTransmission<D: MicrofacetDistribution> {
+    t: Spectrum,
+    distribution: D,
+    eta_a: Float,
+    eta_b: Float,
+    fresnel: FresnelDielectric,
+    mode: TransportMode,
+}
+
+impl<D: MicrofacetDistribution> MicrofacetTransmission<D> {
+    pub fn new(t: Spectrum, distribution: D, eta_a: Float, eta_b: Float, mode: TransportMode) -> Self {
+        Self {
+            t,
+            distribution,
+            eta_a,
+            eta_b,
+            fresnel: FresnelDielectric::new(eta_a, eta_b),
+            mode,
+        }
+    }
+
+    /// The relative index of refraction across the boundary, and the half vector for `wo` and `wi`.
+    fn half_vector(&self, wo: Vector3f, wi: Vector3f) -> Option<(Float, Vector3f)> {
+        let eta = if cos_theta(wo) > 0.0 {
+            self.eta_b / self.eta_a
+        } else {
+            self.eta_a / self.eta_b
+        };
+
+        let wh = wo + wi * eta;
+        if wh.magnitude2() == 0.0 {
+            return None;
+        }
+
+        let wh = wh.normalize();
+        let wh = if wh.z < 0.0 { -wh } else { wh };
+
+        // both directions need to be on different sides of the microfacet
+        if wo.dot(wh) * wi.dot(wh) > 0.0 {
+            return None;
+        }
+
+        Some((eta, wh))
+    }
+}
+
+impl<D: MicrofacetDistribution> Bxdf for MicrofacetTransmission<D> {
+    fn ty(&self) -> BxdfType {
+        BxdfType::Transmission | BxdfType::Glossy
+    }
+
+    fn f(&self, wo: Vector3f, wi: Vector3f) -> Spectrum {
+        if same_hemisphere(wo, wi) {
+            return Spectrum::new(0.0);
+        }
+
+        let cos_theta_o = cos_theta(wo);
+        let cos_theta_i = cos_theta(wi);
+        if cos_theta_i == 0.0 || cos_theta_o == 0.0 {
+            return Spectrum::new(0.0);
+        }
+
+        let (eta, wh) = match self.half_vector(wo, wi) {
+            Some(half) => half,
+            None => return Spectrum::new(0.0),
+        };
+
+        let f = self.fresnel.evaluate(wo.dot(wh));
+
+        let sqrt_denom = wo.dot(wh) + eta * wi.dot(wh);
+        if sqrt_denom == 0.0 {
+            return Spectrum::new(0.0);
+        }
+
+        // account for non-symmetry with transmission to different medium
+        let factor = match self.mode {
+            TransportMode::Radiance => float(1.0) / eta,
+            TransportMode::Importance => float(1.0),
+        };
+
+        (Spectrum::new(1.0) - f) * self.t * (
+            self.distribution.d(wh) * self.distribution.g(wo, wi) * eta * eta *
+            wi.dot(wh).abs() * wo.dot(wh).abs() * factor * factor /
+            (cos_theta_i * cos_theta_o * sqrt_denom * sqrt_denom)
+        ).abs()
+    }
+
+    fn sample_f(&self, wo: Vector3f, u: Point2f) -> Option<Sample> {
+        if wo.z == 0.0 {
+            return None;
+        }
+
+        let wh = self.distribution.sample_wh(wo, u);
+        if wo.dot(wh) <= 0.0 {
+            return None;
+        }
+
+        let eta = if cos_theta(wo) > 0.0 {
+            self.eta_a / self.eta_b
+        } else {
+            self.eta_b / self.eta_a
+        };
+
+        let wi = refract(wo, Normal::from(wh), eta)?;
+        let pdf = self.pdf(wo, wi);
+
+        Some(Sample {
+            wi,
+            pdf,
+            li: self.f(wo, wi),
+            ty: Some(self.ty()),
+        })
+    }
+
+    fn pdf(&self, wo: Vector3f, wi: Vector3f) -> Float {
+        if same_hemisphere(wo, wi) {
+            return float(0.0);
+        }
+
+        let (eta, wh) = match self.half_vector(wo, wi) {
+            Some(half) => half,
+            None => return float(0.0),
+        };
+
+        // compute change of variables for microfacet transmission
+        let sqrt_denom = wo.dot(wh) + eta * wi.dot(wh);
+        if sqrt_denom == 0.0 {
+            return float(0.0);
+        }
+
+        let dwh_dwi = (eta * eta * wi.dot(wh) / (sqrt_denom * sqrt_denom)).abs();
+
+        self.distribution.pdf(wo, wh) * dwh_dwi
+    }
+}
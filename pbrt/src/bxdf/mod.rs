@@ -8,12 +8,24 @@ use crate::sampling::utils::*;
 mod bsdf;
 pub use self::bsdf::*;
 
+mod disney;
+pub use self::disney::*;
+
 mod fresnel;
 pub use self::fresnel::*;
 
+mod fresnel_blend;
+pub use self::fresnel_blend::*;
+
 mod lambertian;
 pub use self::lambertian::*;
 
+mod microfacet;
+pub use self::microfacet::*;
+
+mod oren_nayar;
+pub use self::oren_nayar::*;
+
 mod specular;
 pub use self::specular::*;
 
@@ -70,7 +82,41 @@ pub trait Bxdf: Debug {
         }
     }
 
-    fn rho(&self, wo: Option<Vector3f>, n_samples: i32, samples: &[Point2f]) -> Spectrum;
+    /// Estimates the reflectance with `n_samples` samples.
+    ///
+    /// With `wo` this is the hemispherical-directional reflectance, which uses a sample
+    /// from `samples` for each `wi`. Without it this is the hemispherical-hemispherical
+    /// reflectance, which uses a pair of samples for each `wo` and `wi`.
+    fn rho(&self, wo: Option<Vector3f>, n_samples: i32, samples: &[Point2f]) -> Spectrum {
+        let mut r = Spectrum::new(0.0);
+
+        match wo {
+            Some(wo) => {
+                for &u in samples.iter().take(n_samples as usize) {
+                    if let Some(sample) = self.sample_f(wo, u) {
+                        if sample.pdf > 0.0 {
+                            r += sample.li * cos_theta_abs(sample.wi) / sample.pdf;
+                        }
+                    }
+                }
+
+                r / float(n_samples)
+            },
+            None => {
+                for u in samples.chunks(2).take(n_samples as usize).filter(|u| u.len() == 2) {
+                    let wo = uniform_sample_hemisphere(u[0]);
+                    if let Some(sample) = self.sample_f(wo, u[1]) {
+                        if sample.pdf > 0.0 {
+                            r += sample.li * cos_theta_abs(sample.wi) * cos_theta_abs(wo) /
+                                (uniform_hemisphere_pdf() * sample.pdf);
+                        }
+                    }
+                }
+
+                r / (Float::pi() * float(n_samples))
+            },
+        }
+    }
 }
 
 #[derive(Debug)]
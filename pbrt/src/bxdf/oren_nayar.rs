@@ -0,0 +1,55 @@
+use std::cmp::max;
+use crate::prelude::*;
+use super::*;
+use super::utils::*;
+
+/// Diffuse reflection from a rough surface, modelled as V-shaped microfacets
+/// with a gaussian distribution of orientations.
+#[derive(Debug)]
+pub struct OrenNayar {
+    r: Spectrum,
+    a: Float,
+    b: Float,
+}
+
+impl OrenNayar {
+    /// `sigma` is the standard deviation of the microfacet angles, in degrees.
+    pub fn new(r: Spectrum, sigma: Float) -> Self {
+        let sigma = sigma.to_radians();
+        let sigma_2 = sigma * sigma;
+
+        Self {
+            r,
+            a: float(1.0) - sigma_2 / (float(2.0) * (sigma_2 + float(0.33))),
+            b: float(0.45) * sigma_2 / (sigma_2 + float(0.09)),
+        }
+    }
+}
+
+impl Bxdf for OrenNayar {
+    fn ty(&self) -> BxdfType {
+        BxdfType::Reflection | BxdfType::Diffuse
+    }
+
+    fn f(&self, wo: Vector3f, wi: Vector3f) -> Spectrum {
+        let sin_theta_i = sin_theta(wi);
+        let sin_theta_o = sin_theta(wo);
+
+        // compute cosine term of the Oren-Nayar model
+        let max_cos = if sin_theta_i > 1e-4 && sin_theta_o > 1e-4 {
+            let d_cos = cos_phi(wi) * cos_phi(wo) + sin_phi(wi) * sin_phi(wo);
+            max(float(0.0), d_cos)
+        } else {
+            float(0.0)
+        };
+
+        // compute sine and tangent terms of the Oren-Nayar model
+        let (sin_alpha, tan_beta) = if cos_theta_abs(wi) > cos_theta_abs(wo) {
+            (sin_theta_o, sin_theta_i / cos_theta_abs(wi))
+        } else {
+            (sin_theta_i, sin_theta_o / cos_theta_abs(wo))
+        };
+
+        self.r * Float::frac_1_pi() * (self.a + self.b * max_cos * sin_alpha * tan_beta)
+    }
+}
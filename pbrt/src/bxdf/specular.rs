@@ -1,14 +1,21 @@
 use crate::prelude::*;
 use super::*;
+use super::fresnel::dielectric;
 use super::utils::*;
 
 #[derive(Debug)]
-pub struct SpecularReflection {
-    pub r: Spectrum,
-    pub fresnel: Box<dyn Fresnel>,
+pub struct SpecularReflection<F: Fresnel> {
+    r: Spectrum,
+    fresnel: F,
+}
+
+impl<F: Fresnel> SpecularReflection<F> {
+    pub fn new(r: Spectrum, fresnel: F) -> Self {
+        Self { r, fresnel }
+    }
 }
 
-impl Bxdf for SpecularReflection {
+impl<F: Fresnel> Bxdf for SpecularReflection<F> {
     fn ty(&self) -> BxdfType {
         BxdfType::Reflection | BxdfType::Specular
     }
@@ -28,8 +35,9 @@ impl Bxdf for SpecularReflection {
         })
     }
 
-    fn rho(&self, _wo: Option<Vector3f>, _n_samples: i32, _samples: &[Point2f]) -> Spectrum {
-        unimplemented!()
+    /// There's no chance of another sampling strategy choosing the one reflected direction.
+    fn pdf(&self, _: Vector3f, _: Vector3f) -> Float {
+        float(0.0)
     }
 }
 
@@ -77,23 +85,28 @@ impl Bxdf for SpecularTransmission {
             return None;
         };
 
-        let li = self.t * (Spectrum::new(1.0) - self.fresnel.evaluate(cos_theta(wi)));
+        let mut li = self.t * (Spectrum::new(1.0) - self.fresnel.evaluate(cos_theta(wi)));
 
         // account for non-symmetry w transmission to different medium
+        if self.transport_mode == TransportMode::Radiance {
+            li *= (eta_i * eta_i) / (eta_t * eta_t);
+        }
 
         Some(Sample {
             wi,
             pdf: float(1.0),
-            li,
+            li: li / cos_theta_abs(wi),
             ty: Some(self.ty()),
         })
     }
 
-    fn rho(&self, _wo: Option<Vector3f>, _n_samples: i32, _samples: &[Point2f]) -> Spectrum {
-        unimplemented!()
+    fn pdf(&self, _: Vector3f, _: Vector3f) -> Float {
+        float(0.0)
     }
 }
 
+/// Specular reflection and transmission for a dielectric, choosing between them
+/// in proportion to the Fresnel reflectance.
 #[derive(Debug)]
 pub struct SpecularFresnel {
     r: Spectrum,
@@ -101,7 +114,6 @@ pub struct SpecularFresnel {
     eta_a: Float,
     eta_b: Float,
     mode: TransportMode,
-    fresnel: FresnelDielectric,
 }
 
 impl SpecularFresnel {
@@ -112,7 +124,6 @@ impl SpecularFresnel {
             eta_a,
             eta_b,
             mode,
-            fresnel: FresnelDielectric::new(eta_a, eta_b),
         }
     }
 }
@@ -126,11 +137,46 @@ impl Bxdf for SpecularFresnel {
         Spectrum::new(0.0)
     }
 
-    fn sample_f(&self, _wo: Vector3f, _samples: Point2f) -> Option<Sample> {
-        unimplemented!()
+    fn sample_f(&self, wo: Vector3f, u: Point2f) -> Option<Sample> {
+        let f = dielectric(cos_theta(wo), self.eta_a, self.eta_b);
+
+        if u[0] < f {
+            // compute specular reflection
+            let wi = Vector3f::new(-wo.x, -wo.y, wo.z);
+
+            Some(Sample {
+                wi,
+                pdf: f,
+                li: self.r * f / cos_theta_abs(wi),
+                ty: Some(BxdfType::Reflection | BxdfType::Specular),
+            })
+        } else {
+            // compute specular transmission
+            let (eta_i, eta_t) = if cos_theta(wo) > 0.0 {
+                (self.eta_a, self.eta_b)
+            } else {
+                (self.eta_b, self.eta_a)
+            };
+
+            let wi = refract(wo, Normal::new(0.0, 0.0, 1.0).face_forward(wo), eta_i / eta_t)?;
+
+            let mut li = self.t * (float(1.0) - f);
+
+            // account for non-symmetry w transmission to different medium
+            if self.mode == TransportMode::Radiance {
+                li *= (eta_i * eta_i) / (eta_t * eta_t);
+            }
+
+            Some(Sample {
+                wi,
+                pdf: float(1.0) - f,
+                li: li / cos_theta_abs(wi),
+                ty: Some(BxdfType::Transmission | BxdfType::Specular),
+            })
+        }
     }
 
-    fn rho(&self, _wo: Option<Vector3f>, _n_samples: i32, _samples: &[Point2f]) -> Spectrum {
-        unimplemented!()
+    fn pdf(&self, _: Vector3f, _: Vector3f) -> Float {
+        float(0.0)
     }
 }
@@ -0,0 +1,74 @@
+use std::cmp::min;
+use std::sync::Arc;
+use cgmath::Rad;
+use cgmath::prelude::*;
+use crate::prelude::*;
+use crate::sampling::Distribution2d;
+use crate::sampling::utils::{ concentric_sample_disk, uniform_sample_triangle };
+
+/// The shape of a thin lens's opening, which gives the shape of out of focus highlights.
+#[derive(Clone)]
+pub enum Aperture {
+    Circular,
+    /// A regular polygon, like the opening made by a camera's iris blades.
+    Blades { blades: u32, rotation: Rad<Float> },
+    /// An image mask, where brighter pixels let through more light.
+    /// The image is stretched over the square around the lens.
+    Image(Arc<Distribution2d>),
+}
+
+impl Aperture {
+    pub fn blades(blades: u32, rotation: impl Into<Rad<Float>>) -> Self {
+        assert!(blades >= 3, "an aperture needs at least 3 blades");
+        Aperture::Blades { blades, rotation: rotation.into() }
+    }
+
+    /// `luminance` has `width * height` values, in scanline order starting at the top.
+    pub fn image(luminance: &[Float], width: usize, height: usize) -> Self {
+        Aperture::Image(Arc::new(Distribution2d::new(luminance, width, height)))
+    }
+
+    /// Maps a uniform sample to a point on the aperture, which fits inside the unit disk
+    /// (or the square around it, for image apertures).
+    pub fn sample(&self, u: Point2f) -> Point2f {
+        match self {
+            Aperture::Circular => concentric_sample_disk(u),
+            Aperture::Blades { blades, rotation } => {
+                // pick one of the triangles that fan out from the center,
+                // and reuse `u.x` to sample it
+                let n = float(*blades as f32);
+                let i = min((u.x * n).raw() as u32, blades - 1);
+                let u = Point2f::new(u.x * n - float(i as f32), u.y);
+
+                let vertex = |i: u32| {
+                    let theta = rotation.0 + float(2.0) * Float::pi() * float(i as f32) / n;
+                    Vector2f::new(theta.cos(), theta.sin())
+                };
+
+                let b = uniform_sample_triangle(u);
+                Point2f::from_vec(vertex(i) * b.x + vertex(i + 1) * b.y)
+            },
+            Aperture::Image(distribution) => {
+                let p = distribution.sample_continuous(u).value;
+                // image rows go from the top down
+                Point2f::new(float(2.0) * p.x - float(1.0), float(1.0) - float(2.0) * p.y)
+            },
+        }
+    }
+}
+
+impl Default for Aperture {
+    fn default() -> Self {
+        Aperture::Circular
+    }
+}
+
+/// Moves a ray that leaves the center of a thin lens so that it leaves from `p_lens` instead,
+/// while still passing through the same point on the plane of focus.
+pub(super) fn thin_lens_ray(ray: RayData, p_lens: Point2f, focal_distance: Float) -> RayData {
+    let ft = focal_distance / ray.direction.z.abs();
+    let p_focus = ray.position(ft);
+
+    let origin = Point3f::new(p_lens.x, p_lens.y, float(0.0));
+    RayData::new(origin, (p_focus - origin).normalize())
+}
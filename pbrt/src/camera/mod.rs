@@ -5,6 +5,9 @@ use crate::sampler::CameraSample;
 
 #[macro_use] mod macros;
 
+mod aperture;
+pub use self::aperture::Aperture;
+
 mod orthographic;
 pub use self::orthographic::OrthographicCamera;
 
@@ -1,6 +1,8 @@
 use crate::prelude::*;
 use crate::math::Transform;
+use crate::medium::MediumId;
 #[macro_use] use super::*;
+use super::aperture::thin_lens_ray;
 
 #[allow(dead_code)]
 pub struct OrthographicCamera {
@@ -15,8 +17,9 @@ pub struct OrthographicCamera {
     shutter_close: Float,
     lens_radius: Float,
     focal_distance: Float,
+    aperture: Aperture,
     film: Arc<Mutex<Film>>,
-    medium: Option<()>,
+    medium: Option<MediumId>,
 }
 
 impl OrthographicCamera {
@@ -28,8 +31,9 @@ impl OrthographicCamera {
         shutter_close: Float,
         lens_radius: Float,
         focal_distance: Float,
+        aperture: Aperture,
         film: Arc<Mutex<Film>>,
-        medium: Option<()>,
+        medium: Option<MediumId>,
     ) -> Self {
         let camera_to_screen = Transform::identity();
 
@@ -54,6 +58,7 @@ impl OrthographicCamera {
             shutter_close,
             lens_radius,
             focal_distance,
+            aperture,
             film,
             medium,
         }
@@ -77,12 +82,19 @@ impl Camera for OrthographicCamera {
 
         let mut ray = RayDifferential::from_ray(ray);
 
+        ray.x = Some(RayData::new(ray.origin + self.camera_dx, ray.direction));
+        ray.y = Some(RayData::new(ray.origin + self.camera_dy, ray.direction));
+
+        // modify ray for depth of field
         if self.lens_radius > 0.0 {
-            // todo: modify ray for DoF
-            unimplemented!()
-        } else {
-            ray.x = Some(RayData::new(ray.origin + self.camera_dx, ray.direction));
-            ray.y = Some(RayData::new(ray.origin + self.camera_dy, ray.direction));
+            let p_lens = self.aperture.sample(camera_sample.lens) * self.lens_radius;
+
+            let lens_ray = thin_lens_ray(RayData::new(ray.origin, ray.direction), p_lens, self.focal_distance);
+            ray.origin = lens_ray.origin;
+            ray.direction = lens_ray.direction;
+
+            ray.x = ray.x.map(|rx| thin_lens_ray(rx, p_lens, self.focal_distance));
+            ray.y = ray.y.map(|ry| thin_lens_ray(ry, p_lens, self.focal_distance));
         }
 
         let ray = self.camera_to_world.transform_ray_differential(ray.time, ray);
@@ -4,7 +4,9 @@ use cgmath::Rad;
 use cgmath::prelude::*;
 use crate::prelude::*;
 use crate::math::Transform;
+use crate::medium::MediumId;
 #[macro_use] use super::*;
+use super::aperture::thin_lens_ray;
 
 #[allow(dead_code)]
 pub struct PerspectiveCamera {
@@ -19,12 +21,15 @@ pub struct PerspectiveCamera {
     shutter_close: Float,
     lens_radius: Float,
     focal_distance: Float,
+    aperture: Aperture,
     fov: Rad<Float>,
     film: Arc<Mutex<Film>>,
-    medium: Option<()>,
+    medium: Option<MediumId>,
 }
 
 impl PerspectiveCamera {
+    /// `fov` spans `[-1, 1]` of `screen_window` along both axes, as in pbrt,
+    /// so the aspect ratio only comes from the screen window.
     #[cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
     pub fn new(
         camera_to_world: AnimatedTransform,
@@ -33,18 +38,14 @@ impl PerspectiveCamera {
         shutter_close: Float,
         lens_radius: Float,
         focal_distance: Float,
+        aperture: Aperture,
         fov: impl Into<Rad<Float>> + Copy,
         film: Arc<Mutex<Film>>,
-        medium: Option<()>,
+        medium: Option<MediumId>,
     ) -> Self {
-        let full_resolution = {
-            let film = film.lock().unwrap();
-            film.full_resolution
-        };
-
         let camera_to_screen = cgmath::perspective(
             fov,
-            float(full_resolution.x as f32) / float(full_resolution.y as f32),
+            float(1.0),
             float(5e-3),
             float(1000.0));
         let camera_to_screen = Transform::new(camera_to_screen);
@@ -75,6 +76,7 @@ impl PerspectiveCamera {
             shutter_close,
             lens_radius,
             focal_distance,
+            aperture,
             fov: fov.into(),
             film,
             medium,
@@ -104,21 +106,28 @@ impl Camera for PerspectiveCamera {
             (dir + self.camera_dy).normalize(),
         );
 
-        let ray = RayDifferential {
+        let mut ray = RayDifferential {
             ray: Ray {
                 origin: Point3f::zero(),
                 direction: dir,
                 max: Float::infinity(),
                 time: camera_sample.time.lerp(self.shutter_open, self.shutter_close),
-                medium: None,
+                medium: self.medium,
             },
             x: Some(ray_x),
             y: Some(ray_y),
         };
 
+        // modify ray for depth of field
         if self.lens_radius > 0.0 {
-            // todo: modify ray for DoF
-            unimplemented!()
+            let p_lens = self.aperture.sample(camera_sample.lens) * self.lens_radius;
+
+            let lens_ray = thin_lens_ray(RayData::new(ray.origin, ray.direction), p_lens, self.focal_distance);
+            ray.origin = lens_ray.origin;
+            ray.direction = lens_ray.direction;
+
+            ray.x = Some(thin_lens_ray(ray_x, p_lens, self.focal_distance));
+            ray.y = Some(thin_lens_ray(ray_y, p_lens, self.focal_distance));
         }
 
         let ray = self.camera_to_world.transform_ray_differential(ray.time, ray);
@@ -0,0 +1,754 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{ self, BufWriter, Read, Write };
+use std::iter;
+use std::path::Path;
+use inflate;
+use crate::prelude::*;
+
+/// How each channel's values are stored in the file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExrPixelType {
+    Uint,
+    Half,
+    Float,
+}
+
+impl ExrPixelType {
+    fn id(self) -> u32 {
+        match self {
+            ExrPixelType::Uint => 0,
+            ExrPixelType::Half => 1,
+            ExrPixelType::Float => 2,
+        }
+    }
+
+    fn from_id(id: u32) -> Option<Self> {
+        match id {
+            0 => Some(ExrPixelType::Uint),
+            1 => Some(ExrPixelType::Half),
+            2 => Some(ExrPixelType::Float),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            ExrPixelType::Half => 2,
+            ExrPixelType::Uint | ExrPixelType::Float => 4,
+        }
+    }
+}
+
+/// The error inside the `io::Error` returned when reading a file
+/// with a compression that can't be decoded, with the name of its compression.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct UnsupportedCompression(pub &'static str);
+
+impl fmt::Display for UnsupportedCompression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "OpenEXR {} compression isn't supported, only uncompressed, RLE, ZIPS and ZIP files can be read", self.0)
+    }
+}
+
+impl Error for UnsupportedCompression {}
+
+#[derive(Clone, Debug)]
+pub struct ExrChannel {
+    pub name: String,
+    pub pixel_type: ExrPixelType,
+    /// one value per pixel, in scanline order
+    pub values: Vec<Float>,
+}
+
+/// A single-part, scanline OpenEXR image.
+#[derive(Clone, Debug)]
+pub struct ExrImage {
+    pub width: usize,
+    pub height: usize,
+    channels: Vec<ExrChannel>,
+}
+
+impl ExrImage {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            channels: vec![],
+        }
+    }
+
+    /// `rgb` has 3 values per pixel, in scanline order.
+    pub fn from_rgb(width: usize, height: usize, rgb: &[Float], pixel_type: ExrPixelType) -> Self {
+        let mut image = Self::new(width, height);
+        image.add_interleaved(&["R", "G", "B"], rgb, pixel_type);
+        image
+    }
+
+    /// `rgba` has 4 values per pixel, in scanline order.
+    pub fn from_rgba(width: usize, height: usize, rgba: &[Float], pixel_type: ExrPixelType) -> Self {
+        let mut image = Self::new(width, height);
+        image.add_interleaved(&["R", "G", "B", "A"], rgba, pixel_type);
+        image
+    }
+
+    fn add_interleaved(&mut self, names: &[&str], values: &[Float], pixel_type: ExrPixelType) {
+        let n = names.len();
+        for (i, name) in names.iter().enumerate() {
+            let channel = values.iter().skip(i).step_by(n).cloned().collect();
+            self.add_channel(*name, pixel_type, channel);
+        }
+    }
+
+    /// Adds a channel, replacing any existing channel with the same name.
+    pub fn add_channel(&mut self, name: impl Into<String>, pixel_type: ExrPixelType, values: Vec<Float>) {
+        assert_eq!(values.len(), self.width * self.height, "EXR channel needs one value per pixel");
+
+        let name = name.into();
+        assert!(!name.is_empty() && !name.contains('\0'), "invalid EXR channel name {:?}", name);
+
+        self.channels.retain(|c| c.name != name);
+        self.channels.push(ExrChannel { name, pixel_type, values });
+        // channels are always stored in alphabetical order
+        self.channels.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    pub fn channels(&self) -> &[ExrChannel] {
+        &self.channels
+    }
+
+    pub fn channel(&self, name: &str) -> Option<&ExrChannel> {
+        self.channels.iter().find(|c| c.name == name)
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut data = vec![];
+        File::open(path)?.read_to_end(&mut data)?;
+        Self::read_from(&data)
+    }
+
+    /// Only single-part, scanline files that are uncompressed or use RLE, ZIPS or ZIP compression
+    /// can be read, which includes everything that `write_to` creates.
+    /// Other compressions give an `InvalidData` error wrapping `UnsupportedCompression`.
+    pub fn read_from(data: &[u8]) -> io::Result<Self> {
+        let mut r = ExrReader { data, pos: 0 };
+
+        if r.u32()? != 20_000_630 {
+            return Err(invalid("not an OpenEXR file"));
+        }
+        // tiled, deep & multi-part flags
+        let version = r.u32()?;
+        if version & 0xff != 2 || version & 0x1a00 != 0 {
+            return Err(invalid("only single-part scanline OpenEXR files are supported"));
+        }
+
+        let mut channels = None;
+        let mut compression = None;
+        let mut window = None;
+        loop {
+            let name = r.string()?;
+            if name.is_empty() {
+                break;
+            }
+
+            let _ty = r.string()?;
+            let size = r.u32()? as usize;
+            let value = r.bytes(size)?;
+
+            match name.as_str() {
+                "channels" => channels = Some(read_chlist(value)?),
+                "compression" => compression = value.first().cloned(),
+                "dataWindow" => {
+                    let mut v = ExrReader { data: value, pos: 0 };
+                    window = Some([v.i32()?, v.i32()?, v.i32()?, v.i32()?]);
+                },
+                _ => (),
+            }
+        }
+
+        let channels = channels.ok_or_else(|| invalid("missing \"channels\" attribute"))?;
+        let [x_min, y_min, x_max, y_max] = window.ok_or_else(|| invalid("missing \"dataWindow\" attribute"))?;
+        let compression = compression.ok_or_else(|| invalid("missing \"compression\" attribute"))?;
+        // the number of scanlines in each chunk
+        let lines_per_chunk = match compression {
+            0 | 1 | 2 => 1,
+            3 => 16,
+            id => {
+                let name = match id {
+                    4 => "PIZ",
+                    5 => "PXR24",
+                    6 => "B44",
+                    7 => "B44A",
+                    8 => "DWAA",
+                    9 => "DWAB",
+                    _ => "unknown",
+                };
+                return Err(io::Error::new(io::ErrorKind::InvalidData, UnsupportedCompression(name)));
+            },
+        };
+
+        if x_max < x_min || y_max < y_min {
+            return Err(invalid("empty data window"));
+        }
+        let width = (x_max - x_min + 1) as usize;
+        let height = (y_max - y_min + 1) as usize;
+        let line_size: usize = channels.iter()
+            .map(|(_, pixel_type)| pixel_type.size() * width)
+            .sum();
+
+        let chunks = (height + lines_per_chunk - 1) / lines_per_chunk;
+        let mut offsets = Vec::with_capacity(chunks);
+        for _ in 0..chunks {
+            let low = u64::from(r.u32()?);
+            let high = u64::from(r.u32()?);
+            offsets.push(low | (high << 32));
+        }
+
+        let mut values = vec![vec![float(0.0); width * height]; channels.len()];
+        for offset in offsets {
+            let mut chunk = ExrReader { data, pos: offset as usize };
+
+            let y = chunk.i32()? - y_min;
+            if y < 0 || y as usize >= height {
+                return Err(invalid("scanline is outside of the data window"));
+            }
+            let y = y as usize;
+            let lines = lines_per_chunk.min(height - y);
+
+            let size = chunk.u32()? as usize;
+            let packed = chunk.bytes(size)?;
+            // chunks that wouldn't get any smaller are stored uncompressed
+            let unpacked = if size == lines * line_size {
+                packed.to_vec()
+            } else {
+                decompress(compression, packed, lines * line_size)?
+            };
+            let mut chunk = ExrReader { data: &unpacked, pos: 0 };
+
+            for y in y..y + lines {
+                for ((_, pixel_type), values) in channels.iter().zip(&mut values) {
+                    for v in &mut values[y * width..(y + 1) * width] {
+                        let value = match pixel_type {
+                            ExrPixelType::Uint => chunk.u32()? as f32,
+                            ExrPixelType::Half => half_to_f32(chunk.u16()?),
+                            ExrPixelType::Float => f32::from_bits(chunk.u32()?),
+                        };
+                        // `Float` can't hold NaNs
+                        *v = if value.is_nan() { float(0.0) } else { float(value) };
+                    }
+                }
+            }
+        }
+
+        let mut image = Self::new(width, height);
+        for ((name, pixel_type), values) in channels.into_iter().zip(values) {
+            image.add_channel(name, pixel_type, values);
+        }
+
+        Ok(image)
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write_to(&mut file)?;
+        file.flush()
+    }
+
+    pub fn write_to(&self, w: &mut impl Write) -> io::Result<()> {
+        assert!(self.width > 0 && self.height > 0, "EXR images can't be empty");
+
+        let mut header = vec![];
+
+        // magic number & version 2, single-part scanline
+        push_u32(&mut header, 20_000_630);
+        push_u32(&mut header, 2);
+
+        let mut chlist = vec![];
+        for channel in &self.channels {
+            chlist.extend_from_slice(channel.name.as_bytes());
+            chlist.push(0);
+            push_u32(&mut chlist, channel.pixel_type.id());
+            // pLinear & reserved
+            chlist.extend_from_slice(&[0, 0, 0, 0]);
+            // x & y sampling
+            push_u32(&mut chlist, 1);
+            push_u32(&mut chlist, 1);
+        }
+        chlist.push(0);
+        attribute(&mut header, "channels", "chlist", &chlist);
+
+        // no compression
+        attribute(&mut header, "compression", "compression", &[0]);
+
+        let mut window = vec![];
+        for v in &[0, 0, self.width as u32 - 1, self.height as u32 - 1] {
+            push_u32(&mut window, *v);
+        }
+        attribute(&mut header, "dataWindow", "box2i", &window);
+        attribute(&mut header, "displayWindow", "box2i", &window);
+
+        // increasing y
+        attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+        let mut one = vec![];
+        push_u32(&mut one, 1f32.to_bits());
+        attribute(&mut header, "pixelAspectRatio", "float", &one);
+
+        let mut center = vec![];
+        push_u32(&mut center, 0f32.to_bits());
+        push_u32(&mut center, 0f32.to_bits());
+        attribute(&mut header, "screenWindowCenter", "v2f", &center);
+        attribute(&mut header, "screenWindowWidth", "float", &one);
+
+        // end of header
+        header.push(0);
+
+        // each uncompressed chunk is a single scanline
+        let line_size: usize = self.channels.iter()
+            .map(|c| c.pixel_type.size() * self.width)
+            .sum();
+        let chunk_size = 8 + line_size;
+        let offsets_start = header.len() + 8 * self.height;
+
+        let mut offsets = Vec::with_capacity(8 * self.height);
+        for y in 0..self.height {
+            let offset = (offsets_start + y * chunk_size) as u64;
+            push_u32(&mut offsets, offset as u32);
+            push_u32(&mut offsets, (offset >> 32) as u32);
+        }
+
+        w.write_all(&header)?;
+        w.write_all(&offsets)?;
+
+        let mut line = Vec::with_capacity(chunk_size);
+        for y in 0..self.height {
+            line.clear();
+            push_u32(&mut line, y as u32);
+            push_u32(&mut line, line_size as u32);
+
+            for channel in &self.channels {
+                let values = &channel.values[y * self.width..(y + 1) * self.width];
+                for v in values {
+                    match channel.pixel_type {
+                        ExrPixelType::Uint => push_u32(&mut line, v.raw() as u32),
+                        ExrPixelType::Half => {
+                            let half = f32_to_half(v.raw() as f32);
+                            line.extend_from_slice(&[half as u8, (half >> 8) as u8]);
+                        },
+                        ExrPixelType::Float => push_u32(&mut line, (v.raw() as f32).to_bits()),
+                    }
+                }
+            }
+
+            w.write_all(&line)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// EXR files are always little-endian.
+fn push_u32(buf: &mut Vec<u8>, v: u32) {
+    buf.extend_from_slice(&[v as u8, (v >> 8) as u8, (v >> 16) as u8, (v >> 24) as u8]);
+}
+
+fn attribute(header: &mut Vec<u8>, name: &str, ty: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(ty.as_bytes());
+    header.push(0);
+    push_u32(header, value.len() as u32);
+    header.extend_from_slice(value);
+}
+
+struct ExrReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl ExrReader<'a> {
+    fn bytes(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        if self.pos + n > self.data.len() {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "OpenEXR file is truncated"));
+        }
+
+        let bytes = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        let b = self.bytes(2)?;
+        Ok(u16::from(b[0]) | u16::from(b[1]) << 8)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        let b = self.bytes(4)?;
+        Ok(u32::from(b[0]) | u32::from(b[1]) << 8 | u32::from(b[2]) << 16 | u32::from(b[3]) << 24)
+    }
+
+    fn i32(&mut self) -> io::Result<i32> {
+        Ok(self.u32()? as i32)
+    }
+
+    /// A null-terminated string.
+    fn string(&mut self) -> io::Result<String> {
+        let len = self.data[self.pos.min(self.data.len())..].iter()
+            .position(|b| *b == 0)
+            .ok_or_else(|| invalid("unterminated string"))?;
+
+        let bytes = self.bytes(len + 1)?;
+        Ok(String::from_utf8_lossy(&bytes[..len]).into_owned())
+    }
+}
+
+fn read_chlist(value: &[u8]) -> io::Result<Vec<(String, ExrPixelType)>> {
+    let mut r = ExrReader { data: value, pos: 0 };
+    let mut channels = vec![];
+
+    loop {
+        let name = r.string()?;
+        if name.is_empty() {
+            break;
+        }
+
+        let pixel_type = ExrPixelType::from_id(r.u32()?)
+            .ok_or_else(|| invalid("unknown channel pixel type"))?;
+        // pLinear & reserved
+        r.bytes(4)?;
+        if r.i32()? != 1 || r.i32()? != 1 {
+            return Err(invalid("subsampled channels aren't supported"));
+        }
+
+        channels.push((name, pixel_type));
+    }
+
+    Ok(channels)
+}
+
+/// Decodes a chunk's data, which has to be `size` bytes once decompressed.
+fn decompress(compression: u8, packed: &[u8], size: usize) -> io::Result<Vec<u8>> {
+    let mut data = match compression {
+        1 => rle_decode(packed, size)?,
+        2 | 3 => inflate::inflate_bytes_zlib(packed).map_err(|e| invalid(&e))?,
+        _ => return Err(invalid("chunk has the wrong size")),
+    };
+    if data.len() != size {
+        return Err(invalid("decompressed chunk has the wrong size"));
+    }
+
+    // undo the predictor, which stores the differences between neighbouring bytes
+    for i in 1..data.len() {
+        data[i] = data[i - 1].wrapping_add(data[i]).wrapping_sub(128);
+    }
+
+    // the bytes were split in two halves, with the odd ones in the second
+    let (even, odd) = data.split_at((size + 1) / 2);
+    let mut bytes = Vec::with_capacity(size);
+    for (i, e) in even.iter().enumerate() {
+        bytes.push(*e);
+        if let Some(o) = odd.get(i) {
+            bytes.push(*o);
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// A negative count is followed by that many literal bytes,
+/// otherwise the next byte is repeated `count + 1` times.
+fn rle_decode(packed: &[u8], size: usize) -> io::Result<Vec<u8>> {
+    let truncated = || invalid("RLE chunk is truncated");
+    let mut data = Vec::with_capacity(size);
+    let mut bytes = packed.iter();
+
+    while let Some(count) = bytes.next() {
+        let count = *count as i8;
+        if count < 0 {
+            for _ in 0..-(i32::from(count)) {
+                data.push(*bytes.next().ok_or_else(truncated)?);
+            }
+        } else {
+            let byte = *bytes.next().ok_or_else(truncated)?;
+            data.extend(iter::repeat(byte).take(count as usize + 1));
+        }
+
+        if data.len() > size {
+            break;
+        }
+    }
+
+    Ok(data)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Converts to a 16-bit float, rounding to the nearest even value.
+pub fn f32_to_half(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x007f_ffff;
+
+    // infinity or NaN
+    if exponent == 0xff {
+        let nan = if mantissa != 0 { 0x0200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+
+    let exponent = exponent - 127 + 15;
+
+    // too large, so round to infinity
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+
+    // too small for a normal half
+    if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
+        }
+
+        let mantissa = mantissa | 0x0080_0000;
+        let shift = (14 - exponent) as u32;
+        let round_bit = 1 << (shift - 1);
+        let mut half = mantissa >> shift;
+        if mantissa & round_bit != 0 && mantissa & (3 * round_bit - 1) != 0 {
+            half += 1;
+        }
+
+        return sign | half as u16;
+    }
+
+    let round_bit = 0x0000_1000;
+    let mut half = ((exponent as u32) << 10) | (mantissa >> 13);
+    // a carry here can overflow into the exponent, which is still correct
+    if mantissa & round_bit != 0 && mantissa & (3 * round_bit - 1) != 0 {
+        half += 1;
+    }
+
+    sign | half as u16
+}
+
+pub fn half_to_f32(half: u16) -> f32 {
+    let sign = u32::from(half & 0x8000) << 16;
+    let exponent = u32::from((half >> 10) & 0x1f);
+    let mantissa = u32::from(half & 0x03ff);
+
+    let bits = match exponent {
+        0 if mantissa == 0 => sign,
+        // subnormal halfs are normal floats
+        0 => {
+            let mut exponent = 127 - 14;
+            let mut mantissa = mantissa;
+            while mantissa & 0x0400 == 0 {
+                mantissa <<= 1;
+                exponent -= 1;
+            }
+
+            sign | (exponent << 23) | ((mantissa & 0x03ff) << 13)
+        },
+        // infinity or NaN
+        0x1f => sign | 0x7f80_0000 | (mantissa << 13),
+        _ => sign | ((exponent + 127 - 15) << 23) | (mantissa << 13),
+    };
+
+    f32::from_bits(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(values: &[f32]) -> Vec<Float> {
+        values.iter().map(|v| float(*v)).collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let (width, height) = (3, 2);
+        let mut image = ExrImage::from_rgb(width, height, &values(&[
+            0.1, 0.2, 0.3, 1.0, 2.0, 3.0, 1e-3, 1e3, 1e6,
+            0.0, 0.5, 0.25, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0,
+        ]), ExrPixelType::Float);
+        // exactly representable as halfs
+        image.add_channel("A", ExrPixelType::Half, values(&[0.0, 0.5, 1.0, -3.25, 65504.0, 2.0f32.powi(-24)]));
+        image.add_channel("id", ExrPixelType::Uint, values(&[0.0, 1.0, 2.0, 3.0, 70000.0, 5.0]));
+
+        let mut data = vec![];
+        image.write_to(&mut data).unwrap();
+        let read = ExrImage::read_from(&data).unwrap();
+
+        assert_eq!((read.width, read.height), (width, height));
+        let names = read.channels().iter().map(|c| c.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["A", "B", "G", "R", "id"]);
+        for channel in image.channels() {
+            let read = read.channel(&channel.name).unwrap();
+            assert_eq!(read.pixel_type, channel.pixel_type);
+            assert_eq!(read.values, channel.values, "channel {}", channel.name);
+        }
+    }
+
+    /// Rewrites the uncompressed file `data` of `image` with `compression`,
+    /// where `encode` does the final step after splitting the bytes and the predictor.
+    fn recompress(image: &ExrImage, data: &[u8], compression: u8, lines_per_chunk: usize,
+                  encode: fn(&[u8]) -> Vec<u8>) -> Vec<u8> {
+        let line_size: usize = image.channels().iter()
+            .map(|c| c.pixel_type.size() * image.width)
+            .sum();
+        let header_len = data.len() - image.height * (16 + line_size);
+
+        let mut header = data[..header_len].to_vec();
+        let name = b"compression\0compression\0\x01\0\0\0";
+        let pos = header.windows(name.len()).position(|w| w == &name[..]).unwrap() + name.len();
+        header[pos] = compression;
+
+        let mut chunks = vec![];
+        for (i, lines) in (0..image.height).collect::<Vec<_>>().chunks(lines_per_chunk).enumerate() {
+            let mut raw = vec![];
+            for y in lines {
+                let start = header_len + 8 * image.height + y * (8 + line_size) + 8;
+                raw.extend_from_slice(&data[start..start + line_size]);
+            }
+
+            let mut t: Vec<u8> = raw.iter().step_by(2).chain(raw.iter().skip(1).step_by(2)).cloned().collect();
+            for i in (1..t.len()).rev() {
+                t[i] = t[i].wrapping_sub(t[i - 1]).wrapping_add(128);
+            }
+            let packed = encode(&t);
+
+            let mut chunk = vec![];
+            push_u32(&mut chunk, (i * lines_per_chunk) as u32);
+            push_u32(&mut chunk, packed.len() as u32);
+            chunk.extend_from_slice(&packed);
+            chunks.push(chunk);
+        }
+
+        let mut file = header;
+        let mut offset = file.len() + 8 * chunks.len();
+        for chunk in &chunks {
+            push_u32(&mut file, offset as u32);
+            push_u32(&mut file, 0);
+            offset += chunk.len();
+        }
+        for chunk in &chunks {
+            file.extend_from_slice(chunk);
+        }
+        file
+    }
+
+    /// A zlib stream of uncompressed deflate blocks.
+    fn zlib_stored(data: &[u8]) -> Vec<u8> {
+        let mut zlib = vec![0x78, 0x01];
+        let blocks = data.chunks(0xffff).collect::<Vec<_>>();
+        for (i, block) in blocks.iter().enumerate() {
+            zlib.push(if i + 1 == blocks.len() { 1 } else { 0 });
+            let len = block.len() as u16;
+            zlib.extend_from_slice(&[len as u8, (len >> 8) as u8, !len as u8, (!len >> 8) as u8]);
+            zlib.extend_from_slice(block);
+        }
+
+        let (mut a, mut b) = (1u32, 0u32);
+        for byte in data {
+            a = (a + u32::from(*byte)) % 65521;
+            b = (b + a) % 65521;
+        }
+        let adler = (b << 16) | a;
+        zlib.extend_from_slice(&[(adler >> 24) as u8, (adler >> 16) as u8, (adler >> 8) as u8, adler as u8]);
+        zlib
+    }
+
+    /// Only literal runs.
+    fn rle_literal(data: &[u8]) -> Vec<u8> {
+        let mut rle = vec![];
+        for run in data.chunks(127) {
+            rle.push(-(run.len() as i8) as u8);
+            rle.extend_from_slice(run);
+        }
+        rle
+    }
+
+    #[test]
+    fn compressed() {
+        // 17 lines so that ZIP needs a partial second chunk
+        let (width, height) = (3, 17);
+        let mut image = ExrImage::new(width, height);
+        let n = width * height;
+        image.add_channel("R", ExrPixelType::Float, (0..n).map(|i| float(i as f32 * 0.25)).collect());
+        image.add_channel("A", ExrPixelType::Half, (0..n).map(|i| float((i % 7) as f32 - 3.0)).collect());
+
+        let mut data = vec![];
+        image.write_to(&mut data).unwrap();
+
+        let files = [
+            recompress(&image, &data, 1, 1, rle_literal),
+            recompress(&image, &data, 2, 1, zlib_stored),
+            recompress(&image, &data, 3, 16, zlib_stored),
+        ];
+        for file in &files {
+            let read = ExrImage::read_from(file).unwrap();
+            assert_eq!((read.width, read.height), (width, height));
+            for channel in image.channels() {
+                assert_eq!(read.channel(&channel.name).unwrap().values, channel.values);
+            }
+        }
+
+        let broken = recompress(&image, &data, 3, 16, |t| {
+            let mut zlib = zlib_stored(t);
+            zlib[0] = 0;
+            zlib
+        });
+        assert!(ExrImage::read_from(&broken).is_err());
+
+        let piz = recompress(&image, &data, 4, 32, zlib_stored);
+        let err = ExrImage::read_from(&piz).unwrap_err();
+        let inner = err.get_ref().and_then(|e| e.downcast_ref::<UnsupportedCompression>());
+        assert_eq!(inner, Some(&UnsupportedCompression("PIZ")));
+    }
+
+    #[test]
+    fn invalid_files() {
+        let image = ExrImage::from_rgb(1, 1, &values(&[1.0, 2.0, 3.0]), ExrPixelType::Half);
+        let mut data = vec![];
+        image.write_to(&mut data).unwrap();
+
+        assert!(ExrImage::read_from(&data[..data.len() - 1]).is_err());
+        assert!(ExrImage::read_from(&data[4..]).is_err());
+    }
+
+    #[test]
+    fn half_rounding() {
+        assert_eq!(f32_to_half(0.0), 0x0000);
+        assert_eq!(f32_to_half(-0.0), 0x8000);
+        assert_eq!(f32_to_half(1.0), 0x3c00);
+        assert_eq!(f32_to_half(-2.0), 0xc000);
+        // halfway between 1 and the next half, which rounds to the even one, either way
+        assert_eq!(f32_to_half(1.0 + 2.0f32.powi(-11)), 0x3c00);
+        assert_eq!(f32_to_half(1.0 + 3.0 * 2.0f32.powi(-11)), 0x3c02);
+        // just above halfway rounds up
+        assert_eq!(f32_to_half(1.0 + 2.0f32.powi(-11) + 2.0f32.powi(-20)), 0x3c01);
+        // subnormals
+        assert_eq!(f32_to_half(2.0f32.powi(-24)), 0x0001);
+        assert_eq!(f32_to_half(2.0f32.powi(-25)), 0x0000);
+        assert_eq!(f32_to_half(2.0f32.powi(-14) - 2.0f32.powi(-24)), 0x03ff);
+
+        for half in &[0x0001, 0x03ff, 0x0400, 0x3555, 0x7bff, 0x8001, 0xfbff] {
+            assert_eq!(f32_to_half(half_to_f32(*half)), *half);
+        }
+    }
+
+    #[test]
+    fn half_overflow() {
+        assert_eq!(f32_to_half(65504.0), 0x7bff);
+        // halfway to the next power of two rounds up to infinity
+        assert_eq!(f32_to_half(65520.0), 0x7c00);
+        assert_eq!(f32_to_half(65519.0), 0x7bff);
+        assert_eq!(f32_to_half(1e10), 0x7c00);
+        assert_eq!(f32_to_half(-1e10), 0xfc00);
+        assert_eq!(f32_to_half(std::f32::INFINITY), 0x7c00);
+        assert_eq!(f32_to_half(std::f32::NEG_INFINITY), 0xfc00);
+        assert_eq!(f32_to_half(std::f32::NAN) & 0x7e00, 0x7e00);
+    }
+}
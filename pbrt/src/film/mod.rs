@@ -1,5 +1,6 @@
-use std::env;
 use std::cmp::{ max, min };
+use std::io;
+use std::path::Path;
 use std::sync::{ Arc, Mutex };
 use std::ops::{ Deref, DerefMut };
 use atomic::{ Atomic, Ordering };
@@ -9,13 +10,22 @@ use crate::prelude::*;
 use crate::filter::Filter;
 use crate::spectrum::utils::xyz_to_rgb;
 
+mod exr;
+pub use self::exr::{ ExrChannel, ExrImage, ExrPixelType, UnsupportedCompression };
+
 const FILTER_TABLE_WIDTH: usize = 16;
 
+/// Filters with negative lobes can give a pixel weights that nearly cancel out,
+/// if the sum is this small compared to the sum of the weights' magnitudes,
+/// the magnitudes are used to normalize the pixel instead.
+const MIN_RELATIVE_FILTER_WEIGHT: FloatPrim = 1e-3;
+
 #[repr(align(32))]
 #[derive(Debug)]
 pub struct Pixel {
     xyz: [Float; 3],
     filter_weight_sum: Float,
+    filter_weight_abs_sum: Float,
     // todo - this Atomic<Float> is probably not the best
     splat_xyz: [Atomic<Float>; 3],
 }
@@ -25,6 +35,7 @@ impl Pixel {
         Self {
             xyz: [float(0.0); 3],
             filter_weight_sum: float(0.0),
+            filter_weight_abs_sum: float(0.0),
             splat_xyz: [
                 Atomic::new(float(0.0)),
                 Atomic::new(float(0.0)),
@@ -45,6 +56,7 @@ impl Clone for Pixel {
         Self {
             xyz: self.xyz,
             filter_weight_sum: self.filter_weight_sum,
+            filter_weight_abs_sum: self.filter_weight_abs_sum,
             splat_xyz: [
                 Atomic::new(self.splat_xyz[0].load(Ordering::SeqCst)),
                 Atomic::new(self.splat_xyz[1].load(Ordering::SeqCst)),
@@ -61,7 +73,14 @@ pub struct Film {
     pub filter: Box<dyn Filter + Send>,
     pub diagonal: Float,
     scale: Float,
+    /// The extension chooses the format, `.exr` keeps the full HDR radiance values,
+    /// anything else is gamma-corrected and saved as 8 bits per channel.
+    /// If there isn't an extension, `.png` is added.
     pub filename: String,
+    /// How the channels of `.exr` images are stored, `Float` by default.
+    pub exr_pixel_type: ExrPixelType,
+    /// Whether `.exr` images get an `A` channel, which is always 1.
+    pub exr_alpha: bool,
     pixels: Mutex<Vec<Pixel>>,
     filter_table: Arc<[Float; FILTER_TABLE_WIDTH * FILTER_TABLE_WIDTH]>,
 }
@@ -116,6 +135,8 @@ impl Film {
             diagonal: diagonal * float(0.001),
             scale,
             filename,
+            exr_pixel_type: ExrPixelType::Float,
+            exr_alpha: false,
             pixels,
             filter_table,
         }
@@ -174,6 +195,7 @@ impl Film {
             }
 
             merge_pixel.filter_weight_sum += tile_pixel.filter_weight_sum;
+            merge_pixel.filter_weight_abs_sum += tile_pixel.filter_weight_abs_sum;
         }
     }
 
@@ -185,6 +207,7 @@ impl Film {
             let pixel = &mut pixels[i];
             pixel.xyz = spectrums[i].to_xyz();
             pixel.filter_weight_sum = float(1.0);
+            pixel.filter_weight_abs_sum = float(1.0);
             pixel.splat_xyz[0].store(float(0.0), Ordering::SeqCst);
             pixel.splat_xyz[1].store(float(0.0), Ordering::SeqCst);
             pixel.splat_xyz[2].store(float(0.0), Ordering::SeqCst);
@@ -210,7 +233,7 @@ impl Film {
         pixel.splat_xyz[2].store(z + xyz[2], Ordering::SeqCst);
     }
 
-    pub fn write_image(&self, splat_scale: Float) {
+    pub fn write_image(&self, splat_scale: Float) -> io::Result<()> {
         let pixels = self.pixels.lock().unwrap();
         let mut rgb = vec![float(0.0); 3 * self.cropped_pixel_bounds.area() as usize];
 
@@ -223,8 +246,14 @@ impl Film {
             rgb[offset_3 + 1] = rgb_vals[1];
             rgb[offset_3 + 2] = rgb_vals[2];
 
-            if pixel.filter_weight_sum != float(0.0) {
-                let inv = float(1.0) / pixel.filter_weight_sum;
+            let weight_sum = if pixel.filter_weight_sum.abs() > pixel.filter_weight_abs_sum * MIN_RELATIVE_FILTER_WEIGHT {
+                pixel.filter_weight_sum
+            } else {
+                pixel.filter_weight_abs_sum
+            };
+
+            if weight_sum != float(0.0) {
+                let inv = float(1.0) / weight_sum;
                 rgb[offset_3] = max(float(0.0), rgb[offset_3] * inv);
                 rgb[offset_3 + 1] = max(float(0.0), rgb[offset_3 + 1] * inv);
                 rgb[offset_3 + 2] = max(float(0.0), rgb[offset_3 + 2] * inv);
@@ -240,8 +269,30 @@ impl Film {
             rgb[offset_3 + 2] += splat_rgb[2] * splat_scale;
         }
 
-        let dir = env::current_dir().unwrap();
-        let path = dir.join(format!("{}.png", &self.filename));
+        let width = self.cropped_pixel_bounds.max.x - self.cropped_pixel_bounds.min.x;
+        let height = self.cropped_pixel_bounds.max.y - self.cropped_pixel_bounds.min.y;
+
+        let mut path = Path::new(&self.filename).to_path_buf();
+        if path.extension().is_none() {
+            path.set_extension("png");
+        }
+
+        let is_exr = path.extension()
+            .and_then(|e| e.to_str())
+            .map_or(false, |e| e.eq_ignore_ascii_case("exr"));
+
+        if is_exr {
+            let (width, height) = (width as usize, height as usize);
+            let image = if self.exr_alpha {
+                let rgba: Vec<_> = rgb.chunks(3)
+                    .flat_map(|p| p.iter().cloned().chain(Some(float(1.0))))
+                    .collect();
+                ExrImage::from_rgba(width, height, &rgba, self.exr_pixel_type)
+            } else {
+                ExrImage::from_rgb(width, height, &rgb, self.exr_pixel_type)
+            };
+            return image.write(&path);
+        }
 
         let buf: Vec<_> = rgb.iter().map(|p| {
             num::clamp(
@@ -250,10 +301,8 @@ impl Film {
                 255.0
             ) as u8
         }).collect();
-        let width = self.cropped_pixel_bounds.max.y - self.cropped_pixel_bounds.min.x;
-        let height = self.cropped_pixel_bounds.max.y - self.cropped_pixel_bounds.min.y;
 
-        image::save_buffer(path, &buf, width as u32, height as u32, image::RGB(8)).unwrap();
+        image::save_buffer(&path, &buf, width as u32, height as u32, image::RGB(8))
     }
 }
 
@@ -273,6 +322,7 @@ fn get_pixel_mut(cropped_pixel_bounds: Bounds2i, pixels: &mut impl DerefMut<Targ
 pub struct FilmTilePixel {
     contrib_sum: Spectrum,
     filter_weight_sum: Float,
+    filter_weight_abs_sum: Float,
 }
 
 impl FilmTilePixel {
@@ -280,6 +330,7 @@ impl FilmTilePixel {
         Self {
             contrib_sum: Spectrum::new(0.0),
             filter_weight_sum: float(0.0),
+            filter_weight_abs_sum: float(0.0),
         }
     }
 }
@@ -319,6 +370,12 @@ impl FilmTile {
     }
 
     pub fn add_sample(&mut self, film_point: Point2f, l: Spectrum, sample_weight: Float) {
+        // an infinite sample would be spread with both signs by a filter with negative lobes,
+        // and the infinities would cancel out to NaN
+        if !sample_weight.is_finite() || l.iter().any(|c| !c.is_finite()) {
+            return;
+        }
+
         // compute raster bounds
         let film_discrete = film_point - Vector2f::new(float(0.5), float(0.5));
 
@@ -350,6 +407,7 @@ impl FilmTile {
                 let pixel = self.get_pixel_mut(Point2i::new(x, y));
                 pixel.contrib_sum += l * sample_weight * filter_weight;
                 pixel.filter_weight_sum += filter_weight;
+                pixel.filter_weight_abs_sum += filter_weight.abs();
             }
         }
     }
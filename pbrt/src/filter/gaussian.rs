@@ -0,0 +1,48 @@
+use std::cmp::max;
+use crate::prelude::*;
+use super::Filter;
+
+#[derive(Clone, Debug)]
+pub struct GaussianFilter {
+    radius: Vector2f,
+    radius_inv: Vector2f,
+    alpha: Float,
+    /// the value of the gaussian at the radius, which is subtracted so the filter goes to zero there
+    exp: Vector2f,
+}
+
+impl GaussianFilter {
+    /// Larger values of `alpha` make a narrower filter.
+    pub fn new(radius: Vector2f, alpha: Float) -> Self {
+        Self {
+            radius,
+            radius_inv: Vector2f::new(
+                float(1.0) / radius.x,
+                float(1.0) / radius.y,
+            ),
+            alpha,
+            exp: Vector2f::new(
+                (-alpha * radius.x * radius.x).exp(),
+                (-alpha * radius.y * radius.y).exp(),
+            ),
+        }
+    }
+
+    fn gaussian(&self, d: Float, exp: Float) -> Float {
+        max(float(0.0), (-self.alpha * d * d).exp() - exp)
+    }
+}
+
+impl Filter for GaussianFilter {
+    fn radius(&self) -> Vector2f {
+        self.radius
+    }
+
+    fn radius_inv(&self) -> Vector2f {
+        self.radius_inv
+    }
+
+    fn evaluate(&self, p: Point2f) -> Float {
+        self.gaussian(p.x, self.exp.x) * self.gaussian(p.y, self.exp.y)
+    }
+}
@@ -0,0 +1,61 @@
+use crate::prelude::*;
+use super::Filter;
+
+/// The Mitchell-Netravali family of cubic filters, which have negative lobes
+/// that sharpen the image.
+///
+/// Mitchell and Netravali recommend `b + 2c = 1`.
+#[derive(Clone, Debug)]
+pub struct MitchellFilter {
+    radius: Vector2f,
+    radius_inv: Vector2f,
+    b: Float,
+    c: Float,
+}
+
+impl MitchellFilter {
+    pub fn new(radius: Vector2f, b: Float, c: Float) -> Self {
+        Self {
+            radius,
+            radius_inv: Vector2f::new(
+                float(1.0) / radius.x,
+                float(1.0) / radius.y,
+            ),
+            b,
+            c,
+        }
+    }
+
+    /// The filter over `[-1, 1]`.
+    fn mitchell_1d(&self, x: Float) -> Float {
+        let (b, c) = (self.b, self.c);
+        let x = (float(2.0) * x).abs();
+
+        let value = if x > 1.0 {
+            (-b - float(6.0) * c) * x.powi(3) +
+            (float(6.0) * b + float(30.0) * c) * x.powi(2) +
+            (float(-12.0) * b - float(48.0) * c) * x +
+            (float(8.0) * b + float(24.0) * c)
+        } else {
+            (float(12.0) - float(9.0) * b - float(6.0) * c) * x.powi(3) +
+            (float(-18.0) + float(12.0) * b + float(6.0) * c) * x.powi(2) +
+            (float(6.0) - float(2.0) * b)
+        };
+
+        value / float(6.0)
+    }
+}
+
+impl Filter for MitchellFilter {
+    fn radius(&self) -> Vector2f {
+        self.radius
+    }
+
+    fn radius_inv(&self) -> Vector2f {
+        self.radius_inv
+    }
+
+    fn evaluate(&self, p: Point2f) -> Float {
+        self.mitchell_1d(p.x * self.radius_inv.x) * self.mitchell_1d(p.y * self.radius_inv.y)
+    }
+}
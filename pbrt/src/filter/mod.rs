@@ -3,10 +3,21 @@ use crate::prelude::*;
 mod box_triangle;
 pub use self::box_triangle::{ BoxFilter, TriangleFilter };
 
+mod gaussian;
+pub use self::gaussian::GaussianFilter;
+
+mod mitchell;
+pub use self::mitchell::MitchellFilter;
+
+mod sinc;
+pub use self::sinc::LanczosSincFilter;
+
 pub trait Filter {
     fn radius(&self) -> Vector2f;
 
     fn radius_inv(&self) -> Vector2f;
 
+    /// `p` is relative to the center of the filter.
+    /// Some filters are negative in places, which sharpens the image.
     fn evaluate(&self, p: Point2f) -> Float;
 }
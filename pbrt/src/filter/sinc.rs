@@ -0,0 +1,57 @@
+use crate::prelude::*;
+use super::Filter;
+
+/// A sinc filter windowed by a wider sinc, which rings less than truncating it.
+#[derive(Clone, Debug)]
+pub struct LanczosSincFilter {
+    radius: Vector2f,
+    radius_inv: Vector2f,
+    /// the number of cycles of the sinc that fit in the window
+    tau: Float,
+}
+
+impl LanczosSincFilter {
+    pub fn new(radius: Vector2f, tau: Float) -> Self {
+        Self {
+            radius,
+            radius_inv: Vector2f::new(
+                float(1.0) / radius.x,
+                float(1.0) / radius.y,
+            ),
+            tau,
+        }
+    }
+
+    fn windowed_sinc(&self, x: Float, radius: Float) -> Float {
+        let x = x.abs();
+        if x > radius {
+            return float(0.0);
+        }
+
+        sinc(x) * sinc(x / self.tau)
+    }
+}
+
+impl Filter for LanczosSincFilter {
+    fn radius(&self) -> Vector2f {
+        self.radius
+    }
+
+    fn radius_inv(&self) -> Vector2f {
+        self.radius_inv
+    }
+
+    fn evaluate(&self, p: Point2f) -> Float {
+        self.windowed_sinc(p.x, self.radius.x) * self.windowed_sinc(p.y, self.radius.y)
+    }
+}
+
+fn sinc(x: Float) -> Float {
+    let x = x.abs();
+    if x < 1e-5 {
+        return float(1.0);
+    }
+
+    let x = x * Float::pi();
+    x.sin() / x
+}
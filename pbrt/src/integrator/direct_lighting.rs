@@ -5,6 +5,7 @@ use super::utils::*;
 
 use crate::camera::Camera;
 use crate::math::*;
+use crate::memory::MemoryArena;
 use crate::sampler::Sampler;
 use crate::scene::Scene;
 use crate::bxdf::TransportMode;
@@ -23,12 +24,12 @@ pub struct DirectLightingParIntegratorData {
 }
 
 impl ParIntegratorData for DirectLightingParIntegratorData {
-    fn li(&self, mut ray: RayDifferential, scene: &Scene, sampler: &mut dyn Sampler, arena: &(), depth: i32) -> Spectrum {
+    fn li(&self, mut ray: RayDifferential, scene: &Scene, sampler: &mut dyn Sampler, arena: &MemoryArena, depth: i32) -> Spectrum {
         let mut l = Spectrum::new(0.0);
 
         if let Some(mut isect) = scene.intersect(&mut ray) {
             let mode = TransportMode::Radiance;
-            isect.compute_scattering_functions(&ray, &(), mode, false);
+            isect.compute_scattering_functions(&ray, arena, mode, false);
 
             let wo = isect.wo;
             l += isect.le(&wo);
@@ -42,8 +43,8 @@ impl ParIntegratorData for DirectLightingParIntegratorData {
             }
 
             if depth + 1 < self.max_depth {
-                l += specular_reflect(self, &ray, &isect, &scene, sampler, &arena, depth);
-                l += specular_transmit(self, &ray, &isect, &scene, sampler, &arena, depth);
+                l += specular_reflect(self, &ray, &isect, &scene, sampler, arena, depth);
+                l += specular_transmit(self, &ray, &isect, &scene, sampler, arena, depth);
             }
         } else {
             for light in &*scene.lights {
@@ -1,3 +1,4 @@
+use std::io;
 use crate::scene::Scene;
 use crate::sampler::Sampler;
 
@@ -10,13 +11,20 @@ pub use self::normal::{ NormalIntegrator };
 mod direct_lighting;
 pub use self::direct_lighting::{ DirectLightingIntegrator, LightStrategy };
 
+mod path;
+pub use self::path::PathIntegrator;
+
+mod volpath;
+pub use self::volpath::VolPathIntegrator;
+
 mod whitted;
 pub use self::whitted::WhittedIntegrator;
 
 pub mod utils;
 
 pub trait Integrator {
-    fn render(&mut self, scene: Scene);
+    /// Renders the scene and writes the film's image, returning any error from writing it.
+    fn render(&mut self, scene: Scene) -> io::Result<()>;
 
     fn preprocess(&mut self, _scene: &Scene, _sampler: &mut dyn Sampler) {
 
@@ -5,6 +5,7 @@ use super::utils::*;
 
 use crate::camera::Camera;
 use crate::math::*;
+use crate::memory::MemoryArena;
 use crate::sampler::Sampler;
 use crate::scene::Scene;
 use crate::bxdf::TransportMode;
@@ -14,7 +15,7 @@ use super::{ ParIntegratorData, SamplerIntegrator };
 pub struct NormalParIntegratorData;
 
 impl ParIntegratorData for NormalParIntegratorData {
-    fn li(&self, mut ray: RayDifferential, scene: &Scene, sampler: &mut dyn Sampler, arena: &(), depth: i32) -> Spectrum {
+    fn li(&self, mut ray: RayDifferential, scene: &Scene, sampler: &mut dyn Sampler, arena: &MemoryArena, depth: i32) -> Spectrum {
         let mut l = Spectrum::new(1.0);
 
         if let Some(isect) = scene.intersect(&mut ray) {
@@ -0,0 +1,118 @@
+use std::sync::Arc;
+use crate::prelude::*;
+use super::utils::*;
+
+use crate::camera::Camera;
+use crate::math::*;
+use crate::memory::MemoryArena;
+use crate::sampler::Sampler;
+use crate::scene::Scene;
+use super::{ LightStrategy, ParIntegratorData, SamplerIntegrator };
+
+pub struct PathParIntegratorData {
+    max_depth: i32,
+    rr_threshold: Float,
+    light_strategy: LightStrategy,
+    n_light_samples: Arc<Vec<u32>>,
+}
+
+impl ParIntegratorData for PathParIntegratorData {
+    fn li(&self, ray: RayDifferential, scene: &Scene, sampler: &mut dyn Sampler, arena: &MemoryArena, _depth: i32) -> Spectrum {
+        let mut path = PathState::default();
+        let mut ray = ray;
+
+        let mut bounces = 0;
+        loop {
+            // intersect ray with scene and store intersection in isect
+            let found_intersection = scene.intersect(&mut ray);
+
+            // possibly add emitted light at intersection
+            path.add_emitted(bounces, found_intersection.as_ref(), &ray, scene);
+
+            // terminate path if ray escaped or max_depth was reached
+            let mut isect = match found_intersection {
+                Some(isect) => isect,
+                None => break,
+            };
+
+            if bounces >= self.max_depth {
+                break;
+            }
+
+            match path.surface_bounce(&mut isect, &mut ray, scene, sampler, arena, self.light_strategy, &self.n_light_samples, false) {
+                SurfaceBounce::Boundary => continue,
+                SurfaceBounce::Scattered => (),
+                SurfaceBounce::Absorbed => break,
+            }
+
+            if !path.russian_roulette(bounces, self.rr_threshold, sampler) {
+                break;
+            }
+
+            bounces += 1;
+        }
+
+        path.l
+    }
+}
+
+pub struct PathIntegrator {
+    max_depth: i32,
+    rr_threshold: Float,
+    light_strategy: LightStrategy,
+    camera: Arc<dyn Camera + Send + Sync>,
+    sampler: Box<dyn Sampler>,
+    n_light_samples: Arc<Vec<u32>>,
+}
+
+impl PathIntegrator {
+    /// Paths with a throughput below `rr_threshold` are randomly terminated after a few bounces.
+    pub fn new(max_depth: i32, rr_threshold: Float, light_strategy: LightStrategy, camera: Arc<dyn Camera + Send + Sync>, sampler: Box<dyn Sampler>) -> Self {
+        Self {
+            max_depth,
+            rr_threshold,
+            light_strategy,
+            camera,
+            sampler,
+            n_light_samples: Arc::new(vec![]),
+        }
+    }
+
+    pub fn max_depth(&self) -> i32 {
+        self.max_depth
+    }
+
+    pub fn light_strategy(&self) -> LightStrategy {
+        self.light_strategy
+    }
+}
+
+impl SamplerIntegrator for PathIntegrator {
+    type ParIntegratorData = PathParIntegratorData;
+
+    fn camera(&self) -> Arc<dyn Camera + Send + Sync> {
+        self.camera.clone()
+    }
+
+    fn sampler(&self) -> &dyn Sampler {
+        self.sampler.as_ref()
+    }
+
+    fn sampler_mut(&mut self) -> &mut dyn Sampler {
+        self.sampler.as_mut()
+    }
+
+    fn preprocess(&mut self, scene: &Scene, sampler: &mut dyn Sampler) {
+        let n_light_samples = request_light_samples(self.light_strategy, self.max_depth, scene, sampler);
+        self.n_light_samples = Arc::new(n_light_samples);
+    }
+
+    fn par_data(&self) -> Self::ParIntegratorData {
+        PathParIntegratorData {
+            max_depth: self.max_depth,
+            rr_threshold: self.rr_threshold,
+            light_strategy: self.light_strategy,
+            n_light_samples: self.n_light_samples.clone(),
+        }
+    }
+}
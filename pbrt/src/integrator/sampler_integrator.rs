@@ -1,16 +1,18 @@
 use std::cmp;
+use std::io;
 use std::sync::Arc;
 use rayon::prelude::*;
 
 use crate::prelude::*;
 use crate::camera::Camera;
 use crate::math::*;
+use crate::memory::MemoryArena;
 use crate::sampler::Sampler;
 use crate::scene::Scene;
 use super::Integrator;
 
 pub trait ParIntegratorData: Send {
-    fn li(&self, ray: RayDifferential, scene: &Scene, sampler: &mut dyn Sampler, arena: &(), depth: i32) -> Spectrum;
+    fn li(&self, ray: RayDifferential, scene: &Scene, sampler: &mut dyn Sampler, arena: &MemoryArena, depth: i32) -> Spectrum;
 }
 
 pub trait SamplerIntegrator: Integrator {
@@ -26,7 +28,7 @@ pub trait SamplerIntegrator: Integrator {
 
     }
 
-    fn render(&mut self, scene: Arc<Scene>) {
+    fn render(&mut self, scene: Arc<Scene>) -> io::Result<()> {
         const TILE_SIZE: i32 = 16;
 
         <Self as SamplerIntegrator>::preprocess(self, &*scene, self.sampler().create_new(0).as_mut());
@@ -64,7 +66,7 @@ pub trait SamplerIntegrator: Integrator {
                 let tile = Point2i::new(x, y);
 
                 // allocate MemoryArena for tile
-                let arena = ();
+                let mut arena = MemoryArena::new();
 
                 // compute sample bounds for tile
                 let x0 = sample_bounds.min.x + tile.x * TILE_SIZE;
@@ -112,7 +114,7 @@ pub trait SamplerIntegrator: Integrator {
                             film_tile.add_sample(camera_sample.film, l, ray_weight);
 
                             // free MemoryArena memory from computing image sample value
-                            // arena.reset();
+                            arena.reset();
                         }
                     }
                 }
@@ -125,18 +127,16 @@ pub trait SamplerIntegrator: Integrator {
                 };
             });
 
-        {
-            let camera = self.camera();
-            let film = camera.film();
-            let film = film.lock().unwrap();
-            film.write_image(float(1.0));
-        }
+        let camera = self.camera();
+        let film = camera.film();
+        let film = film.lock().unwrap();
+        film.write_image(float(1.0))
     }
 }
 
 impl<T: SamplerIntegrator> Integrator for T {
-    fn render(&mut self, scene: Scene) {
-        <Self as SamplerIntegrator>::render(self, Arc::new(scene));
+    fn render(&mut self, scene: Scene) -> io::Result<()> {
+        <Self as SamplerIntegrator>::render(self, Arc::new(scene))
     }
 
     fn preprocess(&mut self, scene: &Scene, sampler: &mut dyn Sampler) {
@@ -5,16 +5,17 @@ use itertools::izip;
 use num;
 
 use crate::prelude::*;
-use crate::bxdf::BxdfType;
+use crate::bxdf::{ BxdfType, TransportMode };
 use crate::light::Light;
 use crate::math::*;
+use crate::memory::MemoryArena;
 use crate::sampler::Sampler;
 use crate::sampling::utils::*;
 use crate::scene::Scene;
 use crate::interaction::{ Interactions, SurfaceInteraction };
-use super::ParIntegratorData;
+use super::{ LightStrategy, ParIntegratorData };
 
-pub fn uniform_sample_all_lights(isect: &(impl Into<Interactions<'a>> + Clone), scene: &Scene, sampler: &mut dyn Sampler, arena: &(), n_samples: &[u32], handle_media: bool) -> Spectrum {
+pub fn uniform_sample_all_lights(isect: &(impl Into<Interactions<'a>> + Clone), scene: &Scene, sampler: &mut dyn Sampler, arena: &MemoryArena, n_samples: &[u32], handle_media: bool) -> Spectrum {
     let mut l = Spectrum::new(0.0);
 
     for (light, s_i) in izip!(&*scene.lights, n_samples) {
@@ -44,14 +45,14 @@ pub fn uniform_sample_all_lights(isect: &(impl Into<Interactions<'a>> + Clone),
     l
 }
 
-pub fn uniform_sample_one_light(isect: &(impl Into<Interactions<'a>> + Clone), scene: &Scene, sampler: &mut dyn Sampler, arena: &(), handle_media: bool) -> Spectrum {
+pub fn uniform_sample_one_light(isect: &(impl Into<Interactions<'a>> + Clone), scene: &Scene, sampler: &mut dyn Sampler, arena: &MemoryArena, handle_media: bool) -> Spectrum {
     // randomly choose a single light to sample
     let n_lights = scene.lights.len();
     if n_lights == 0 {
         return Spectrum::new(0.0);
     }
 
-    let light_num = min(sampler.get_1d().raw() as usize * n_lights, n_lights - 1);
+    let light_num = min((sampler.get_1d() * float(n_lights)).raw() as usize, n_lights - 1);
     let light = &scene.lights[light_num];
 
     let u_light = sampler.get_2d();
@@ -60,8 +61,39 @@ pub fn uniform_sample_one_light(isect: &(impl Into<Interactions<'a>> + Clone), s
     estimate_direct(isect, u_scattering, light, u_light, scene, sampler, arena, handle_media, false) / float(n_lights)
 }
 
+/// Samples direct lighting at `isect` with `strategy`,
+/// where `n_light_samples` are the counts from `request_light_samples`.
+pub fn sample_lights(isect: &(impl Into<Interactions<'a>> + Clone), scene: &Scene, sampler: &mut dyn Sampler, arena: &MemoryArena, strategy: LightStrategy, n_light_samples: &[u32], handle_media: bool) -> Spectrum {
+    match strategy {
+        LightStrategy::UniformSampleAll => uniform_sample_all_lights(isect, scene, sampler, arena, n_light_samples, handle_media),
+        LightStrategy::UniformSampleOne => uniform_sample_one_light(isect, scene, sampler, arena, handle_media),
+    }
+}
+
+/// The number of samples to use for each light with `UniformSampleAll`, after requesting
+/// their sample arrays for every bounce of a path. Other strategies don't need any.
+pub fn request_light_samples(strategy: LightStrategy, max_depth: i32, scene: &Scene, sampler: &mut dyn Sampler) -> Vec<u32> {
+    let mut n_light_samples = vec![];
+    if strategy == LightStrategy::UniformSampleAll {
+        // compute number of samples to use for each light
+        for light in &*scene.lights {
+            n_light_samples.push(sampler.round_count(light.num_samples()));
+        }
+
+        // request samples for sampling all lights at every bounce
+        for _ in 0..max_depth {
+            for (_, samples) in izip!(scene.lights.iter(), n_light_samples.iter()) {
+                sampler.request_2d_vec(*samples);
+                sampler.request_2d_vec(*samples);
+            }
+        }
+    }
+
+    n_light_samples
+}
+
 #[cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
-pub fn estimate_direct(isect: &(impl Into<Interactions<'a>> + Clone), u_scattering: Point2f, light: &Arc<dyn Light + Send + Sync>, u_light: Point2f, scene: &Scene, sampler: &mut dyn Sampler, _arena: &(), handle_media: bool, specular: bool) -> Spectrum {
+pub fn estimate_direct(isect: &(impl Into<Interactions<'a>> + Clone), u_scattering: Point2f, light: &Arc<dyn Light + Send + Sync>, u_light: Point2f, scene: &Scene, sampler: &mut dyn Sampler, _arena: &MemoryArena, handle_media: bool, specular: bool) -> Spectrum {
     let isect: Interactions<'_> = (*isect).clone().into();
     let flags = if specular {
         BxdfType::all()
@@ -91,9 +123,11 @@ pub fn estimate_direct(isect: &(impl Into<Interactions<'a>> + Clone), u_scatteri
                     ) * light_sample.wi.dot(*isect.shading.n).abs();
                 scattering_pdf = bsdf.pdf(isect.wo, light_sample.wi, flags);
             }
-        } else {
+        } else if let Some(mi) = isect.get_medium() {
             // evaluate phase function for light sampling strategy
-            unimplemented!()
+            let p = mi.phase.p(mi.wo, light_sample.wi);
+            f = Spectrum::new(p);
+            scattering_pdf = p;
         }
 
         if vis.is_some() && !f.is_black() {
@@ -120,6 +154,7 @@ pub fn estimate_direct(isect: &(impl Into<Interactions<'a>> + Clone), u_scatteri
     // sample BSDF with multiple importance sampling
     if !light.is_delta_light() {
         let mut f = Spectrum::new(0.0);
+        let mut wi = Vector3f::zero();
 
         // was a delta distribution sampled?
         // specular is always a delta distribution
@@ -131,20 +166,23 @@ pub fn estimate_direct(isect: &(impl Into<Interactions<'a>> + Clone), u_scatteri
             if let Some(bsdf) = &isect.bsdf {
                 if let Some(sample) = bsdf.sample_f(isect.wo, u_scattering, flags) {
                     f = sample.li * sample.wi.dot(*isect.shading.n).abs();
+                    wi = sample.wi;
                     sampled_specular = sample.ty.map_or(false, |ty| ty.contains(BxdfType::Specular));
                     scattering_pdf = sample.pdf;
                 }
             }
-        } else {
-            unimplemented!()
+        } else if let Some(mi) = isect.get_medium() {
+            // sample scattered direction for medium interactions
+            let (p, sampled_wi) = mi.phase.sample_p(mi.wo, u_scattering);
+            f = Spectrum::new(p);
+            wi = sampled_wi;
+            scattering_pdf = p;
         }
 
         if !f.is_black() && scattering_pdf > 0.0 {
             // account for light contributions along wi
             let weight = if !sampled_specular {
-                //let light_pdf = light.pdf_li(isect, light_sample.wi);
-                // todo - for point lights this is 0.0
-                let light_pdf = float(0.0);
+                let light_pdf = light.pdf_li(&isect, wi);
                 if light_pdf == 0.0 {
                     return ld;
                 }
@@ -155,15 +193,12 @@ pub fn estimate_direct(isect: &(impl Into<Interactions<'a>> + Clone), u_scatteri
             };
 
             // find intersection & compute transmittance
-            let mut ray = isect.get_base().spawn_ray(&light_sample.wi);
-
-            let tr = Spectrum::new(1.0);
+            let mut ray = isect.get_base().spawn_ray(&wi);
 
-            let found_surface_interaction = if handle_media {
-                //scene.intersect_tr(ray, sampler);
-                None
+            let (found_surface_interaction, tr) = if handle_media {
+                scene.intersect_tr(ray, sampler)
             } else {
-                scene.intersect(&mut ray)
+                (scene.intersect(&mut ray), Spectrum::new(1.0))
             };
 
             // add light contribution from material sampling
@@ -173,7 +208,7 @@ pub fn estimate_direct(isect: &(impl Into<Interactions<'a>> + Clone), u_scatteri
                     if let Some(area_light) = primitive.get_area_light() {
                         // if the two Arc<Light>s point to the same light
                         if Arc::ptr_eq(&area_light, light) {
-                            li = l_isect.le(&-light_sample.wi);
+                            li = l_isect.le(&-wi);
                         }
                     }
                 }
@@ -190,7 +225,7 @@ pub fn estimate_direct(isect: &(impl Into<Interactions<'a>> + Clone), u_scatteri
     ld
 }
 
-pub fn specular_reflect(integrator: &impl ParIntegratorData, ray: &RayDifferential, isect: &SurfaceInteraction<'_>, scene: &Scene, sampler: &mut dyn Sampler, arena: &(), depth: i32) -> Spectrum {
+pub fn specular_reflect(integrator: &impl ParIntegratorData, ray: &RayDifferential, isect: &SurfaceInteraction<'_>, scene: &Scene, sampler: &mut dyn Sampler, arena: &MemoryArena, depth: i32) -> Spectrum {
     // compute specular reflection direction wi and bsdf value
     let wo = isect.wo;
 
@@ -247,7 +282,7 @@ pub fn specular_reflect(integrator: &impl ParIntegratorData, ray: &RayDifferenti
     }
 }
 
-pub fn specular_transmit(integrator: &impl ParIntegratorData, ray: &RayDifferential, isect: &SurfaceInteraction<'_>, scene: &Scene, sampler: &mut dyn Sampler, arena: &(), depth: i32) -> Spectrum {
+pub fn specular_transmit(integrator: &impl ParIntegratorData, ray: &RayDifferential, isect: &SurfaceInteraction<'_>, scene: &Scene, sampler: &mut dyn Sampler, arena: &MemoryArena, depth: i32) -> Spectrum {
     // compute specular reflection direction wi and bsdf value
     let wo = isect.wo;
 
@@ -314,3 +349,133 @@ pub fn specular_transmit(integrator: &impl ParIntegratorData, ray: &RayDifferent
         Spectrum::new(0.0)
     }
 }
+
+/// What happened to a path at a surface, see `PathState::surface_bounce`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SurfaceBounce {
+    /// the surface only separates two media, so the path carries on through it without a bounce
+    Boundary,
+    Scattered,
+    /// sampling the BSDF didn't give a direction that carries any light
+    Absorbed,
+}
+
+/// The radiance and throughput of a path, for the path tracing integrators.
+pub struct PathState {
+    pub l: Spectrum,
+    pub beta: Spectrum,
+    pub specular_bounce: bool,
+    /// tracks the accumulated effect of radiance scaling due to refraction,
+    /// so that russian roulette isn't affected by it
+    pub eta_scale: Float,
+}
+
+impl Default for PathState {
+    fn default() -> Self {
+        Self {
+            l: Spectrum::new(0.0),
+            beta: Spectrum::new(1.0),
+            specular_bounce: false,
+            eta_scale: float(1.0),
+        }
+    }
+}
+
+impl PathState {
+    /// Adds the light emitted at the surface that `ray` hit, or by the lights it escaped to.
+    pub fn add_emitted(&mut self, bounces: i32, isect: Option<&SurfaceInteraction<'_>>, ray: &RayDifferential, scene: &Scene) {
+        // emission is already accounted for by direct lighting at the
+        // previous vertex, unless that vertex was specular or this is the camera ray
+        if bounces > 0 && !self.specular_bounce {
+            return;
+        }
+
+        match isect {
+            Some(isect) => self.l += self.beta * isect.le(&-ray.direction),
+            None => for light in &*scene.lights {
+                self.l += self.beta * light.le(ray);
+            },
+        }
+    }
+
+    /// Samples direct lighting at `isect`, then its BSDF for the path's next direction,
+    /// which replaces `ray`.
+    #[cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
+    pub fn surface_bounce(
+        &mut self,
+        isect: &mut SurfaceInteraction<'a>,
+        ray: &mut RayDifferential,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+        arena: &'a MemoryArena,
+        strategy: LightStrategy,
+        n_light_samples: &[u32],
+        handle_media: bool,
+    ) -> SurfaceBounce {
+        // compute scattering functions and skip over medium boundaries
+        isect.compute_scattering_functions(ray, arena, TransportMode::Radiance, true);
+
+        let bsdf = match &isect.bsdf {
+            Some(bsdf) => bsdf,
+            None => {
+                *ray = RayDifferential::from_ray(isect.spawn_ray(&ray.direction));
+                return SurfaceBounce::Boundary;
+            },
+        };
+
+        // sample illumination from lights to find path contribution
+        // (but skip this for perfectly specular BSDFs)
+        let mut non_specular = BxdfType::all();
+        non_specular.remove(BxdfType::Specular);
+
+        if bsdf.num_components(non_specular) > 0 {
+            self.l += self.beta * sample_lights(&*isect, scene, sampler, arena, strategy, n_light_samples, handle_media);
+        }
+
+        // sample BSDF to get new path direction
+        let wo = -ray.direction;
+        let sample = match bsdf.sample_f(wo, sampler.get_2d(), BxdfType::all()) {
+            Some(sample) => sample,
+            None => return SurfaceBounce::Absorbed,
+        };
+
+        if sample.li.is_black() || sample.pdf == 0.0 {
+            return SurfaceBounce::Absorbed;
+        }
+
+        self.beta *= sample.li * sample.wi.dot(*isect.shading.n).abs() / sample.pdf;
+
+        let ty = sample.ty.unwrap_or_else(BxdfType::empty);
+        self.specular_bounce = ty.contains(BxdfType::Specular);
+
+        if ty.contains(BxdfType::Specular) && ty.contains(BxdfType::Transmission) {
+            let eta = bsdf.eta;
+            let n = isect.n.unwrap_or(isect.shading.n);
+
+            self.eta_scale *= if wo.dot(*n) > 0.0 {
+                eta * eta
+            } else {
+                float(1.0) / (eta * eta)
+            };
+        }
+
+        *ray = RayDifferential::from_ray(isect.spawn_ray(&sample.wi));
+        SurfaceBounce::Scattered
+    }
+
+    /// Possibly terminates the path with russian roulette, once it's made a few bounces
+    /// and its throughput is below `rr_threshold`. Returns whether the path carries on.
+    pub fn russian_roulette(&mut self, bounces: i32, rr_threshold: Float, sampler: &mut dyn Sampler) -> bool {
+        let rr_beta = self.beta * self.eta_scale;
+        if rr_beta.max_component_value() < rr_threshold && bounces > 3 {
+            let q = max(float(0.05), float(1.0) - rr_beta.max_component_value());
+            if sampler.get_1d() < q {
+                return false;
+            }
+
+            self.beta /= float(1.0) - q;
+        }
+
+        true
+    }
+}
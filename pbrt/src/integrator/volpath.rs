@@ -0,0 +1,148 @@
+use std::sync::Arc;
+use crate::prelude::*;
+use super::utils::*;
+
+use crate::camera::Camera;
+use crate::math::*;
+use crate::memory::MemoryArena;
+use crate::sampler::Sampler;
+use crate::scene::Scene;
+use super::{ LightStrategy, ParIntegratorData, SamplerIntegrator };
+
+pub struct VolPathParIntegratorData {
+    max_depth: i32,
+    rr_threshold: Float,
+    light_strategy: LightStrategy,
+    n_light_samples: Arc<Vec<u32>>,
+}
+
+impl ParIntegratorData for VolPathParIntegratorData {
+    fn li(&self, ray: RayDifferential, scene: &Scene, sampler: &mut dyn Sampler, arena: &MemoryArena, _depth: i32) -> Spectrum {
+        let mut path = PathState::default();
+        let mut ray = ray;
+
+        let mut bounces = 0;
+        loop {
+            // intersect ray with scene and store intersection in isect
+            let found_intersection = scene.intersect(&mut ray);
+
+            // sample the participating medium, if any, up to the intersection
+            let mut medium_interaction = None;
+            if let Some(medium) = ray.medium {
+                let (weight, mi) = scene.medium(medium).sample(&ray, sampler, arena);
+                path.beta *= weight;
+                medium_interaction = mi;
+            }
+
+            if path.beta.is_black() {
+                break;
+            }
+
+            if let Some(mi) = medium_interaction {
+                // handle scattering at a point in the medium
+                if bounces >= self.max_depth {
+                    break;
+                }
+
+                path.l += path.beta * sample_lights(&mi, scene, sampler, arena, self.light_strategy, &self.n_light_samples, true);
+
+                // the phase function's value is its pdf, so sampling it leaves beta unchanged
+                let (_, wi) = mi.phase.sample_p(mi.wo, sampler.get_2d());
+                ray = RayDifferential::from_ray(mi.spawn_ray(&wi));
+                path.specular_bounce = false;
+            } else {
+                // handle scattering at a point on a surface
+
+                // possibly add emitted light at intersection
+                path.add_emitted(bounces, found_intersection.as_ref(), &ray, scene);
+
+                // terminate path if ray escaped or max_depth was reached
+                let mut isect = match found_intersection {
+                    Some(isect) => isect,
+                    None => break,
+                };
+
+                if bounces >= self.max_depth {
+                    break;
+                }
+
+                match path.surface_bounce(&mut isect, &mut ray, scene, sampler, arena, self.light_strategy, &self.n_light_samples, true) {
+                    SurfaceBounce::Boundary => continue,
+                    SurfaceBounce::Scattered => (),
+                    SurfaceBounce::Absorbed => break,
+                }
+            }
+
+            if !path.russian_roulette(bounces, self.rr_threshold, sampler) {
+                break;
+            }
+
+            bounces += 1;
+        }
+
+        path.l
+    }
+}
+
+/// A path tracer that also scatters light in participating media,
+/// and accounts for their transmittance when sampling lights.
+pub struct VolPathIntegrator {
+    max_depth: i32,
+    rr_threshold: Float,
+    light_strategy: LightStrategy,
+    camera: Arc<dyn Camera + Send + Sync>,
+    sampler: Box<dyn Sampler>,
+    n_light_samples: Arc<Vec<u32>>,
+}
+
+impl VolPathIntegrator {
+    /// Paths with a throughput below `rr_threshold` are randomly terminated after a few bounces.
+    pub fn new(max_depth: i32, rr_threshold: Float, light_strategy: LightStrategy, camera: Arc<dyn Camera + Send + Sync>, sampler: Box<dyn Sampler>) -> Self {
+        Self {
+            max_depth,
+            rr_threshold,
+            light_strategy,
+            camera,
+            sampler,
+            n_light_samples: Arc::new(vec![]),
+        }
+    }
+
+    pub fn max_depth(&self) -> i32 {
+        self.max_depth
+    }
+
+    pub fn light_strategy(&self) -> LightStrategy {
+        self.light_strategy
+    }
+}
+
+impl SamplerIntegrator for VolPathIntegrator {
+    type ParIntegratorData = VolPathParIntegratorData;
+
+    fn camera(&self) -> Arc<dyn Camera + Send + Sync> {
+        self.camera.clone()
+    }
+
+    fn sampler(&self) -> &dyn Sampler {
+        self.sampler.as_ref()
+    }
+
+    fn sampler_mut(&mut self) -> &mut dyn Sampler {
+        self.sampler.as_mut()
+    }
+
+    fn preprocess(&mut self, scene: &Scene, sampler: &mut dyn Sampler) {
+        let n_light_samples = request_light_samples(self.light_strategy, self.max_depth, scene, sampler);
+        self.n_light_samples = Arc::new(n_light_samples);
+    }
+
+    fn par_data(&self) -> Self::ParIntegratorData {
+        VolPathParIntegratorData {
+            max_depth: self.max_depth,
+            rr_threshold: self.rr_threshold,
+            light_strategy: self.light_strategy,
+            n_light_samples: self.n_light_samples.clone(),
+        }
+    }
+}
@@ -4,6 +4,7 @@ use crate::prelude::*;
 
 use crate::camera::Camera;
 use crate::math::*;
+use crate::memory::MemoryArena;
 use crate::sampler::Sampler;
 use crate::scene::Scene;
 use crate::bxdf::{ BxdfType, TransportMode };
@@ -14,7 +15,7 @@ pub struct WhittedParIntegratorData {
 }
 
 impl ParIntegratorData for WhittedParIntegratorData {
-    fn li(&self, mut ray: RayDifferential, scene: &Scene, sampler: &mut dyn Sampler, arena: &(), depth: i32) -> Spectrum {
+    fn li(&self, mut ray: RayDifferential, scene: &Scene, sampler: &mut dyn Sampler, arena: &MemoryArena, depth: i32) -> Spectrum {
         let mut l = Spectrum::new(0.0);
 
         if let Some(mut isect) = scene.intersect(&mut ray) {
@@ -23,7 +24,7 @@ impl ParIntegratorData for WhittedParIntegratorData {
             let wo = isect.wo;
 
             // compute scattering fn for surface interaction
-            isect.compute_scattering_functions(&ray, &arena, TransportMode::Radiance, false);
+            isect.compute_scattering_functions(&ray, arena, TransportMode::Radiance, false);
 
             // compute emitted light if ray hit area light source
             l += isect.le(&wo);
@@ -51,8 +52,8 @@ impl ParIntegratorData for WhittedParIntegratorData {
 
             if depth + 1 < self.max_depth {
                 // trace rays for specular reflection & refraction
-                l += super::utils::specular_reflect(self, &ray, &isect, &scene, sampler, &arena, depth);
-                l += super::utils::specular_transmit(self, &ray, &isect, &scene, sampler, &arena, depth);
+                l += super::utils::specular_reflect(self, &ray, &isect, &scene, sampler, arena, depth);
+                l += super::utils::specular_transmit(self, &ray, &isect, &scene, sampler, arena, depth);
             }
 
         } else {
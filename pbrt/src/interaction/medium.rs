@@ -0,0 +1,33 @@
+use cgmath::prelude::*;
+use shrinkwraprs::Shrinkwrap;
+
+use crate::prelude::*;
+use crate::medium::{ MediumInterface, PhaseFunction };
+use super::BaseInteraction;
+
+/// A point inside a medium where light is scattered.
+#[derive(Clone, Debug, Shrinkwrap)]
+#[shrinkwrap(mutable)]
+pub struct MediumInteraction<'a> {
+    #[shrinkwrap(main_field)]
+    pub interaction: BaseInteraction,
+    pub phase: &'a dyn PhaseFunction,
+}
+
+impl MediumInteraction<'a> {
+    pub fn new(p: Point3f, wo: Vector3f, time: Float, medium_interface: MediumInterface, phase: &'a dyn PhaseFunction) -> Self {
+        let interaction = BaseInteraction {
+            p,
+            time,
+            p_err: Vector3f::zero(),
+            wo: wo.normalize(),
+            n: None,
+            medium_interface,
+        };
+
+        Self {
+            interaction,
+            phase,
+        }
+    }
+}
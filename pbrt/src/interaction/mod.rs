@@ -1,6 +1,10 @@
 use cgmath::prelude::*;
 use crate::prelude::*;
 use crate::bxdf::BxdfType;
+use crate::medium::{ MediumId, MediumInterface };
+
+mod medium;
+pub use self::medium::MediumInteraction;
 
 mod surface;
 pub use self::surface::*;
@@ -9,6 +13,7 @@ pub use self::surface::*;
 pub enum Interactions<'a> {
     Interaction(BaseInteraction),
     SurfaceInteraction(SurfaceInteraction<'a>),
+    MediumInteraction(MediumInteraction<'a>),
 }
 
 impl Interactions<'_> {
@@ -16,13 +21,21 @@ impl Interactions<'_> {
         match self {
             Interactions::Interaction(isect) => isect,
             Interactions::SurfaceInteraction(isect) => &**isect,
+            Interactions::MediumInteraction(isect) => &**isect,
         }
     }
 
     pub fn get_surface(&self) -> Option<&SurfaceInteraction<'_>> {
         match self {
-            Interactions::Interaction(_) => None,
             Interactions::SurfaceInteraction(isect) => Some(isect),
+            _ => None,
+        }
+    }
+
+    pub fn get_medium(&self) -> Option<&MediumInteraction<'_>> {
+        match self {
+            Interactions::MediumInteraction(isect) => Some(isect),
+            _ => None,
         }
     }
 }
@@ -39,6 +52,12 @@ impl From<SurfaceInteraction<'a>> for Interactions<'a> {
     }
 }
 
+impl From<MediumInteraction<'a>> for Interactions<'a> {
+    fn from(mi: MediumInteraction<'a>) -> Self {
+        Interactions::MediumInteraction(mi)
+    }
+}
+
 impl Into<BaseInteraction> for Interactions<'a> {
     fn into(self) -> BaseInteraction {
         self.get_base().clone()
@@ -52,11 +71,11 @@ pub struct BaseInteraction {
     pub p_err: Vector3f,
     pub wo: Vector3f,
     pub n: Option<Normal>,
-    pub medium: Option<()>,
+    pub medium_interface: MediumInterface,
 }
 
 impl BaseInteraction {
-    pub fn new(p: Point3f, n: Normal, p_err: Vector3f, wo: Vector3f, time: Float, medium: Option<()>) -> Self {
+    pub fn new(p: Point3f, n: Normal, p_err: Vector3f, wo: Vector3f, time: Float, medium_interface: MediumInterface) -> Self {
         let wo = wo.normalize();
 
         Self {
@@ -65,7 +84,7 @@ impl BaseInteraction {
             p_err,
             wo,
             n: Some(n),
-            medium,
+            medium_interface,
         }
     }
 
@@ -73,12 +92,25 @@ impl BaseInteraction {
         self.n.map_or(false, |n| n != Normal::zero())
     }
 
+    /// The medium that a ray leaving in the direction `w` travels through.
+    pub fn get_medium(&self, w: &Vector3f) -> Option<MediumId> {
+        match self.n {
+            Some(n) if self.is_surface_interaction() => if w.dot(*n) > 0.0 {
+                self.medium_interface.outside
+            } else {
+                self.medium_interface.inside
+            },
+            _ => self.medium_interface.inside,
+        }
+    }
+
     pub fn spawn_ray(&self, dir: &Vector3f) -> Ray {
         let n = self.n.unwrap_or_else(Normal::zero);
         let o = offset_ray_origin(&self.p, &self.p_err, &n, dir);
 
         let mut ray = Ray::new(o, *dir);
         ray.time = self.time;
+        ray.medium = self.get_medium(dir);
         ray
     }
 
@@ -92,7 +124,7 @@ impl BaseInteraction {
         let mut ray = Ray::new(o, d);
         ray.max = float(1.0 - SHADOW_EPSILON);
         ray.time = self.time;
-        // todo: ray.medium = GetMedium(d);
+        ray.medium = self.get_medium(&d);
         ray
     }
 }
@@ -3,6 +3,8 @@ use shrinkwraprs::Shrinkwrap;
 
 use crate::prelude::*;
 use crate::math::*;
+use crate::medium::MediumInterface;
+use crate::memory::MemoryArena;
 use crate::bxdf::{ Bsdf, TransportMode };
 use crate::primitive::Primitive;
 use crate::shape::Shape;
@@ -27,7 +29,7 @@ pub struct SurfaceInteraction<'a> {
     pub shape: Option<&'a dyn Shape>,
     pub primitive: Option<&'a dyn Primitive>,
     pub shading: Shading,
-    pub bsdf: Option<Bsdf>,
+    pub bsdf: Option<&'a Bsdf<'a>>,
     pub bssrdf: Option<()>,
 }
 
@@ -53,7 +55,7 @@ impl SurfaceInteraction<'a> {
             n = (-*n).into();
         }
 
-        let interaction = BaseInteraction::new(p, n, p_err, wo, time, None);
+        let interaction = BaseInteraction::new(p, n, p_err, wo, time, MediumInterface::default());
 
         // init shading geom from true geom
         let shading = Shading {
@@ -91,13 +93,17 @@ impl SurfaceInteraction<'a> {
 
         // adjust normal based on orientation & handiness
         if self.shape.as_ref().map_or(false, |s| s.reverse_orientation() ^ s.transform_swaps_handedness()) {
-            *n = (-*n).into();
+            n = (-*n).into();
         }
 
+        self.shading.n = n;
+
+        // make sure the true normal and the shading normal lie in the same hemisphere
+        let geometric = self.n.unwrap();
         if orientation_is_authoritative {
-            self.n = Some(n.face_forward(self.shading.n));
+            self.n = Some(geometric.face_forward(self.shading.n));
         } else {
-            self.shading.n = self.shading.n.face_forward(n);
+            self.shading.n = self.shading.n.face_forward(geometric);
         }
 
         // initialize shading partial derivative values
@@ -107,7 +113,7 @@ impl SurfaceInteraction<'a> {
         self.shading.dndv = dndvs;
     }
 
-    pub fn compute_scattering_functions(&mut self, ray: &RayDifferential, arena: &(), mode: TransportMode, allow_multiple_lobes: bool) {
+    pub fn compute_scattering_functions(&mut self, ray: &RayDifferential, arena: &'a MemoryArena, mode: TransportMode, allow_multiple_lobes: bool) {
         self.compute_differentials(ray);
 
         match &self.primitive {
@@ -196,13 +202,11 @@ impl SurfaceInteraction<'a> {
         }
     }
 
-    pub fn le(&self, _ray: &Vector3f) -> Spectrum {
-        if let Some(prim) = &self.primitive {
-            if let Some(_area) = prim.get_area_light() {
-                unimplemented!()
-            }
+    /// The radiance emitted in the direction `w` if this is a point on an area light.
+    pub fn le(&self, w: &Vector3f) -> Spectrum {
+        match self.primitive.and_then(|p| p.get_area_light()) {
+            Some(area) => area.l(&self.interaction, w),
+            None => Spectrum::new(0.0),
         }
-
-        Spectrum::new(0.0)
     }
 }
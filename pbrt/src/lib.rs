@@ -22,8 +22,12 @@ pub mod filter;
 pub mod interaction;
 pub mod integrator;
 pub mod light;
+pub mod loader;
 pub mod material;
+pub mod medium;
 pub mod math;
+pub mod memory;
+pub mod parser;
 pub mod primitive;
 pub mod sampler;
 pub mod sampling;
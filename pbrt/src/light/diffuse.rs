@@ -0,0 +1,116 @@
+use std::sync::Arc;
+use cgmath::prelude::*;
+use crate::prelude::*;
+use super::{ Light, LightType, VisibilityTester };
+use crate::interaction::{ Interactions, BaseInteraction, Sample };
+use crate::math::Transform;
+use crate::medium::MediumInterface;
+use crate::shape::Shape;
+
+/// An area light that emits the same radiance from every point on its `Shape`.
+///
+/// The same `Arc` needs to be given to both the `GeometricPrimitive` for the shape
+/// and the `Scene`, so that rays that hit the shape can be matched up with the light.
+#[derive(Debug)]
+pub struct DiffuseAreaLight {
+    l_emit: Spectrum,
+    shape: Arc<dyn Shape + Send + Sync>,
+    two_sided: bool,
+    area: Float,
+    n_samples: u32,
+}
+
+impl DiffuseAreaLight {
+    pub fn new(l_emit: Spectrum, n_samples: u32, shape: Arc<dyn Shape + Send + Sync>, two_sided: bool) -> Self {
+        Self {
+            l_emit,
+            area: shape.area(),
+            shape,
+            two_sided,
+            n_samples,
+        }
+    }
+}
+
+impl Light for DiffuseAreaLight {
+    fn ty(&self) -> LightType {
+        LightType::Area
+    }
+
+    fn num_samples(&self) -> u32 {
+        self.n_samples
+    }
+
+    fn medium_interface(&self) -> MediumInterface {
+        MediumInterface::default()
+    }
+
+    fn light_to_world(&self) -> &Transform {
+        self.shape.object_to_world()
+    }
+
+    fn world_to_light(&self) -> &Transform {
+        self.shape.world_to_object()
+    }
+
+    fn le(&self, _: &Ray) -> Spectrum {
+        Spectrum::new(0.0)
+    }
+
+    fn l(&self, isect: &BaseInteraction, w: &Vector3f) -> Spectrum {
+        let n = isect.n.unwrap_or_else(Normal::zero);
+
+        if self.two_sided || (*n).dot(*w) > 0.0 {
+            self.l_emit
+        } else {
+            Spectrum::new(0.0)
+        }
+    }
+
+    fn sample_li(&self, isect: &Interactions<'a>, u: Point2f) -> (Sample, Option<VisibilityTester>) {
+        let reference = isect.get_base();
+        let (mut p_shape, pdf) = self.shape.sample_solid_angle(reference, u);
+        p_shape.time = reference.time;
+        p_shape.medium_interface = reference.medium_interface;
+
+        let no_sample = Sample {
+            li: Spectrum::new(0.0),
+            wi: Vector3f::zero(),
+            pdf: float(0.0),
+            ty: None,
+        };
+
+        if pdf == 0.0 || (p_shape.p - reference.p).magnitude2() == 0.0 {
+            return (no_sample, None);
+        }
+
+        let wi = (p_shape.p - reference.p).normalize();
+        let li = self.l(&p_shape, &-wi);
+
+        let vis = if li.is_black() {
+            None
+        } else {
+            Some(VisibilityTester::new(reference.clone(), p_shape))
+        };
+
+        (
+            Sample {
+                li,
+                wi,
+                pdf,
+                ty: None,
+            },
+            vis,
+        )
+    }
+
+    fn pdf_li(&self, isect: &Interactions<'a>, wi: Vector3f) -> Float {
+        self.shape.pdf_solid_angle(isect.get_base(), wi)
+    }
+
+    fn power(&self) -> Spectrum {
+        let sides = if self.two_sided { float(2.0) } else { float(1.0) };
+
+        self.l_emit * sides * self.area * Float::pi()
+    }
+}
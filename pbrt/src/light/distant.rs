@@ -0,0 +1,101 @@
+use std::sync::Arc;
+use atomic::{ Atomic, Ordering };
+use cgmath::prelude::*;
+use crate::prelude::*;
+use super::{ Light, LightType, VisibilityTester };
+use crate::interaction::{ Interactions, BaseInteraction, Sample };
+use crate::math::Transform;
+use crate::medium::MediumInterface;
+use crate::scene::Scene;
+
+/// Light arriving from a single direction, like sunlight.
+#[derive(Debug)]
+pub struct DistantLight {
+    light_to_world: Arc<Transform>,
+    world_to_light: Arc<Transform>,
+    l: Spectrum,
+    /// the direction that the light arrives *from*, in world space
+    w_light: Vector3f,
+    // these are set by `preprocess`
+    world_center: Atomic<Point3f>,
+    world_radius: Atomic<Float>,
+}
+
+impl DistantLight {
+    /// `w_light` is in light space, and points towards the light.
+    pub fn new(l: Spectrum, w_light: Vector3f, light_to_world: Arc<Transform>) -> Self {
+        Self {
+            l,
+            w_light: light_to_world.transform_vector(w_light).normalize(),
+            world_to_light: Arc::new(light_to_world.inverse()),
+            light_to_world,
+            world_center: Atomic::new(Point3f::origin()),
+            world_radius: Atomic::new(float(0.0)),
+        }
+    }
+}
+
+impl Light for DistantLight {
+    fn ty(&self) -> LightType {
+        LightType::DeltaDirection
+    }
+
+    fn medium_interface(&self) -> MediumInterface {
+        MediumInterface::default()
+    }
+
+    fn light_to_world(&self) -> &Transform {
+        &self.light_to_world
+    }
+
+    fn world_to_light(&self) -> &Transform {
+        &self.world_to_light
+    }
+
+    fn preprocess(&self, scene: &Scene) {
+        let (center, radius) = scene.world_bound().bounding_sphere();
+        self.world_center.store(center, Ordering::SeqCst);
+        self.world_radius.store(radius, Ordering::SeqCst);
+    }
+
+    fn le(&self, _: &Ray) -> Spectrum {
+        Spectrum::new(0.0)
+    }
+
+    fn sample_li(&self, isect: &Interactions<'a>, _sample: Point2f) -> (Sample, Option<VisibilityTester>) {
+        let isect = isect.get_base();
+
+        // a point that is definitely outside of the scene
+        let world_radius = self.world_radius.load(Ordering::SeqCst);
+        let p_outside = isect.p + self.w_light * (float(2.0) * world_radius);
+
+        let vis = VisibilityTester::new(isect.clone(), BaseInteraction {
+            p: p_outside,
+            time: isect.time,
+            p_err: Vector3f::zero(),
+            wo: Vector3f::zero(),
+            n: None,
+            medium_interface: self.medium_interface(),
+        });
+
+        (
+            Sample {
+                li: self.l,
+                pdf: float(1.0),
+                wi: self.w_light,
+                ty: None,
+            },
+            Some(vis),
+        )
+    }
+
+    fn pdf_li(&self, _isect: &Interactions<'a>, _wi: Vector3f) -> Float {
+        float(0.0)
+    }
+
+    /// The power arriving at a disk that covers the whole scene.
+    fn power(&self) -> Spectrum {
+        let world_radius = self.world_radius.load(Ordering::SeqCst);
+        self.l * Float::pi() * world_radius * world_radius
+    }
+}
@@ -0,0 +1,145 @@
+use std::sync::Arc;
+use cgmath::prelude::*;
+use crate::prelude::*;
+use super::{ Light, LightType, VisibilityTester };
+use crate::interaction::{ Interactions, BaseInteraction, Sample };
+use crate::loader::IesProfile;
+use crate::math::Transform;
+use crate::medium::MediumInterface;
+use crate::texture::{ ImageWrap, MipMap };
+
+/// The resolution of the intensity map that IES profiles are resampled into.
+const IES_MAP_WIDTH: usize = 512;
+const IES_MAP_HEIGHT: usize = 256;
+
+/// A point light whose intensity varies with direction,
+/// given by a latitude-longitude map.
+///
+/// As in pbrt, the top of the map is the light's `+y` axis, and the left edge is at `+x`.
+#[derive(Debug)]
+pub struct GoniometricLight {
+    light_to_world: Arc<Transform>,
+    world_to_light: Arc<Transform>,
+    position: Point3f,
+    intensity: Spectrum,
+    mipmap: MipMap<Spectrum>,
+    /// the integral of the map over the sphere of directions
+    map_integral: Spectrum,
+}
+
+impl GoniometricLight {
+    /// `texels` is in scanline order, starting at the top, and is scaled by `intensity`.
+    pub fn new(intensity: Spectrum, resolution: Point2i, texels: Vec<Spectrum>, light_to_world: Arc<Transform>) -> Self {
+        let width = resolution.x as usize;
+        let height = resolution.y as usize;
+
+        // each texel covers a patch of the sphere with solid angle sin(theta) dtheta dphi
+        let d_theta = Float::pi() / float(height as f32);
+        let d_phi = float(2.0) * Float::pi() / float(width as f32);
+        let mut map_integral = Spectrum::new(0.0);
+        for (y, row) in texels.chunks(width).enumerate() {
+            let sin_theta = ((float(y as f32) + float(0.5)) * d_theta).sin();
+            for texel in row {
+                map_integral += *texel * (sin_theta * d_theta * d_phi);
+            }
+        }
+
+        Self {
+            intensity,
+            mipmap: MipMap::new(resolution, texels, true, float(8.0), ImageWrap::Repeat),
+            map_integral,
+            world_to_light: Arc::new(light_to_world.inverse()),
+            position: light_to_world.transform_point(Point3f::zero()),
+            light_to_world,
+        }
+    }
+
+    /// Resamples the candela values from an IES profile.
+    ///
+    /// The luminaire points down the light's `-y` axis,
+    /// with the horizontal angle of 0 along `+x` and 90 along `+z`.
+    pub fn from_ies(intensity: Spectrum, profile: &IesProfile, light_to_world: Arc<Transform>) -> Self {
+        let mut texels = Vec::with_capacity(IES_MAP_WIDTH * IES_MAP_HEIGHT);
+        for y in 0..IES_MAP_HEIGHT {
+            // the top row is straight up, which is a vertical angle of 180
+            let theta = (float(y as f32) + float(0.5)) / float(IES_MAP_HEIGHT as f32) * float(180.0);
+            let vertical = float(180.0) - theta;
+
+            for x in 0..IES_MAP_WIDTH {
+                let horizontal = (float(x as f32) + float(0.5)) / float(IES_MAP_WIDTH as f32) * float(360.0);
+                texels.push(Spectrum::new(profile.intensity(vertical, horizontal)));
+            }
+        }
+
+        let resolution = Point2i::new(IES_MAP_WIDTH as i32, IES_MAP_HEIGHT as i32);
+        Self::new(intensity, resolution, texels, light_to_world)
+    }
+
+    fn scale(&self, w: Vector3f) -> Spectrum {
+        let wl = self.world_to_light.transform_vector(w).normalize();
+        // the map's poles are on the y axis
+        let wl = Vector3f::new(wl.x, wl.z, wl.y);
+        let st = Point2f::new(
+            spherical_phi(wl) / (float(2.0) * Float::pi()),
+            spherical_theta(wl) / Float::pi(),
+        );
+
+        self.mipmap.lookup_trilinear(st, float(0.0))
+    }
+}
+
+impl Light for GoniometricLight {
+    fn ty(&self) -> LightType {
+        LightType::DeltaPosition
+    }
+
+    fn medium_interface(&self) -> MediumInterface {
+        MediumInterface::default()
+    }
+
+    fn light_to_world(&self) -> &Transform {
+        &self.light_to_world
+    }
+
+    fn world_to_light(&self) -> &Transform {
+        &self.world_to_light
+    }
+
+    fn le(&self, _: &Ray) -> Spectrum {
+        Spectrum::new(0.0)
+    }
+
+    fn sample_li(&self, isect: &Interactions<'a>, _sample: Point2f) -> (Sample, Option<VisibilityTester>) {
+        let isect = isect.get_base();
+
+        let wi = (self.position - isect.p).normalize();
+        let vis = VisibilityTester::new(isect.clone(), BaseInteraction {
+            p: self.position,
+            time: isect.time,
+            p_err: Vector3f::zero(),
+            wo: Vector3f::zero(),
+            n: None,
+            medium_interface: self.medium_interface(),
+        });
+
+        let li = self.intensity * self.scale(-wi) / self.position.distance2(isect.p);
+
+        (
+            Sample {
+                li,
+                pdf: float(1.0),
+                wi,
+                ty: None,
+            },
+            Some(vis),
+        )
+    }
+
+    fn pdf_li(&self, _isect: &Interactions<'a>, _wi: Vector3f) -> Float {
+        float(0.0)
+    }
+
+    fn power(&self) -> Spectrum {
+        self.intensity * self.map_integral
+    }
+}
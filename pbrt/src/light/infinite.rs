@@ -0,0 +1,181 @@
+use std::cmp::min;
+use std::sync::Arc;
+use atomic::{ Atomic, Ordering };
+use cgmath::prelude::*;
+use crate::prelude::*;
+use super::{ Light, LightType, VisibilityTester };
+use crate::interaction::{ Interactions, BaseInteraction, Sample };
+use crate::math::Transform;
+use crate::medium::MediumInterface;
+use crate::sampling::Distribution2d;
+use crate::scene::Scene;
+use crate::texture::{ ImageWrap, MipMap };
+
+/// Light arriving from infinitely far away in every direction,
+/// given by an environment map in latitude-longitude format.
+///
+/// The `+z` axis in light space points at the top of the image.
+#[derive(Debug)]
+pub struct InfiniteAreaLight {
+    light_to_world: Arc<Transform>,
+    world_to_light: Arc<Transform>,
+    l_map: MipMap<Spectrum>,
+    distribution: Distribution2d,
+    n_samples: u32,
+    // these are set by `preprocess`
+    world_center: Atomic<Point3f>,
+    world_radius: Atomic<Float>,
+}
+
+impl InfiniteAreaLight {
+    /// `texels` is in scanline order, starting at the top of the image,
+    /// and is scaled by `l`.
+    pub fn new(l: Spectrum, n_samples: u32, resolution: Point2i, texels: Vec<Spectrum>, light_to_world: Arc<Transform>) -> Self {
+        let texels = texels.into_iter().map(|t| t * l).collect();
+        let l_map = MipMap::new(resolution, texels, true, float(8.0), ImageWrap::Repeat);
+
+        // compute scalar-valued image from the environment map,
+        // weighted by sin(theta) to account for the distortion of the lat-long mapping
+        let width = 2 * l_map.width();
+        let height = 2 * l_map.height();
+        let filter_width = float(0.5) / float(min(width, height) as f32);
+
+        let mut img = Vec::with_capacity(width * height);
+        for v in 0..height {
+            let vp = (float(v as f32) + float(0.5)) / float(height as f32);
+            let sin_theta = (Float::pi() * vp).sin();
+
+            for u in 0..width {
+                let up = (float(u as f32) + float(0.5)) / float(width as f32);
+                img.push(l_map.lookup_trilinear(Point2f::new(up, vp), filter_width).y() * sin_theta);
+            }
+        }
+
+        Self {
+            world_to_light: Arc::new(light_to_world.inverse()),
+            light_to_world,
+            l_map,
+            distribution: Distribution2d::new(&img, width, height),
+            n_samples,
+            world_center: Atomic::new(Point3f::origin()),
+            world_radius: Atomic::new(float(0.0)),
+        }
+    }
+
+    /// A light with the same radiance `l` from every direction.
+    pub fn constant(l: Spectrum, n_samples: u32, light_to_world: Arc<Transform>) -> Self {
+        Self::new(l, n_samples, Point2i::new(1, 1), vec![Spectrum::new(1.0)], light_to_world)
+    }
+
+    fn lookup(&self, st: Point2f) -> Spectrum {
+        self.l_map.lookup_trilinear(st, float(0.0))
+    }
+}
+
+impl Light for InfiniteAreaLight {
+    fn ty(&self) -> LightType {
+        LightType::Infinite
+    }
+
+    fn num_samples(&self) -> u32 {
+        self.n_samples
+    }
+
+    fn medium_interface(&self) -> MediumInterface {
+        MediumInterface::default()
+    }
+
+    fn light_to_world(&self) -> &Transform {
+        &self.light_to_world
+    }
+
+    fn world_to_light(&self) -> &Transform {
+        &self.world_to_light
+    }
+
+    fn preprocess(&self, scene: &Scene) {
+        let (center, radius) = scene.world_bound().bounding_sphere();
+        self.world_center.store(center, Ordering::SeqCst);
+        self.world_radius.store(radius, Ordering::SeqCst);
+    }
+
+    fn le(&self, ray: &Ray) -> Spectrum {
+        let w = self.world_to_light.transform_vector(ray.direction).normalize();
+        let st = Point2f::new(
+            spherical_phi(w) / (float(2.0) * Float::pi()),
+            spherical_theta(w) / Float::pi(),
+        );
+
+        self.lookup(st)
+    }
+
+    fn sample_li(&self, isect: &Interactions<'a>, u: Point2f) -> (Sample, Option<VisibilityTester>) {
+        let reference = isect.get_base();
+
+        let no_sample = Sample {
+            li: Spectrum::new(0.0),
+            wi: Vector3f::zero(),
+            pdf: float(0.0),
+            ty: None,
+        };
+
+        // find (u, v) sample coordinates in infinite light texture
+        let sample = self.distribution.sample_continuous(u);
+        if sample.pdf == 0.0 {
+            return (no_sample, None);
+        }
+        let uv = sample.value;
+
+        // convert infinite light sample point to direction
+        let theta = uv.y * Float::pi();
+        let phi = uv.x * float(2.0) * Float::pi();
+        let sin_theta = theta.sin();
+        if sin_theta == 0.0 {
+            return (no_sample, None);
+        }
+        let wi = self.light_to_world.transform_vector(spherical_direction(sin_theta, theta.cos(), phi));
+
+        // compute pdf for sampled infinite light direction
+        let pdf = sample.pdf / (float(2.0) * Float::pi() * Float::pi() * sin_theta);
+
+        let world_radius = self.world_radius.load(Ordering::SeqCst);
+        let vis = VisibilityTester::new(reference.clone(), BaseInteraction {
+            p: reference.p + wi * (float(2.0) * world_radius),
+            time: reference.time,
+            p_err: Vector3f::zero(),
+            wo: Vector3f::zero(),
+            n: None,
+            medium_interface: self.medium_interface(),
+        });
+
+        (
+            Sample {
+                li: self.lookup(uv),
+                wi,
+                pdf,
+                ty: None,
+            },
+            Some(vis),
+        )
+    }
+
+    fn pdf_li(&self, _isect: &Interactions<'a>, w: Vector3f) -> Float {
+        let wi = self.world_to_light.transform_vector(w).normalize();
+        let theta = spherical_theta(wi);
+        let phi = spherical_phi(wi);
+
+        let sin_theta = theta.sin();
+        if sin_theta == 0.0 {
+            return float(0.0);
+        }
+
+        let st = Point2f::new(phi / (float(2.0) * Float::pi()), theta / Float::pi());
+        self.distribution.pdf(st) / (float(2.0) * Float::pi() * Float::pi() * sin_theta)
+    }
+
+    fn power(&self) -> Spectrum {
+        let world_radius = self.world_radius.load(Ordering::SeqCst);
+        self.l_map.lookup_trilinear(Point2f::new(float(0.5), float(0.5)), float(0.5)) *
+            (Float::pi() * world_radius * world_radius)
+    }
+}
@@ -6,10 +6,29 @@ use crate::interaction::{ Interactions, BaseInteraction, Sample };
 use crate::sampler::Sampler;
 use crate::math::*;
 use crate::math::Transform;
+use crate::medium::MediumInterface;
+
+mod diffuse;
+pub use self::diffuse::DiffuseAreaLight;
+
+mod distant;
+pub use self::distant::DistantLight;
+
+mod goniometric;
+pub use self::goniometric::GoniometricLight;
+
+mod infinite;
+pub use self::infinite::InfiniteAreaLight;
 
 mod point;
 pub use self::point::PointLight;
 
+mod projection;
+pub use self::projection::ProjectionLight;
+
+mod spot;
+pub use self::spot::SpotLight;
+
 bitflags! {
     pub struct LightType: u8 {
         /// The light uses a Delta Function for its position.
@@ -33,24 +52,36 @@ pub trait Light: Debug {
         1
     }
 
-    fn medium_interface(&self) -> Option<()>;
+    /// The media on either side of the light, which is a vacuum unless the light is inside a medium.
+    fn medium_interface(&self) -> MediumInterface;
 
     fn light_to_world(&self) -> &Transform;
 
     fn world_to_light(&self) -> &Transform;
 
-    fn preprocess(&mut self, _: &Scene) {
+    fn preprocess(&self, _: &Scene) {
 
     }
 
+    /// The radiance carried by a ray that escapes the scene.
     fn le(&self, ray: &Ray) -> Spectrum;
 
+    /// The radiance emitted from the point `isect` on the surface of an area light
+    /// in the direction `w`.
+    /// Lights that aren't attached to a surface never emit any.
+    fn l(&self, _isect: &BaseInteraction, _w: &Vector3f) -> Spectrum {
+        Spectrum::new(0.0)
+    }
+
     /// Returns the radiance arriving at the `isect` point and time,
     /// assuming there are no occluding objects between them.
     /// The `VisibilityTester` is not returned if the radiance is black,
     /// as in this case, visibility is irrelevant.
     fn sample_li(&self, isect: &Interactions<'a>, sample: Point2f) -> (Sample, Option<VisibilityTester>);
 
+    /// The pdf with respect to solid angle of `sample_li` choosing the direction `wi` from `isect`.
+    fn pdf_li(&self, isect: &Interactions<'a>, wi: Vector3f) -> Float;
+
     fn power(&self) -> Spectrum;
 }
 
@@ -75,9 +106,11 @@ impl VisibilityTester {
         !scene.intersect_p(&self.ray())
     }
 
-    pub fn tr(&self, scene: &Scene, _sampler: &dyn Sampler) -> Spectrum {
+    /// The transmittance between the two points, passing through surfaces without
+    /// a material, which only mark the boundaries of media.
+    pub fn tr(&self, scene: &Scene, sampler: &mut dyn Sampler) -> Spectrum {
         let mut ray = self.ray();
-        let tr = Spectrum::new(1.0);
+        let mut tr = Spectrum::new(1.0);
 
         loop {
             let isect = scene.intersect(&mut ray);
@@ -90,9 +123,9 @@ impl VisibilityTester {
                 }
             }
 
-            // todo - transmittance for current ray segment in medium
-            if let Some(_medium) = ray.medium {
-                // tr *= ray.medium.tr(ray, sampler)
+            // transmittance for current ray segment in medium
+            if let Some(medium) = ray.medium {
+                tr *= scene.medium(medium).tr(&ray, sampler);
             }
 
             // if no intersection is found then the ray has got to
@@ -4,6 +4,7 @@ use crate::prelude::*;
 use super::{ Light, LightType, VisibilityTester };
 use crate::interaction::{ Interactions, BaseInteraction, Sample };
 use crate::math::Transform;
+use crate::medium::MediumInterface;
 
 #[derive(Debug)]
 pub struct PointLight {
@@ -29,8 +30,8 @@ impl Light for PointLight {
         LightType::DeltaPosition
     }
 
-    fn medium_interface(&self) -> Option<()> {
-        None
+    fn medium_interface(&self) -> MediumInterface {
+        MediumInterface::default()
     }
 
     fn light_to_world(&self) -> &Transform {
@@ -59,7 +60,7 @@ impl Light for PointLight {
             p_err: Vector3f::zero(),
             wo: Vector3f::zero(),
             n: None,
-            medium: self.medium_interface(),
+            medium_interface: self.medium_interface(),
         });
 
         let li = self.spectrum / self.position.distance2(isect.p);
@@ -75,6 +76,10 @@ impl Light for PointLight {
         )
     }
 
+    fn pdf_li(&self, _isect: &Interactions<'a>, _wi: Vector3f) -> Float {
+        float(0.0)
+    }
+
     fn power(&self) -> Spectrum {
         self.spectrum * float(4.0) * Float::pi()
     }
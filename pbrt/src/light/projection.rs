@@ -0,0 +1,161 @@
+use std::sync::Arc;
+use cgmath::Rad;
+use cgmath::prelude::*;
+use crate::prelude::*;
+use super::{ Light, LightType, VisibilityTester };
+use crate::interaction::{ Interactions, BaseInteraction, Sample };
+use crate::math::Transform;
+use crate::medium::MediumInterface;
+use crate::texture::{ ImageWrap, MipMap };
+
+/// A point light that projects an image along its `+z` axis, like a slide projector.
+#[derive(Debug)]
+pub struct ProjectionLight {
+    light_to_world: Arc<Transform>,
+    world_to_light: Arc<Transform>,
+    position: Point3f,
+    intensity: Spectrum,
+    projection_map: Option<MipMap<Spectrum>>,
+    /// the image plane at `z = 1`, with the shorter axis spanning `[-1, 1]`
+    screen_bounds: Bounds2f,
+    /// `tan(fov / 2)`
+    tan_half_fov: Float,
+    hither: Float,
+    cos_total_width: Float,
+}
+
+impl ProjectionLight {
+    /// `fov` is the field of view of the image's shorter axis.
+    /// `image` is in scanline order, starting at the top, and has linear values,
+    /// so 8-bit sRGB images have to be converted first.
+    /// Without one the light projects a white rectangle.
+    pub fn new(
+        intensity: Spectrum,
+        image: Option<(Point2i, Vec<Spectrum>)>,
+        fov: impl Into<Rad<Float>>,
+        light_to_world: Arc<Transform>,
+    ) -> Self {
+        let (aspect, projection_map) = match image {
+            Some((resolution, texels)) => {
+                let aspect = float(resolution.x as f32) / float(resolution.y as f32);
+                let mipmap = MipMap::new(resolution, texels, true, float(8.0), ImageWrap::Black);
+                (aspect, Some(mipmap))
+            },
+            None => (float(1.0), None),
+        };
+
+        let screen_bounds = if aspect > 1.0 {
+            Bounds2f::new(Point2f::new(-aspect, float(-1.0)), Point2f::new(aspect, float(1.0)))
+        } else {
+            Bounds2f::new(Point2f::new(float(-1.0), float(-1.0) / aspect), Point2f::new(float(1.0), float(1.0) / aspect))
+        };
+
+        // compute cosine of cone surrounding projection directions
+        let tan_half_fov = (fov.into().0 / float(2.0)).tan();
+        let tan_diag = tan_half_fov * (float(1.0) + float(1.0) / (aspect * aspect)).sqrt();
+        let cos_total_width = tan_diag.atan().cos();
+
+        Self {
+            intensity,
+            projection_map,
+            screen_bounds,
+            tan_half_fov,
+            hither: float(1e-3),
+            cos_total_width,
+            world_to_light: Arc::new(light_to_world.inverse()),
+            position: light_to_world.transform_point(Point3f::zero()),
+            light_to_world,
+        }
+    }
+
+    /// The fraction of the light's intensity that is projected in the direction `w`.
+    fn projection(&self, w: Vector3f) -> Spectrum {
+        let wl = self.world_to_light.transform_vector(w);
+
+        // discard directions behind projection light
+        if wl.z < self.hither {
+            return Spectrum::new(0.0);
+        }
+
+        // project point onto projection plane and compute light
+        let p = Point2f::new(wl.x / (wl.z * self.tan_half_fov), wl.y / (wl.z * self.tan_half_fov));
+        let bounds = &self.screen_bounds;
+        if p.x < bounds.min.x || p.x > bounds.max.x || p.y < bounds.min.y || p.y > bounds.max.y {
+            return Spectrum::new(0.0);
+        }
+
+        match &self.projection_map {
+            Some(map) => {
+                // image rows go from the top down
+                let st = Point2f::new(
+                    (p.x - bounds.min.x) / (bounds.max.x - bounds.min.x),
+                    (bounds.max.y - p.y) / (bounds.max.y - bounds.min.y),
+                );
+                map.lookup_trilinear(st, float(0.0))
+            },
+            None => Spectrum::new(1.0),
+        }
+    }
+}
+
+impl Light for ProjectionLight {
+    fn ty(&self) -> LightType {
+        LightType::DeltaPosition
+    }
+
+    fn medium_interface(&self) -> MediumInterface {
+        MediumInterface::default()
+    }
+
+    fn light_to_world(&self) -> &Transform {
+        &self.light_to_world
+    }
+
+    fn world_to_light(&self) -> &Transform {
+        &self.world_to_light
+    }
+
+    fn le(&self, _: &Ray) -> Spectrum {
+        Spectrum::new(0.0)
+    }
+
+    fn sample_li(&self, isect: &Interactions<'a>, _sample: Point2f) -> (Sample, Option<VisibilityTester>) {
+        let isect = isect.get_base();
+
+        let wi = (self.position - isect.p).normalize();
+        let vis = VisibilityTester::new(isect.clone(), BaseInteraction {
+            p: self.position,
+            time: isect.time,
+            p_err: Vector3f::zero(),
+            wo: Vector3f::zero(),
+            n: None,
+            medium_interface: self.medium_interface(),
+        });
+
+        let li = self.intensity * self.projection(-wi) / self.position.distance2(isect.p);
+
+        (
+            Sample {
+                li,
+                pdf: float(1.0),
+                wi,
+                ty: None,
+            },
+            Some(vis),
+        )
+    }
+
+    fn pdf_li(&self, _isect: &Interactions<'a>, _wi: Vector3f) -> Float {
+        float(0.0)
+    }
+
+    /// Approximates the projected image by its average, spread over the cone around it.
+    fn power(&self) -> Spectrum {
+        let average = match &self.projection_map {
+            Some(map) => map.lookup_trilinear(Point2f::new(float(0.5), float(0.5)), float(0.5)),
+            None => Spectrum::new(1.0),
+        };
+
+        average * self.intensity * float(2.0) * Float::pi() * (float(1.0) - self.cos_total_width)
+    }
+}
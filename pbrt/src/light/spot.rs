@@ -0,0 +1,112 @@
+use std::sync::Arc;
+use cgmath::Rad;
+use cgmath::prelude::*;
+use crate::prelude::*;
+use super::{ Light, LightType, VisibilityTester };
+use crate::interaction::{ Interactions, BaseInteraction, Sample };
+use crate::math::Transform;
+use crate::medium::MediumInterface;
+
+/// A point light that shines in a cone around its `+z` axis.
+#[derive(Debug)]
+pub struct SpotLight {
+    light_to_world: Arc<Transform>,
+    world_to_light: Arc<Transform>,
+    position: Point3f,
+    intensity: Spectrum,
+    cos_total_width: Float,
+    cos_falloff_start: Float,
+}
+
+impl SpotLight {
+    /// The light is at full intensity inside `falloff_start`,
+    /// and smoothly falls off to nothing at `total_width`.
+    pub fn new(
+        intensity: Spectrum,
+        total_width: impl Into<Rad<Float>>,
+        falloff_start: impl Into<Rad<Float>>,
+        light_to_world: Arc<Transform>,
+    ) -> Self {
+        Self {
+            intensity,
+            cos_total_width: total_width.into().0.cos(),
+            cos_falloff_start: falloff_start.into().0.cos(),
+            world_to_light: Arc::new(light_to_world.inverse()),
+            position: light_to_world.transform_point(Point3f::zero()),
+            light_to_world,
+        }
+    }
+
+    fn falloff(&self, w: Vector3f) -> Float {
+        let wl = self.world_to_light.transform_vector(w).normalize();
+        let cos_theta = wl.z;
+
+        if cos_theta < self.cos_total_width {
+            return float(0.0);
+        }
+        if cos_theta >= self.cos_falloff_start {
+            return float(1.0);
+        }
+
+        // compute falloff inside spotlight cone
+        let delta = (cos_theta - self.cos_total_width) / (self.cos_falloff_start - self.cos_total_width);
+        delta.powi(4)
+    }
+}
+
+impl Light for SpotLight {
+    fn ty(&self) -> LightType {
+        LightType::DeltaPosition
+    }
+
+    fn medium_interface(&self) -> MediumInterface {
+        MediumInterface::default()
+    }
+
+    fn light_to_world(&self) -> &Transform {
+        &self.light_to_world
+    }
+
+    fn world_to_light(&self) -> &Transform {
+        &self.world_to_light
+    }
+
+    fn le(&self, _: &Ray) -> Spectrum {
+        Spectrum::new(0.0)
+    }
+
+    fn sample_li(&self, isect: &Interactions<'a>, _sample: Point2f) -> (Sample, Option<VisibilityTester>) {
+        let isect = isect.get_base();
+
+        let wi = (self.position - isect.p).normalize();
+        let vis = VisibilityTester::new(isect.clone(), BaseInteraction {
+            p: self.position,
+            time: isect.time,
+            p_err: Vector3f::zero(),
+            wo: Vector3f::zero(),
+            n: None,
+            medium_interface: self.medium_interface(),
+        });
+
+        let li = self.intensity * self.falloff(-wi) / self.position.distance2(isect.p);
+
+        (
+            Sample {
+                li,
+                pdf: float(1.0),
+                wi,
+                ty: None,
+            },
+            Some(vis),
+        )
+    }
+
+    fn pdf_li(&self, _isect: &Interactions<'a>, _wi: Vector3f) -> Float {
+        float(0.0)
+    }
+
+    fn power(&self) -> Spectrum {
+        self.intensity * float(2.0) * Float::pi() *
+            (float(1.0) - float(0.5) * (self.cos_falloff_start + self.cos_total_width))
+    }
+}
@@ -0,0 +1,152 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{ self, Read };
+use std::path::Path;
+use crate::prelude::*;
+
+#[derive(Debug)]
+pub enum DensityError {
+    Io(io::Error),
+    /// the file ended before all of the densities were read
+    UnexpectedEof,
+    InvalidValue(String),
+    /// one of the dimensions of the grid is zero, or there are too many voxels to count
+    InvalidResolution,
+    /// there are more values after the densities
+    TrailingData,
+}
+
+impl fmt::Display for DensityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DensityError::Io(err) => write!(f, "io error: {}", err),
+            DensityError::UnexpectedEof => write!(f, "unexpected end of file"),
+            DensityError::InvalidValue(value) => write!(f, "invalid value: {:?}", value),
+            DensityError::InvalidResolution => write!(f, "invalid resolution"),
+            DensityError::TrailingData => write!(f, "more values than the resolution needs"),
+        }
+    }
+}
+
+impl Error for DensityError {
+    fn cause(&self) -> Option<&dyn Error> {
+        match self {
+            DensityError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for DensityError {
+    fn from(err: io::Error) -> Self {
+        DensityError::Io(err)
+    }
+}
+
+/// A 3d grid of densities, for a `GridDensityMedium`.
+///
+/// The file is plain text, with `#` starting a comment that runs to the end of the line.
+/// It has the resolution of the grid in x, y and z, followed by a non-negative density for
+/// each voxel, with x changing fastest and z slowest.
+#[derive(Clone, Debug)]
+pub struct DensityGrid {
+    pub resolution: [usize; 3],
+    /// `resolution[0] * resolution[1] * resolution[2]` values, indexed by `(z * ny + y) * nx + x`
+    pub density: Vec<Float>,
+}
+
+impl DensityGrid {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, DensityError> {
+        let mut text = String::new();
+        File::open(path)?.read_to_string(&mut text)?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, DensityError> {
+        let mut tokens = text.lines()
+            .flat_map(|line| line.split('#').next().unwrap_or("").split_whitespace());
+
+        let mut resolution = [0; 3];
+        for r in resolution.iter_mut() {
+            let token = tokens.next().ok_or(DensityError::UnexpectedEof)?;
+            *r = token.parse()
+                .map_err(|_| DensityError::InvalidValue(token.to_string()))?;
+        }
+        if resolution.iter().any(|&r| r == 0) {
+            return Err(DensityError::InvalidResolution);
+        }
+
+        let n = resolution[0].checked_mul(resolution[1])
+            .and_then(|n| n.checked_mul(resolution[2]))
+            .ok_or(DensityError::InvalidResolution)?;
+        // the resolution comes from the file, so only reserve what its size allows
+        let mut density = Vec::with_capacity(n.min(text.len() / 2));
+        for _ in 0..n {
+            let token = tokens.next().ok_or(DensityError::UnexpectedEof)?;
+            let value: f64 = token.parse()
+                .map_err(|_| DensityError::InvalidValue(token.to_string()))?;
+            if !value.is_finite() || value < 0.0 {
+                return Err(DensityError::InvalidValue(token.to_string()));
+            }
+            density.push(float(value));
+        }
+
+        if tokens.next().is_some() {
+            return Err(DensityError::TrailingData);
+        }
+
+        Ok(Self {
+            resolution,
+            density,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let grid = DensityGrid::parse("# a 2x1x2 grid\n2 1 2\n0 0.5 # z = 0\n1 2.5\n").unwrap();
+        assert_eq!(grid.resolution, [2, 1, 2]);
+        assert_eq!(grid.density, vec![float(0.0), float(0.5), float(1.0), float(2.5)]);
+    }
+
+    #[test]
+    fn invalid_resolution() {
+        for text in &["0 1 1\n", "1 1 0 1\n", "4294967296 4294967296 4294967296 1\n", "18446744073709551615 2 1 1\n"] {
+            match DensityGrid::parse(text) {
+                Err(DensityError::InvalidResolution) => (),
+                other => panic!("expected an invalid resolution error for {:?}, got {:?}", text, other),
+            }
+        }
+
+        match DensityGrid::parse("1 -1 1\n1\n") {
+            Err(DensityError::InvalidValue(_)) => (),
+            other => panic!("expected an invalid value error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn invalid_densities() {
+        // a huge resolution with too few values runs out, without reserving them all first
+        match DensityGrid::parse("1000000 1000000 1000 1 2 3\n") {
+            Err(DensityError::UnexpectedEof) => (),
+            other => panic!("expected an unexpected eof error, got {:?}", other),
+        }
+
+        for value in &["-1", "nan", "inf", "x"] {
+            match DensityGrid::parse(&format!("1 1 2 1 {}\n", value)) {
+                Err(DensityError::InvalidValue(ref v)) if v == value => (),
+                other => panic!("expected an invalid value error for {}, got {:?}", value, other),
+            }
+        }
+
+        match DensityGrid::parse("1 1 1 1 2\n") {
+            Err(DensityError::TrailingData) => (),
+            other => panic!("expected a trailing data error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{ self, Read };
+use std::path::Path;
+use num;
+use crate::prelude::*;
+
+/// The most angles or tilt pairs a file can have, far more than any real profile uses.
+const MAX_COUNT: usize = 100_000;
+
+#[derive(Debug)]
+pub enum IesError {
+    Io(io::Error),
+    /// there is no `TILT=` line, which comes before the photometric data
+    MissingTilt,
+    /// the tilt data is in a separate file, which isn't supported
+    TiltFile(String),
+    /// the file ended before all of the photometric data was read
+    UnexpectedEof,
+    InvalidValue(String),
+    /// only type C photometry, which is used by nearly all luminaires, is supported
+    UnsupportedPhotometricType(i32),
+    /// the angles aren't in increasing order, or don't cover a range that can be used
+    InvalidAngles,
+}
+
+impl fmt::Display for IesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IesError::Io(err) => write!(f, "io error: {}", err),
+            IesError::MissingTilt => write!(f, "missing TILT line"),
+            IesError::TiltFile(file) => write!(f, "tilt files aren't supported: {:?}", file),
+            IesError::UnexpectedEof => write!(f, "unexpected end of file"),
+            IesError::InvalidValue(value) => write!(f, "invalid value: {:?}", value),
+            IesError::UnsupportedPhotometricType(ty) => write!(f, "unsupported photometric type {}", ty),
+            IesError::InvalidAngles => write!(f, "invalid angles"),
+        }
+    }
+}
+
+impl Error for IesError {
+    fn cause(&self) -> Option<&dyn Error> {
+        match self {
+            IesError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for IesError {
+    fn from(err: io::Error) -> Self {
+        IesError::Io(err)
+    }
+}
+
+/// How the candela values are mirrored around the luminaire, which depends on
+/// the range that the horizontal angles cover.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum IesSymmetry {
+    /// the same in every horizontal direction, there is only one horizontal angle
+    Rotational,
+    /// symmetric about the 0-180 and 90-270 planes, angles cover 0-90
+    Quadrant,
+    /// symmetric about the 0-180 plane, angles cover 0-180
+    Bilateral,
+    /// symmetric about the 90-270 plane, angles cover 90-270
+    BilateralLateral,
+    /// angles cover 0-360
+    None,
+}
+
+/// The luminous intensity distribution from an IES LM-63 photometric file.
+///
+/// Only type C photometry is supported, where vertical angles are measured from
+/// straight down (nadir), and horizontal angles go anticlockwise when looking down on the luminaire.
+#[derive(Clone, Debug)]
+pub struct IesProfile {
+    /// in degrees, increasing
+    pub vertical_angles: Vec<Float>,
+    /// in degrees, increasing
+    pub horizontal_angles: Vec<Float>,
+    /// in candela, a row of values for each vertical angle for each horizontal angle
+    pub candela: Vec<Vec<Float>>,
+    pub symmetry: IesSymmetry,
+}
+
+impl IesProfile {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, IesError> {
+        let mut text = String::new();
+        File::open(path)?.read_to_string(&mut text)?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, IesError> {
+        // skip the keywords, everything after the TILT line is numbers
+        let mut lines = text.lines();
+        let tilt = loop {
+            match lines.next() {
+                Some(line) if line.trim_left().starts_with("TILT=") => break line.trim()["TILT=".len()..].to_string(),
+                Some(_) => continue,
+                None => return Err(IesError::MissingTilt),
+            }
+        };
+
+        let rest = lines.collect::<Vec<_>>().join("\n");
+        let mut tokens = rest.split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|t| !t.is_empty());
+
+        let mut number = || -> Result<Float, IesError> {
+            let token = tokens.next().ok_or(IesError::UnexpectedEof)?;
+            // `Float` can't hold NaNs, and infinite values would poison the profile
+            token.parse::<FloatPrim>().ok()
+                .filter(|v| v.is_finite())
+                .map(float)
+                .ok_or_else(|| IesError::InvalidValue(token.to_string()))
+        };
+
+        match tilt.as_str() {
+            "NONE" => (),
+            "INCLUDE" => {
+                // lamp-to-luminaire geometry, then pairs of angles & multiplying factors,
+                // which only matter for lamps that aren't mounted as they were measured
+                number()?;
+                let n_tilt = count(number()?)?;
+                for _ in 0..2 * n_tilt {
+                    number()?;
+                }
+            },
+            file => return Err(IesError::TiltFile(file.to_string())),
+        }
+
+        let _n_lamps = number()?;
+        let _lumens_per_lamp = number()?;
+        let multiplier = number()?;
+        let n_vertical = count(number()?)?;
+        let n_horizontal = count(number()?)?;
+        let photometric_type = number()?.raw() as i32;
+        let _units = number()?;
+        let _width = number()?;
+        let _length = number()?;
+        let _height = number()?;
+        let ballast_factor = number()?;
+        let ballast_lamp_factor = number()?;
+        let _input_watts = number()?;
+
+        if photometric_type != 1 {
+            return Err(IesError::UnsupportedPhotometricType(photometric_type));
+        }
+        if n_vertical == 0 || n_horizontal == 0 {
+            return Err(IesError::InvalidAngles);
+        }
+
+        let vertical_angles = (0..n_vertical).map(|_| number()).collect::<Result<Vec<_>, _>>()?;
+        let horizontal_angles = (0..n_horizontal).map(|_| number()).collect::<Result<Vec<_>, _>>()?;
+
+        let scale = multiplier * ballast_factor * ballast_lamp_factor;
+        let mut candela = Vec::with_capacity(n_horizontal);
+        for _ in 0..n_horizontal {
+            let row = (0..n_vertical)
+                .map(|_| number().map(|c| c * scale))
+                .collect::<Result<Vec<_>, _>>()?;
+            candela.push(row);
+        }
+
+        let increasing = |angles: &[Float]| angles.windows(2).all(|w| w[0] < w[1]);
+        if !increasing(&vertical_angles) || !increasing(&horizontal_angles) {
+            return Err(IesError::InvalidAngles);
+        }
+
+        let first = horizontal_angles[0];
+        let last = horizontal_angles[n_horizontal - 1];
+        let symmetry = if n_horizontal == 1 {
+            IesSymmetry::Rotational
+        } else if first == 0.0 && last == 90.0 {
+            IesSymmetry::Quadrant
+        } else if first == 0.0 && last == 180.0 {
+            IesSymmetry::Bilateral
+        } else if first == 90.0 && last == 270.0 {
+            IesSymmetry::BilateralLateral
+        } else if first == 0.0 && last == 360.0 {
+            IesSymmetry::None
+        } else {
+            return Err(IesError::InvalidAngles);
+        };
+
+        Ok(Self {
+            vertical_angles,
+            horizontal_angles,
+            candela,
+            symmetry,
+        })
+    }
+
+    /// The intensity in candela at the vertical and horizontal angles, which are in degrees.
+    pub fn intensity(&self, vertical: Float, horizontal: Float) -> Float {
+        let first = self.vertical_angles[0];
+        let last = self.vertical_angles[self.vertical_angles.len() - 1];
+        if vertical < first || vertical > last {
+            return float(0.0);
+        }
+
+        if self.symmetry == IesSymmetry::Rotational {
+            return interpolate(&self.vertical_angles, &self.candela[0], vertical);
+        }
+
+        // map the horizontal angle into the range that is covered by the data
+        let h = horizontal % float(360.0);
+        let h = if h < 0.0 { h + float(360.0) } else { h };
+        let h = match self.symmetry {
+            IesSymmetry::Quadrant => {
+                let h = if h > 180.0 { float(360.0) - h } else { h };
+                if h > 90.0 { float(180.0) - h } else { h }
+            },
+            IesSymmetry::Bilateral => if h > 180.0 { float(360.0) - h } else { h },
+            IesSymmetry::BilateralLateral => {
+                if h < 90.0 {
+                    float(180.0) - h
+                } else if h > 270.0 {
+                    float(540.0) - h
+                } else {
+                    h
+                }
+            },
+            IesSymmetry::Rotational | IesSymmetry::None => h,
+        };
+
+        // interpolate between the two nearest horizontal planes
+        let angles = &self.horizontal_angles;
+        let i = find_interval(angles.len(), |i| angles[i] <= h);
+        let t = num::clamp((h - angles[i]) / (angles[i + 1] - angles[i]), float(0.0), float(1.0));
+
+        let c0 = interpolate(&self.vertical_angles, &self.candela[i], vertical);
+        let c1 = interpolate(&self.vertical_angles, &self.candela[i + 1], vertical);
+        c0 * (float(1.0) - t) + c1 * t
+    }
+}
+
+/// Checks that a number of angles or tilt pairs is a plausible count.
+fn count(value: Float) -> Result<usize, IesError> {
+    if value < 0.0 || value > MAX_COUNT as FloatPrim || value.fract() != 0.0 {
+        return Err(IesError::InvalidValue(value.raw().to_string()));
+    }
+
+    Ok(value.raw() as usize)
+}
+
+/// Linearly interpolates the `values` that are at `angles`.
+fn interpolate(angles: &[Float], values: &[Float], angle: Float) -> Float {
+    if angles.len() == 1 {
+        return values[0];
+    }
+
+    let i = find_interval(angles.len(), |i| angles[i] <= angle);
+    let t = num::clamp((angle - angles[i]) / (angles[i + 1] - angles[i]), float(0.0), float(1.0));
+    values[i] * (float(1.0) - t) + values[i + 1] * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NONE: &str = "IESNA:LM-63-2002
+[TEST] rotational
+TILT=NONE
+1 1000 1 3 1 1 2 0 0 0
+1 1 100
+0 45 90
+0
+100 50 0
+";
+
+    const INCLUDE: &str = "IESNA:LM-63-2002
+[TEST] quadrant, with tilt data
+TILT=INCLUDE
+1
+2
+0 90
+1 0.5
+1 1000 2 2 2 1 2 0 0 0
+1 1 100
+0, 90
+0, 90
+10, 0
+30, 0
+";
+
+    #[test]
+    fn tilt_none() {
+        let profile = IesProfile::parse(NONE).unwrap();
+        assert_eq!(profile.symmetry, IesSymmetry::Rotational);
+        assert_eq!(profile.vertical_angles, vec![float(0.0), float(45.0), float(90.0)]);
+        assert_eq!(profile.intensity(float(0.0), float(0.0)), 100.0);
+        assert_eq!(profile.intensity(float(22.5), float(123.0)), 75.0);
+        assert_eq!(profile.intensity(float(120.0), float(0.0)), 0.0);
+    }
+
+    #[test]
+    fn tilt_include() {
+        let profile = IesProfile::parse(INCLUDE).unwrap();
+        assert_eq!(profile.symmetry, IesSymmetry::Quadrant);
+        assert_eq!(profile.horizontal_angles, vec![float(0.0), float(90.0)]);
+        // scaled by the multiplier
+        assert_eq!(profile.candela, vec![vec![float(20.0), float(0.0)], vec![float(60.0), float(0.0)]]);
+        assert_eq!(profile.intensity(float(0.0), float(45.0)), 40.0);
+        // mirrored into the first quadrant
+        assert_eq!(profile.intensity(float(0.0), float(270.0)), 60.0);
+        assert_eq!(profile.intensity(float(0.0), float(180.0)), 20.0);
+    }
+
+    #[test]
+    fn missing_tilt() {
+        match IesProfile::parse("IESNA:LM-63-2002\n1 2 3\n") {
+            Err(IesError::MissingTilt) => (),
+            other => panic!("expected a missing tilt error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn bad_count() {
+        for count in &["2.5", "-1", "1000000"] {
+            let text = NONE.replace("1 1000 1 3 1", &format!("1 1000 1 {} 1", count));
+            match IesProfile::parse(&text) {
+                Err(IesError::InvalidValue(_)) => (),
+                other => panic!("expected an invalid count error for {}, got {:?}", count, other),
+            }
+        }
+
+        // the tilt pairs have a count too
+        let text = INCLUDE.replace("1\n2\n0 90", "1\n-2\n0 90");
+        assert!(IesProfile::parse(&text).is_err());
+    }
+
+    #[test]
+    fn non_finite_values() {
+        for value in &["nan", "inf", "-inf", "1e40"] {
+            let text = NONE.replace("100 50 0", &format!("100 {} 0", value));
+            match IesProfile::parse(&text) {
+                Err(IesError::InvalidValue(ref v)) if v == value => (),
+                other => panic!("expected an invalid value error for {}, got {:?}", value, other),
+            }
+        }
+    }
+
+    #[test]
+    fn truncated() {
+        match IesProfile::parse(&NONE[..NONE.len() - 4]) {
+            Err(IesError::UnexpectedEof) => (),
+            other => panic!("expected an unexpected eof error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{ self, BufReader, Read };
+use std::path::Path;
+use image;
+use itertools::izip;
+use crate::prelude::*;
+use crate::film::{ ExrImage, UnsupportedCompression };
+use crate::spectrum::SpectrumType;
+
+#[derive(Debug)]
+pub enum ImageError {
+    Io(io::Error),
+    /// a PNG, JPEG or HDR couldn't be decoded
+    Decode(image::ImageError),
+    /// a PFM file has a bad header or is too short
+    InvalidPfm(String),
+    /// an EXR file doesn't have red, green & blue or luminance channels
+    MissingChannels,
+    /// an EXR file uses a compression that can't be decoded
+    UnsupportedCompression(&'static str),
+    UnknownExtension(String),
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageError::Io(err) => write!(f, "io error: {}", err),
+            ImageError::Decode(err) => write!(f, "decoding error: {}", err),
+            ImageError::InvalidPfm(message) => write!(f, "invalid pfm: {}", message),
+            ImageError::MissingChannels => write!(f, "image needs R, G & B or Y channels"),
+            ImageError::UnsupportedCompression(name) =>
+                write!(f, "unsupported OpenEXR {} compression, only uncompressed, RLE, ZIPS and ZIP files can be read", name),
+            ImageError::UnknownExtension(ext) => write!(f, "unknown image extension {:?}", ext),
+        }
+    }
+}
+
+impl Error for ImageError {
+    fn cause(&self) -> Option<&dyn Error> {
+        match self {
+            ImageError::Io(err) => Some(err),
+            ImageError::Decode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ImageError {
+    fn from(err: io::Error) -> Self {
+        ImageError::Io(err)
+    }
+}
+
+impl From<image::ImageError> for ImageError {
+    fn from(err: image::ImageError) -> Self {
+        ImageError::Decode(err)
+    }
+}
+
+/// Reads a PNG, JPEG, EXR, PFM or Radiance HDR image, chosen by the file's extension.
+///
+/// The pixels are returned in scanline order, starting at the top.
+/// 8-bit values are scaled to `[0, 1]`, but are left in sRGB.
+/// EXR files have to be uncompressed or use RLE, ZIPS or ZIP compression, see `ExrImage::read_from`.
+pub fn read_image(path: &Path) -> Result<(Point2i, Vec<Spectrum>), ImageError> {
+    let extension = path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "png" | "jpg" | "jpeg" => read_8bit(path),
+        "exr" => read_exr(path),
+        "pfm" => read_pfm(path),
+        "hdr" => read_hdr(path),
+        _ => Err(ImageError::UnknownExtension(extension)),
+    }
+}
+
+fn rgb(r: Float, g: Float, b: Float) -> Spectrum {
+    Spectrum::from_rgb([r, g, b], SpectrumType::Reflectance)
+}
+
+fn read_8bit(path: &Path) -> Result<(Point2i, Vec<Spectrum>), ImageError> {
+    let image = image::open(path)?.to_rgb();
+    let resolution = Point2i::new(image.width() as i32, image.height() as i32);
+
+    let pixels = image.pixels()
+        .map(|p| {
+            let c = |v: u8| float(v) / float(255.0);
+            rgb(c(p[0]), c(p[1]), c(p[2]))
+        })
+        .collect();
+
+    Ok((resolution, pixels))
+}
+
+fn read_exr(path: &Path) -> Result<(Point2i, Vec<Spectrum>), ImageError> {
+    let image = ExrImage::read(path).map_err(|err| {
+        let compression = err.get_ref()
+            .and_then(|e| e.downcast_ref::<UnsupportedCompression>())
+            .map(|c| c.0);
+
+        match compression {
+            Some(name) => ImageError::UnsupportedCompression(name),
+            None => ImageError::Io(err),
+        }
+    })?;
+    let resolution = Point2i::new(image.width as i32, image.height as i32);
+
+    let pixels = match (image.channel("R"), image.channel("G"), image.channel("B"), image.channel("Y")) {
+        (Some(r), Some(g), Some(b), _) => izip!(&r.values, &g.values, &b.values)
+            .map(|(r, g, b)| rgb(*r, *g, *b))
+            .collect(),
+        (_, _, _, Some(y)) => y.values.iter()
+            .map(|y| rgb(*y, *y, *y))
+            .collect(),
+        _ => return Err(ImageError::MissingChannels),
+    };
+
+    Ok((resolution, pixels))
+}
+
+fn read_hdr(path: &Path) -> Result<(Point2i, Vec<Spectrum>), ImageError> {
+    let decoder = image::hdr::HDRDecoder::new(BufReader::new(File::open(path)?))?;
+    let metadata = decoder.metadata();
+    let resolution = Point2i::new(metadata.width as i32, metadata.height as i32);
+
+    let pixels = decoder.read_image_hdr()?
+        .iter()
+        .map(|p| rgb(float(p[0]), float(p[1]), float(p[2])))
+        .collect();
+
+    Ok((resolution, pixels))
+}
+
+fn pfm_token(data: &[u8], pos: &mut usize) -> Result<String, ImageError> {
+    while *pos < data.len() && data[*pos].is_ascii_whitespace() {
+        *pos += 1;
+    }
+
+    let start = *pos;
+    while *pos < data.len() && !data[*pos].is_ascii_whitespace() {
+        *pos += 1;
+    }
+
+    if start == *pos {
+        return Err(ImageError::InvalidPfm("header is truncated".to_string()));
+    }
+
+    Ok(String::from_utf8_lossy(&data[start..*pos]).into_owned())
+}
+
+fn read_pfm(path: &Path) -> Result<(Point2i, Vec<Spectrum>), ImageError> {
+    let mut data = vec![];
+    File::open(path)?.read_to_end(&mut data)?;
+
+    let mut pos = 0;
+    let n_channels = match pfm_token(&data, &mut pos)?.as_str() {
+        "PF" => 3,
+        "Pf" => 1,
+        magic => return Err(ImageError::InvalidPfm(format!("unknown magic number {:?}", magic))),
+    };
+
+    let mut number = |name: &str| -> Result<f32, ImageError> {
+        let token = pfm_token(&data, &mut pos)?;
+        token.parse()
+            .map_err(|_| ImageError::InvalidPfm(format!("invalid {} {:?}", name, token)))
+    };
+
+    let width = number("width")?;
+    let height = number("height")?;
+    // the sign gives the endianness
+    let scale = number("scale")?;
+
+    if width < 1.0 || height < 1.0 || width.fract() != 0.0 || height.fract() != 0.0 {
+        return Err(ImageError::InvalidPfm(format!("invalid resolution {}x{}", width, height)));
+    }
+    let (width, height) = (width as usize, height as usize);
+    let little_endian = scale < 0.0;
+    let scale = scale.abs();
+
+    // a single whitespace character separates the header from the data
+    pos += 1;
+
+    let n_values = width * height * n_channels;
+    if data.len() < pos + 4 * n_values {
+        return Err(ImageError::InvalidPfm("data is truncated".to_string()));
+    }
+
+    let values = data[pos..pos + 4 * n_values].chunks(4)
+        .map(|b| {
+            let (b0, b1, b2, b3) = (u32::from(b[0]), u32::from(b[1]), u32::from(b[2]), u32::from(b[3]));
+            let bits = if little_endian {
+                b0 | b1 << 8 | b2 << 16 | b3 << 24
+            } else {
+                b3 | b2 << 8 | b1 << 16 | b0 << 24
+            };
+
+            let v = f32::from_bits(bits) * scale;
+            // `Float` can't hold NaNs
+            if v.is_nan() { float(0.0) } else { float(v) }
+        })
+        .collect::<Vec<_>>();
+
+    // rows are stored from the bottom up
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in (0..height).rev() {
+        let row = &values[y * width * n_channels..(y + 1) * width * n_channels];
+        for v in row.chunks(n_channels) {
+            pixels.push(match v {
+                [r, g, b] => rgb(*r, *g, *b),
+                [y] => rgb(*y, *y, *y),
+                _ => unreachable!(),
+            });
+        }
+    }
+
+    Ok((Point2i::new(width as i32, height as i32), pixels))
+}
@@ -0,0 +1,42 @@
+use std::sync::Arc;
+use crate::material::Material;
+use crate::medium::MediumInterface;
+use crate::primitive::{ GeometricPrimitive, Primitive };
+use crate::shape::Shape;
+
+mod density;
+pub use self::density::{ DensityError, DensityGrid };
+
+mod ies;
+pub use self::ies::{ IesError, IesProfile, IesSymmetry };
+
+mod imageio;
+pub use self::imageio::{ read_image, ImageError };
+
+mod mtl;
+pub use self::mtl::{ default_material, read_mtl, MtlMaterial };
+
+mod obj;
+pub use self::obj::{ read_obj, ObjError, ObjFile, ObjMesh };
+
+mod ply;
+pub use self::ply::{ read_ply, PlyError, PlyMesh };
+
+/// Wraps each shape in a `GeometricPrimitive` that shares `material`,
+/// ready to be handed to `BvhAccel::new`.
+pub fn shapes_to_primitives(
+    shapes: Vec<Arc<dyn Shape + Send + Sync>>,
+    material: Option<Arc<dyn Material + Send + Sync>>,
+) -> Vec<Arc<dyn Primitive + Send>> {
+    shapes.into_iter()
+        .map(|shape| {
+            let primitive = GeometricPrimitive {
+                shape,
+                material: material.clone(),
+                area_light: None,
+                medium_interface: MediumInterface::default(),
+            };
+            Arc::new(primitive) as Arc<dyn Primitive + Send>
+        })
+        .collect()
+}
@@ -0,0 +1,195 @@
+use std::cmp::max;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{ BufRead, BufReader };
+use std::path::{ Path, PathBuf };
+use std::sync::Arc;
+use num;
+use crate::prelude::*;
+use crate::material::{ Material, MatteMaterial, UberMaterial };
+use crate::spectrum::SpectrumType;
+use crate::texture::{ ConstantTexture, ImageTexture, ImageWrap, Texture, UvMapping2d };
+use super::ObjError;
+
+/// A single `newmtl` entry from a MTL library.
+#[derive(Clone, Debug)]
+pub struct MtlMaterial {
+    pub name: String,
+    /// diffuse reflectance
+    pub kd: Spectrum,
+    /// specular reflectance
+    pub ks: Spectrum,
+    /// specular exponent
+    pub ns: Float,
+    /// index of refraction, which sets the strength of the specular reflection
+    pub ni: Float,
+    /// dissolve, the opacity of the surface, `Tr` sets it to `1 - Tr`
+    pub d: Float,
+    /// diffuse texture, relative paths are resolved against the MTL file
+    pub map_kd: Option<PathBuf>,
+}
+
+impl MtlMaterial {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            kd: Spectrum::new(0.5),
+            ks: Spectrum::new(0.0),
+            ns: float(0.0),
+            // MTL's own default of 1 would have no specular reflection at all
+            ni: float(1.5),
+            d: float(1.0),
+            map_kd: None,
+        }
+    }
+
+    /// Converts the MTL parameters into one of the crate's `Material`s.
+    ///
+    /// Opaque materials without a specular reflectance are a `MatteMaterial`, the others are an
+    /// `UberMaterial`, with `Ni` as its index of refraction, `d` as its opacity,
+    /// and a roughness that is derived from the specular exponent.
+    pub fn to_material(&self) -> Result<Arc<dyn Material + Send + Sync>, ObjError> {
+        let kd: Arc<dyn Texture<Spectrum> + Send + Sync> = match &self.map_kd {
+            Some(file) => {
+                let mapping = Box::new(UvMapping2d::default());
+                // 8-bit images are usually stored in sRGB
+                let gamma = file.extension()
+                    .map_or(false, |ext| ext != "exr" && ext != "pfm");
+
+                let texture = ImageTexture::<Spectrum>::new(mapping, file, false, float(8.0), ImageWrap::Repeat, float(1.0), gamma)
+                    .map_err(|error| ObjError::Image { file: file.clone(), error })?;
+                Arc::new(texture)
+            },
+            None => Arc::new(ConstantTexture::new(self.kd)),
+        };
+
+        Ok(self.with_kd(kd))
+    }
+
+    fn with_kd(&self, kd: Arc<dyn Texture<Spectrum> + Send + Sync>) -> Arc<dyn Material + Send + Sync> {
+        let d = num::clamp(self.d, float(0.0), float(1.0));
+        if self.ks.is_black() && d == 1.0 {
+            let sigma = Arc::new(ConstantTexture::new(0.0));
+            return Arc::new(MatteMaterial::new(kd, sigma, None));
+        }
+
+        let black = Arc::new(ConstantTexture::new(Spectrum::new(0.0)));
+        Arc::new(UberMaterial::new(
+            kd,
+            Arc::new(ConstantTexture::new(self.ks)),
+            black.clone(),
+            black,
+            Arc::new(ConstantTexture::new(phong_to_alpha(self.ns))),
+            None,
+            None,
+            Arc::new(ConstantTexture::new(self.ni)),
+            Arc::new(ConstantTexture::new(Spectrum::new(d))),
+            None,
+            false,
+        ))
+    }
+}
+
+/// The material used for faces that don't have a `usemtl`,
+/// or whose material can't be found.
+pub fn default_material() -> Arc<dyn Material + Send + Sync> {
+    let material = MtlMaterial::new(String::new());
+    material.with_kd(Arc::new(ConstantTexture::new(material.kd)))
+}
+
+/// Reads every material in the MTL library at `path`.
+pub fn read_mtl(path: &Path) -> Result<HashMap<String, MtlMaterial>, ObjError> {
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut materials = HashMap::new();
+    let mut current: Option<MtlMaterial> = None;
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let error = |message: &str| ObjError::Parse {
+            file: path.to_path_buf(),
+            line: i + 1,
+            message: message.to_string(),
+        };
+
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let args = tokens.collect::<Vec<_>>();
+
+        if keyword == "newmtl" {
+            if let Some(material) = current.take() {
+                materials.insert(material.name.clone(), material);
+            }
+            let name = args.join(" ");
+            if name.is_empty() {
+                return Err(error("newmtl without a name"));
+            }
+            current = Some(MtlMaterial::new(name));
+            continue;
+        }
+
+        let material = match &mut current {
+            Some(material) => material,
+            None => return Err(error("material statement before newmtl")),
+        };
+
+        match keyword {
+            "Kd" => material.kd = parse_rgb(&args).ok_or_else(|| error("invalid Kd"))?,
+            "Ks" => material.ks = parse_rgb(&args).ok_or_else(|| error("invalid Ks"))?,
+            "Ns" => material.ns = parse_float(&args).ok_or_else(|| error("invalid Ns"))?,
+            "Ni" => {
+                let ni = parse_float(&args).ok_or_else(|| error("invalid Ni"))?;
+                if ni <= 0.0 {
+                    return Err(error("Ni must be positive"));
+                }
+                material.ni = ni;
+            },
+            "d" => material.d = parse_float(&args).ok_or_else(|| error("invalid d"))?,
+            "Tr" => material.d = float(1.0) - parse_float(&args).ok_or_else(|| error("invalid Tr"))?,
+            "map_Kd" => {
+                // the file name is the last argument, after any options
+                let file = args.last().ok_or_else(|| error("map_Kd without a file name"))?;
+                material.map_kd = Some(dir.join(file));
+            },
+            // everything else (Ka, illum, bump maps...) isn't supported yet
+            _ => (),
+        }
+    }
+
+    if let Some(material) = current.take() {
+        materials.insert(material.name.clone(), material);
+    }
+
+    Ok(materials)
+}
+
+/// The microfacet alpha with roughly the same highlight as the Phong exponent `ns`.
+fn phong_to_alpha(ns: Float) -> Float {
+    (float(2.0) / (max(ns, float(0.0)) + float(2.0))).sqrt()
+}
+
+fn parse_float(args: &[&str]) -> Option<Float> {
+    let value = args.first()?.parse::<FloatPrim>().ok()?;
+    if value.is_finite() {
+        Some(float(value))
+    } else {
+        None
+    }
+}
+
+fn parse_rgb(args: &[&str]) -> Option<Spectrum> {
+    let r = parse_float(args)?;
+    // a single value is used for all of the channels
+    let (g, b) = match args.len() {
+        1 => (r, r),
+        _ => (parse_float(&args[1..])?, parse_float(&args[2..])?),
+    };
+
+    Some(Spectrum::from_rgb([r, g, b], SpectrumType::Reflectance))
+}
@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{ self, BufRead, BufReader };
+use std::path::{ Path, PathBuf };
+use std::sync::Arc;
+use crate::prelude::*;
+use crate::math::Transform;
+use crate::primitive::Primitive;
+use crate::shape::create_triangle_mesh;
+use super::ImageError;
+use super::mtl::{ default_material, read_mtl, MtlMaterial };
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(io::Error),
+    /// a statement in an OBJ or MTL file couldn't be understood
+    Parse { file: PathBuf, line: usize, message: String },
+    /// a texture used by a MTL material couldn't be read
+    Image { file: PathBuf, error: ImageError },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io(err) => write!(f, "io error: {}", err),
+            ObjError::Parse { file, line, message } =>
+                write!(f, "{}:{}: {}", file.display(), line, message),
+            ObjError::Image { file, error } =>
+                write!(f, "couldn't read texture {}: {}", file.display(), error),
+        }
+    }
+}
+
+impl Error for ObjError {
+    fn cause(&self) -> Option<&dyn Error> {
+        match self {
+            ObjError::Io(err) => Some(err),
+            ObjError::Image { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ObjError {
+    fn from(err: io::Error) -> Self {
+        ObjError::Io(err)
+    }
+}
+
+/// The faces of a single group that share a material,
+/// re-indexed so that they can be turned into a `TriangleMesh`.
+#[derive(Clone, Debug)]
+pub struct ObjMesh {
+    /// the name of the `o` or `g` group
+    pub group: String,
+    /// the name given to `usemtl`
+    pub material: Option<String>,
+    pub p: Vec<Point3f>,
+    pub n: Option<Vec<Normal>>,
+    pub uv: Option<Vec<Point2f>>,
+    pub vertex_indices: Vec<usize>,
+}
+
+/// The `(position, texture, normal)` indices of a face corner,
+/// already converted to be zero-based.
+type Corner = (usize, Option<usize>, Option<usize>);
+
+#[derive(Default)]
+struct MeshBuilder {
+    group: String,
+    material: Option<String>,
+    corners: Vec<Corner>,
+    indices: HashMap<Corner, usize>,
+    vertex_indices: Vec<usize>,
+}
+
+impl MeshBuilder {
+    fn new(group: String, material: Option<String>) -> Self {
+        Self {
+            group,
+            material,
+            ..Default::default()
+        }
+    }
+
+    fn add_corner(&mut self, corner: Corner) -> usize {
+        let corners = &mut self.corners;
+        *self.indices.entry(corner)
+            .or_insert_with(|| {
+                corners.push(corner);
+                corners.len() - 1
+            })
+    }
+
+    fn build(self, p: &[Point3f], n: &[Normal], uv: &[Point2f]) -> ObjMesh {
+        // attributes are only used if every corner has them
+        let has_n = self.corners.iter().all(|c| c.2.is_some());
+        let has_uv = self.corners.iter().all(|c| c.1.is_some());
+
+        let mesh_p = self.corners.iter()
+            .map(|c| p[c.0])
+            .collect();
+        let mesh_n = if has_n {
+            Some(self.corners.iter().map(|c| n[c.2.unwrap()]).collect())
+        } else {
+            None
+        };
+        let mesh_uv = if has_uv {
+            Some(self.corners.iter().map(|c| uv[c.1.unwrap()]).collect())
+        } else {
+            None
+        };
+
+        ObjMesh {
+            group: self.group,
+            material: self.material,
+            p: mesh_p,
+            n: mesh_n,
+            uv: mesh_uv,
+            vertex_indices: self.vertex_indices,
+        }
+    }
+}
+
+/// The meshes of an OBJ file, along with the materials from its MTL libraries.
+#[derive(Clone, Debug)]
+pub struct ObjFile {
+    pub meshes: Vec<ObjMesh>,
+    pub materials: HashMap<String, MtlMaterial>,
+}
+
+impl ObjFile {
+    /// Reads an OBJ file, and any MTL libraries that it references.
+    ///
+    /// MTL libraries that can't be opened are skipped,
+    /// and the faces that use them fall back to the default material.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ObjError> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+        let mut p = Vec::new();
+        let mut n = Vec::new();
+        let mut uv = Vec::new();
+
+        let mut materials = HashMap::new();
+        let mut meshes = Vec::new();
+        let mut builder = MeshBuilder::new(String::new(), None);
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let error = |message: &str| ObjError::Parse {
+                file: path.to_path_buf(),
+                line: i + 1,
+                message: message.to_string(),
+            };
+
+            let line = line.split('#').next().unwrap_or("").trim();
+            let mut tokens = line.split_whitespace();
+            let keyword = match tokens.next() {
+                Some(keyword) => keyword,
+                None => continue,
+            };
+            let args = tokens.collect::<Vec<_>>();
+
+            match keyword {
+                "v" => {
+                    let v = parse_floats(&args, 3).ok_or_else(|| error("invalid vertex"))?;
+                    p.push(Point3f::new(v[0], v[1], v[2]));
+                },
+                "vn" => {
+                    let v = parse_floats(&args, 3).ok_or_else(|| error("invalid normal"))?;
+                    n.push(Normal::new(v[0], v[1], v[2]));
+                },
+                "vt" => {
+                    // the optional `w` coordinate is ignored
+                    let v = parse_floats(&args, 1).ok_or_else(|| error("invalid texture coordinate"))?;
+                    uv.push(Point2f::new(v[0], v.get(1).cloned().unwrap_or_else(|| float(0.0))));
+                },
+                "f" => {
+                    if args.len() < 3 {
+                        return Err(error("faces must have at least 3 vertices"));
+                    }
+
+                    let corners = args.iter()
+                        .map(|arg| parse_corner(arg, p.len(), uv.len(), n.len()))
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(|| error("invalid face"))?;
+                    let corners = corners.into_iter()
+                        .map(|c| builder.add_corner(c))
+                        .collect::<Vec<_>>();
+
+                    // triangulate polygons as a fan around the first vertex
+                    for j in 1..corners.len() - 1 {
+                        builder.vertex_indices.extend(&[corners[0], corners[j], corners[j + 1]]);
+                    }
+                },
+                "o" | "g" => {
+                    let material = builder.material.clone();
+                    let next = MeshBuilder::new(args.join(" "), material);
+                    meshes.push(std::mem::replace(&mut builder, next));
+                },
+                "usemtl" => {
+                    let group = builder.group.clone();
+                    let next = MeshBuilder::new(group, Some(args.join(" ")));
+                    meshes.push(std::mem::replace(&mut builder, next));
+                },
+                "mtllib" => {
+                    for lib in &args {
+                        match read_mtl(&dir.join(lib)) {
+                            Ok(lib) => materials.extend(lib),
+                            // a missing library isn't fatal, a malformed one is
+                            Err(ObjError::Io(_)) => (),
+                            Err(err) => return Err(err),
+                        }
+                    }
+                },
+                // smoothing groups, lines, points and curves aren't supported
+                _ => (),
+            }
+        }
+        meshes.push(builder);
+
+        let meshes = meshes.into_iter()
+            .filter(|b| !b.vertex_indices.is_empty())
+            .map(|b| b.build(&p, &n, &uv))
+            .collect();
+
+        Ok(Self { meshes, materials })
+    }
+
+    /// Creates a `GeometricPrimitive` for each triangle,
+    /// with each group using its own mesh and material.
+    ///
+    /// Fails if the texture of a material that is used can't be read.
+    pub fn into_primitives(self, object_to_world: Arc<Transform>, reverse_orientation: bool) -> Result<Vec<Arc<dyn Primitive + Send>>, ObjError> {
+        let default = default_material();
+        let mut materials = HashMap::new();
+        for name in self.meshes.iter().filter_map(|mesh| mesh.material.as_ref()) {
+            if materials.contains_key(name) {
+                continue;
+            }
+            if let Some(m) = self.materials.get(name) {
+                materials.insert(name.clone(), m.to_material()?);
+            }
+        }
+
+        let mut primitives = Vec::new();
+        for mesh in self.meshes {
+            let material = mesh.material.as_ref()
+                .and_then(|name| materials.get(name))
+                .unwrap_or(&default)
+                .clone();
+
+            let shapes = create_triangle_mesh(
+                object_to_world.clone(),
+                reverse_orientation,
+                mesh.vertex_indices,
+                mesh.p,
+                mesh.n,
+                None,
+                mesh.uv,
+            );
+            primitives.extend(super::shapes_to_primitives(shapes, Some(material)));
+        }
+
+        Ok(primitives)
+    }
+}
+
+/// Reads an OBJ file and returns a `GeometricPrimitive` for each of its triangles.
+pub fn read_obj(
+    path: impl AsRef<Path>,
+    object_to_world: Arc<Transform>,
+    reverse_orientation: bool,
+) -> Result<Vec<Arc<dyn Primitive + Send>>, ObjError> {
+    let obj = ObjFile::open(path)?;
+    obj.into_primitives(object_to_world, reverse_orientation)
+}
+
+fn parse_floats(args: &[&str], min: usize) -> Option<Vec<Float>> {
+    if args.len() < min {
+        return None;
+    }
+
+    args.iter()
+        .map(|a| a.parse::<FloatPrim>().ok().filter(|v| v.is_finite()).map(float))
+        .collect()
+}
+
+/// Converts a one-based (or negative, relative) index into a zero-based one.
+fn parse_index(s: &str, len: usize) -> Option<usize> {
+    let index = s.parse::<i64>().ok()?;
+    let index = if index < 0 {
+        len as i64 + index
+    } else {
+        index - 1
+    };
+
+    if index >= 0 && (index as usize) < len {
+        Some(index as usize)
+    } else {
+        None
+    }
+}
+
+/// Parses `v`, `v/vt`, `v//vn` or `v/vt/vn`.
+fn parse_corner(s: &str, n_p: usize, n_uv: usize, n_n: usize) -> Option<Corner> {
+    let mut parts = s.split('/');
+    let p = parse_index(parts.next()?, n_p)?;
+
+    let uv = match parts.next() {
+        Some("") | None => None,
+        Some(uv) => Some(parse_index(uv, n_uv)?),
+    };
+    let n = match parts.next() {
+        Some("") | None => None,
+        Some(n) => Some(parse_index(n, n_n)?),
+    };
+
+    Some((p, uv, n))
+}
@@ -0,0 +1,583 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{ self, BufRead, BufReader, Read };
+use std::path::Path;
+use std::sync::Arc;
+use crate::prelude::*;
+use crate::math::Transform;
+use crate::material::Material;
+use crate::primitive::Primitive;
+use crate::shape::{ create_triangle_mesh, Shape };
+
+#[derive(Debug)]
+pub enum PlyError {
+    Io(io::Error),
+    /// the header is missing, or contains a line that can't be understood
+    InvalidHeader(String),
+    /// the body ended before all of the elements in the header were read
+    UnexpectedEof,
+    /// an ascii value couldn't be parsed as the type declared in the header
+    InvalidValue(String),
+    /// a required vertex or face property isn't declared
+    MissingProperty(&'static str),
+    /// a face refers to a vertex that doesn't exist
+    InvalidIndex { index: i64, n_vertices: usize },
+    /// a face has fewer than 3 or more than 4 vertices
+    InvalidFace(usize),
+}
+
+impl fmt::Display for PlyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlyError::Io(err) => write!(f, "io error: {}", err),
+            PlyError::InvalidHeader(line) => write!(f, "invalid header line: {:?}", line),
+            PlyError::UnexpectedEof => write!(f, "unexpected end of file"),
+            PlyError::InvalidValue(value) => write!(f, "invalid value: {:?}", value),
+            PlyError::MissingProperty(name) => write!(f, "missing property: {}", name),
+            PlyError::InvalidIndex { index, n_vertices } =>
+                write!(f, "vertex index {} out of range (mesh has {} vertices)", index, n_vertices),
+            PlyError::InvalidFace(n) => write!(f, "faces must have 3 or 4 vertices, found {}", n),
+        }
+    }
+}
+
+impl Error for PlyError {
+    fn cause(&self) -> Option<&dyn Error> {
+        match self {
+            PlyError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PlyError {
+    fn from(err: io::Error) -> Self {
+        PlyError::Io(err)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum ScalarType {
+    Int8,
+    Uint8,
+    Int16,
+    Uint16,
+    Int32,
+    Uint32,
+    Float32,
+    Float64,
+}
+
+impl ScalarType {
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "char" | "int8" => ScalarType::Int8,
+            "uchar" | "uint8" => ScalarType::Uint8,
+            "short" | "int16" => ScalarType::Int16,
+            "ushort" | "uint16" => ScalarType::Uint16,
+            "int" | "int32" => ScalarType::Int32,
+            "uint" | "uint32" => ScalarType::Uint32,
+            "float" | "float32" => ScalarType::Float32,
+            "double" | "float64" => ScalarType::Float64,
+            _ => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            ScalarType::Int8 | ScalarType::Uint8 => 1,
+            ScalarType::Int16 | ScalarType::Uint16 => 2,
+            ScalarType::Int32 | ScalarType::Uint32 | ScalarType::Float32 => 4,
+            ScalarType::Float64 => 8,
+        }
+    }
+
+    fn is_float(self) -> bool {
+        self == ScalarType::Float32 || self == ScalarType::Float64
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+enum PropertyType {
+    Scalar(ScalarType),
+    List { count: ScalarType, item: ScalarType },
+}
+
+#[derive(Clone, Debug)]
+struct Property {
+    name: String,
+    ty: PropertyType,
+}
+
+#[derive(Clone, Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+impl Element {
+    fn find(&self, names: &[&str]) -> Option<usize> {
+        self.properties.iter()
+            .position(|p| names.contains(&p.name.as_str()))
+    }
+}
+
+/// Reads the scalar values out of the body of the file,
+/// with every type widened to `f64`.
+trait BodyReader {
+    fn read(&mut self, ty: ScalarType) -> Result<f64, PlyError>;
+
+    fn read_property(&mut self, ty: PropertyType, values: &mut Vec<f64>) -> Result<(), PlyError> {
+        values.clear();
+        match ty {
+            PropertyType::Scalar(ty) => values.push(self.read(ty)?),
+            PropertyType::List { count, item } => {
+                let count = self.read(count)?;
+                if count < 0.0 {
+                    return Err(PlyError::InvalidValue(count.to_string()));
+                }
+                for _ in 0..count as usize {
+                    values.push(self.read(item)?);
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+struct AsciiReader<'a> {
+    tokens: std::str::SplitWhitespace<'a>,
+}
+
+impl BodyReader for AsciiReader<'_> {
+    fn read(&mut self, ty: ScalarType) -> Result<f64, PlyError> {
+        let token = self.tokens.next().ok_or(PlyError::UnexpectedEof)?;
+
+        let value = if ty.is_float() {
+            token.parse::<f64>().ok()
+        } else {
+            token.parse::<i64>().ok().map(|i| i as f64)
+        };
+
+        value.ok_or_else(|| PlyError::InvalidValue(token.to_string()))
+    }
+}
+
+struct BinaryReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+    big_endian: bool,
+}
+
+impl BodyReader for BinaryReader<'_> {
+    fn read(&mut self, ty: ScalarType) -> Result<f64, PlyError> {
+        let size = ty.size();
+        if self.offset + size > self.data.len() {
+            return Err(PlyError::UnexpectedEof);
+        }
+
+        let mut bytes = [0u8; 8];
+        bytes[..size].copy_from_slice(&self.data[self.offset..self.offset + size]);
+        self.offset += size;
+
+        if self.big_endian {
+            bytes[..size].reverse();
+        }
+
+        // assemble the little-endian bytes
+        let bits = bytes[..size].iter()
+            .rev()
+            .fold(0u64, |acc, b| (acc << 8) | u64::from(*b));
+
+        Ok(match ty {
+            ScalarType::Int8 => f64::from(bits as u8 as i8),
+            ScalarType::Uint8 => f64::from(bits as u8),
+            ScalarType::Int16 => f64::from(bits as u16 as i16),
+            ScalarType::Uint16 => f64::from(bits as u16),
+            ScalarType::Int32 => f64::from(bits as u32 as i32),
+            ScalarType::Uint32 => f64::from(bits as u32),
+            ScalarType::Float32 => f64::from(f32::from_bits(bits as u32)),
+            ScalarType::Float64 => f64::from_bits(bits),
+        })
+    }
+}
+
+/// The triangulated contents of a PLY file, in object space.
+#[derive(Clone, Debug)]
+pub struct PlyMesh {
+    pub p: Vec<Point3f>,
+    pub n: Option<Vec<Normal>>,
+    pub uv: Option<Vec<Point2f>>,
+    /// three indices per triangle, quads are split into two triangles
+    pub vertex_indices: Vec<usize>,
+    /// the `face_indices` property of each triangle,
+    /// both halves of a quad share the index of the original face
+    pub face_indices: Option<Vec<i32>>,
+}
+
+impl PlyMesh {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, PlyError> {
+        let file = File::open(path)?;
+        Self::read(BufReader::new(file))
+    }
+
+    pub fn read(mut reader: impl BufRead) -> Result<Self, PlyError> {
+        let (format, elements) = read_header(&mut reader)?;
+
+        let mut body = Vec::new();
+        reader.read_to_end(&mut body)?;
+
+        match format {
+            Format::Ascii => {
+                let body = String::from_utf8_lossy(&body);
+                let mut reader = AsciiReader { tokens: body.split_whitespace() };
+                Self::read_body(&mut reader, &elements)
+            },
+            Format::BinaryLittleEndian | Format::BinaryBigEndian => {
+                let mut reader = BinaryReader {
+                    data: &body,
+                    offset: 0,
+                    big_endian: format == Format::BinaryBigEndian,
+                };
+                Self::read_body(&mut reader, &elements)
+            },
+        }
+    }
+
+    fn read_body(reader: &mut dyn BodyReader, elements: &[Element]) -> Result<Self, PlyError> {
+        let mut p = Vec::new();
+        let mut n = Vec::new();
+        let mut uv = Vec::new();
+        let mut has_n = false;
+        let mut has_uv = false;
+        let mut vertex_indices = Vec::new();
+        let mut face_indices = Vec::new();
+        let mut has_face_indices = false;
+        let mut values = Vec::new();
+
+        for element in elements {
+            match element.name.as_str() {
+                "vertex" => {
+                    let xyz = [
+                        element.find(&["x"]).ok_or(PlyError::MissingProperty("x"))?,
+                        element.find(&["y"]).ok_or(PlyError::MissingProperty("y"))?,
+                        element.find(&["z"]).ok_or(PlyError::MissingProperty("z"))?,
+                    ];
+                    let normal = match (element.find(&["nx"]), element.find(&["ny"]), element.find(&["nz"])) {
+                        (Some(x), Some(y), Some(z)) => Some([x, y, z]),
+                        _ => None,
+                    };
+                    let u = element.find(&["u", "s", "texture_u", "texture_s"]);
+                    let v = element.find(&["v", "t", "texture_v", "texture_t"]);
+                    let texture = match (u, v) {
+                        (Some(u), Some(v)) => Some([u, v]),
+                        _ => None,
+                    };
+                    has_n = normal.is_some();
+                    has_uv = texture.is_some();
+
+                    let mut vertex = vec![0.0; element.properties.len()];
+                    for _ in 0..element.count {
+                        for (i, property) in element.properties.iter().enumerate() {
+                            reader.read_property(property.ty, &mut values)?;
+                            vertex[i] = values.first().cloned().unwrap_or(0.0);
+                        }
+
+                        let get = |i: usize| float(vertex[i] as FloatPrim);
+                        p.push(Point3f::new(get(xyz[0]), get(xyz[1]), get(xyz[2])));
+                        if let Some([x, y, z]) = normal {
+                            n.push(Normal::new(get(x), get(y), get(z)));
+                        }
+                        if let Some([u, v]) = texture {
+                            uv.push(Point2f::new(get(u), get(v)));
+                        }
+                    }
+                },
+                "face" => {
+                    let indices = element.find(&["vertex_indices", "vertex_index"])
+                        .ok_or(PlyError::MissingProperty("vertex_indices"))?;
+                    let face_index = element.find(&["face_indices"]);
+                    has_face_indices = face_index.is_some();
+
+                    for _ in 0..element.count {
+                        let mut face = [0; 4];
+                        let mut face_len = 0;
+                        let mut index = 0;
+
+                        for (i, property) in element.properties.iter().enumerate() {
+                            reader.read_property(property.ty, &mut values)?;
+
+                            if i == indices {
+                                face_len = values.len();
+                                if face_len < 3 || face_len > 4 {
+                                    return Err(PlyError::InvalidFace(face_len));
+                                }
+                                for (f, v) in face.iter_mut().zip(values.iter()) {
+                                    *f = *v as i64;
+                                }
+                            } else if Some(i) == face_index {
+                                index = values.first().cloned().unwrap_or(0.0) as i32;
+                            }
+                        }
+
+                        vertex_indices.extend(&[face[0], face[1], face[2]]);
+                        face_indices.push(index);
+                        if face_len == 4 {
+                            vertex_indices.extend(&[face[0], face[2], face[3]]);
+                            face_indices.push(index);
+                        }
+                    }
+                },
+                _ => {
+                    // skip over elements we don't know about
+                    for _ in 0..element.count {
+                        for property in &element.properties {
+                            reader.read_property(property.ty, &mut values)?;
+                        }
+                    }
+                },
+            }
+        }
+
+        let n_vertices = p.len();
+        let vertex_indices = vertex_indices.into_iter()
+            .map(|index| {
+                if index < 0 || index as usize >= n_vertices {
+                    Err(PlyError::InvalidIndex { index, n_vertices })
+                } else {
+                    Ok(index as usize)
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            p,
+            n: if has_n { Some(n) } else { None },
+            uv: if has_uv { Some(uv) } else { None },
+            vertex_indices,
+            face_indices: if has_face_indices { Some(face_indices) } else { None },
+        })
+    }
+
+    pub fn into_shapes(self, object_to_world: Arc<Transform>, reverse_orientation: bool) -> Vec<Arc<dyn Shape + Send + Sync>> {
+        create_triangle_mesh(
+            object_to_world,
+            reverse_orientation,
+            self.vertex_indices,
+            self.p,
+            self.n,
+            None,
+            self.uv,
+        )
+    }
+
+    pub fn into_primitives(
+        self,
+        object_to_world: Arc<Transform>,
+        reverse_orientation: bool,
+        material: Option<Arc<dyn Material + Send + Sync>>,
+    ) -> Vec<Arc<dyn Primitive + Send>> {
+        let shapes = self.into_shapes(object_to_world, reverse_orientation);
+        super::shapes_to_primitives(shapes, material)
+    }
+}
+
+fn read_header(reader: &mut impl BufRead) -> Result<(Format, Vec<Element>), PlyError> {
+    let mut line = String::new();
+    let mut next_line = |line: &mut String| -> Result<(), PlyError> {
+        line.clear();
+        if reader.read_line(line)? == 0 {
+            return Err(PlyError::UnexpectedEof);
+        }
+        Ok(())
+    };
+
+    next_line(&mut line)?;
+    if line.trim() != "ply" {
+        return Err(PlyError::InvalidHeader(line));
+    }
+
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+
+    loop {
+        next_line(&mut line)?;
+        let invalid = || PlyError::InvalidHeader(line.trim().to_string());
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+
+        match tokens.as_slice() {
+            ["end_header"] => break,
+            ["comment", ..] | ["obj_info", ..] | [] => (),
+            ["format", ty, _version] => {
+                format = Some(match *ty {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    _ => return Err(invalid()),
+                });
+            },
+            ["element", name, count] => {
+                let count = count.parse().map_err(|_| invalid())?;
+                elements.push(Element {
+                    name: name.to_string(),
+                    count,
+                    properties: Vec::new(),
+                });
+            },
+            ["property", "list", count, item, name] => {
+                let count = ScalarType::parse(count).ok_or_else(invalid)?;
+                let item = ScalarType::parse(item).ok_or_else(invalid)?;
+                let element = elements.last_mut().ok_or_else(invalid)?;
+                element.properties.push(Property {
+                    name: name.to_string(),
+                    ty: PropertyType::List { count, item },
+                });
+            },
+            ["property", ty, name] => {
+                let ty = ScalarType::parse(ty).ok_or_else(invalid)?;
+                let element = elements.last_mut().ok_or_else(invalid)?;
+                element.properties.push(Property {
+                    name: name.to_string(),
+                    ty: PropertyType::Scalar(ty),
+                });
+            },
+            _ => return Err(invalid()),
+        }
+    }
+
+    let format = format.ok_or_else(|| PlyError::InvalidHeader(String::from("missing format")))?;
+
+    Ok((format, elements))
+}
+
+/// Reads a PLY file and returns a `GeometricPrimitive` for each of its triangles,
+/// all sharing `material`.
+pub fn read_ply(
+    path: impl AsRef<Path>,
+    object_to_world: Arc<Transform>,
+    reverse_orientation: bool,
+    material: Option<Arc<dyn Material + Send + Sync>>,
+) -> Result<Vec<Arc<dyn Primitive + Send>>, PlyError> {
+    let mesh = PlyMesh::open(path)?;
+    Ok(mesh.into_primitives(object_to_world, reverse_orientation, material))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POSITIONS: [[f32; 3]; 4] = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.5]];
+
+    fn header(format: &str) -> String {
+        format!(
+            "ply\nformat {} 1.0\ncomment a quad and a triangle\n\
+             element vertex 4\nproperty float x\nproperty float y\nproperty float z\n\
+             element face 2\nproperty list uchar int vertex_indices\nend_header\n",
+            format,
+        )
+    }
+
+    fn ascii() -> Vec<u8> {
+        let mut ply = header("ascii");
+        for p in &POSITIONS {
+            ply += &format!("{} {} {}\n", p[0], p[1], p[2]);
+        }
+        ply += "4 0 1 2 3\n3 3 2 1\n";
+        ply.into_bytes()
+    }
+
+    fn push_u32(buf: &mut Vec<u8>, v: u32, big_endian: bool) {
+        let bytes = [v as u8, (v >> 8) as u8, (v >> 16) as u8, (v >> 24) as u8];
+        if big_endian {
+            buf.extend(bytes.iter().rev());
+        } else {
+            buf.extend(&bytes);
+        }
+    }
+
+    fn binary(big_endian: bool) -> Vec<u8> {
+        let mut ply = header(if big_endian { "binary_big_endian" } else { "binary_little_endian" }).into_bytes();
+        for p in &POSITIONS {
+            for c in p {
+                push_u32(&mut ply, c.to_bits(), big_endian);
+            }
+        }
+        for face in &[&[0, 1, 2, 3][..], &[3, 2, 1][..]] {
+            // the list count is a uchar
+            ply.push(face.len() as u8);
+            for i in face.iter() {
+                push_u32(&mut ply, *i, big_endian);
+            }
+        }
+        ply
+    }
+
+    fn assert_mesh(mesh: &PlyMesh) {
+        let p = mesh.p.iter()
+            .map(|p| [p.x.raw(), p.y.raw(), p.z.raw()])
+            .collect::<Vec<_>>();
+        assert_eq!(p, POSITIONS.to_vec());
+        // the quad is split into two triangles that share its first vertex
+        assert_eq!(mesh.vertex_indices, vec![0, 1, 2, 0, 2, 3, 3, 2, 1]);
+        assert!(mesh.n.is_none() && mesh.uv.is_none() && mesh.face_indices.is_none());
+    }
+
+    fn is_eof(result: Result<PlyMesh, PlyError>) -> bool {
+        match result {
+            Err(PlyError::UnexpectedEof) => true,
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn ascii_mesh() {
+        assert_mesh(&PlyMesh::read(&ascii()[..]).unwrap());
+    }
+
+    #[test]
+    fn binary_meshes() {
+        assert_mesh(&PlyMesh::read(&binary(false)[..]).unwrap());
+        assert_mesh(&PlyMesh::read(&binary(true)[..]).unwrap());
+    }
+
+    #[test]
+    fn truncated_body() {
+        let ply = ascii();
+        let without_last_index = &ply[..ply.len() - 2];
+        assert!(is_eof(PlyMesh::read(without_last_index)));
+
+        for big_endian in &[false, true] {
+            let ply = binary(*big_endian);
+            assert!(is_eof(PlyMesh::read(&ply[..ply.len() - 1])));
+        }
+
+        let header_only = header("ascii");
+        assert!(is_eof(PlyMesh::read(&header_only.as_bytes()[..header_only.len() - 12])));
+    }
+
+    #[test]
+    fn invalid_faces() {
+        let mut ply = header("ascii");
+        ply += "0 0 0\n1 0 0\n1 1 0\n0 1 0\n3 0 1 4\n3 0 1 2\n";
+        match PlyMesh::read(ply.as_bytes()) {
+            Err(PlyError::InvalidIndex { index: 4, n_vertices: 4 }) => (),
+            other => panic!("expected an invalid index, got {:?}", other),
+        }
+
+        let mut ply = header("ascii");
+        ply += "0 0 0\n1 0 0\n1 1 0\n0 1 0\n2 0 1\n3 0 1 2\n";
+        match PlyMesh::read(ply.as_bytes()) {
+            Err(PlyError::InvalidFace(2)) => (),
+            other => panic!("expected an invalid face, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use std::cmp::max;
+use std::sync::Arc;
+use num;
+use crate::prelude::*;
+use super::{ BumpMap, Material };
+use crate::interaction::SurfaceInteraction;
+use crate::bxdf::{
+    schlick_r0_from_eta,
+    Bsdf,
+    DisneyClearcoat,
+    DisneyDiffuse,
+    DisneyFakeSs,
+    DisneyFresnel,
+    DisneyMicrofacetDistribution,
+    DisneyRetro,
+    DisneySheen,
+    LambertianTransmission,
+    MicrofacetReflection,
+    MicrofacetTransmission,
+    TransportMode,
+    TrowbridgeReitzDistribution,
+};
+use crate::memory::MemoryArena;
+use crate::texture::Texture;
+
+/// Burley's principled BSDF, where every parameter except `color` is in [0, 1].
+///
+/// Thin surfaces, like leaves or paper, have no interior. Their diffuse light is
+/// blended with a subsurface approximation by `flatness`, and part of it is transmitted
+/// to the other side by `diff_trans`, which goes up to 2.
+#[derive(Clone, Debug)]
+pub struct DisneyMaterial {
+    color: Arc<dyn Texture<Spectrum> + Send + Sync>,
+    metallic: Arc<dyn Texture<Float> + Send + Sync>,
+    roughness: Arc<dyn Texture<Float> + Send + Sync>,
+    /// the reflectance at normal incidence of non-metals, 0.5 is an index of refraction of 1.5
+    specular: Arc<dyn Texture<Float> + Send + Sync>,
+    specular_tint: Arc<dyn Texture<Float> + Send + Sync>,
+    anisotropic: Arc<dyn Texture<Float> + Send + Sync>,
+    sheen: Arc<dyn Texture<Float> + Send + Sync>,
+    sheen_tint: Arc<dyn Texture<Float> + Send + Sync>,
+    clearcoat: Arc<dyn Texture<Float> + Send + Sync>,
+    clearcoat_gloss: Arc<dyn Texture<Float> + Send + Sync>,
+    spec_trans: Arc<dyn Texture<Float> + Send + Sync>,
+    flatness: Arc<dyn Texture<Float> + Send + Sync>,
+    diff_trans: Arc<dyn Texture<Float> + Send + Sync>,
+    thin: bool,
+    bump: Option<BumpMap>,
+}
+
+impl DisneyMaterial {
+    #[cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
+    pub fn new(
+        color: Arc<dyn Texture<Spectrum> + Send + Sync>,
+        metallic: Arc<dyn Texture<Float> + Send + Sync>,
+        roughness: Arc<dyn Texture<Float> + Send + Sync>,
+        specular: Arc<dyn Texture<Float> + Send + Sync>,
+        specular_tint: Arc<dyn Texture<Float> + Send + Sync>,
+        anisotropic: Arc<dyn Texture<Float> + Send + Sync>,
+        sheen: Arc<dyn Texture<Float> + Send + Sync>,
+        sheen_tint: Arc<dyn Texture<Float> + Send + Sync>,
+        clearcoat: Arc<dyn Texture<Float> + Send + Sync>,
+        clearcoat_gloss: Arc<dyn Texture<Float> + Send + Sync>,
+        spec_trans: Arc<dyn Texture<Float> + Send + Sync>,
+        flatness: Arc<dyn Texture<Float> + Send + Sync>,
+        diff_trans: Arc<dyn Texture<Float> + Send + Sync>,
+        thin: bool,
+        bump: Option<BumpMap>,
+    ) -> Self {
+        Self {
+            color,
+            metallic,
+            roughness,
+            specular,
+            specular_tint,
+            anisotropic,
+            sheen,
+            sheen_tint,
+            clearcoat,
+            clearcoat_gloss,
+            spec_trans,
+            flatness,
+            diff_trans,
+            thin,
+            bump,
+        }
+    }
+}
+
+impl Material for DisneyMaterial {
+    fn compute_scattering_functions(&self, isect: SurfaceInteraction<'a>, arena: &'a MemoryArena, mode: TransportMode, _allow_multiple_lobes: bool) -> SurfaceInteraction<'a> {
+        let mut isect = match &self.bump {
+            Some(bump) => bump.apply(&isect),
+            None => isect,
+        };
+
+        let eta = specular_to_eta(self.specular.evaluate(&isect));
+        let bsdf = arena.alloc(Bsdf::new(&isect, Some(eta)));
+
+        // diffuse
+        let c = self.color.evaluate(&isect).clamp(None, None);
+        let metallic = self.metallic.evaluate(&isect);
+        let spec_trans = self.spec_trans.evaluate(&isect);
+        let diffuse_weight = (float(1.0) - metallic) * (float(1.0) - spec_trans);
+        // 0: all diffuse is reflected -> 1, transmitted
+        let dt = self.diff_trans.evaluate(&isect) / float(2.0);
+        let rough = self.roughness.evaluate(&isect);
+
+        // normalize luminance to isolate hue and saturation
+        let lum = c.y();
+        let c_tint = if lum > 0.0 { c / lum } else { Spectrum::new(1.0) };
+
+        if diffuse_weight > 0.0 {
+            if self.thin {
+                // blend between the diffuse and fake subsurface lobes by flatness,
+                // and weight both by the reflected fraction of diff_trans
+                let flat = self.flatness.evaluate(&isect);
+                bsdf.add(arena.alloc(DisneyDiffuse::new(c * (diffuse_weight * (float(1.0) - flat) * (float(1.0) - dt)))));
+                bsdf.add(arena.alloc(DisneyFakeSs::new(c * (diffuse_weight * flat * (float(1.0) - dt)), rough)));
+            } else {
+                bsdf.add(arena.alloc(DisneyDiffuse::new(c * diffuse_weight)));
+            }
+
+            // retro-reflection
+            bsdf.add(arena.alloc(DisneyRetro::new(c * diffuse_weight, rough)));
+
+            // sheen, if enabled
+            let sheen_weight = self.sheen.evaluate(&isect);
+            if sheen_weight > 0.0 {
+                let sheen_tint = self.sheen_tint.evaluate(&isect);
+                let c_sheen = Spectrum::new(1.0).lerp(c_tint, sheen_tint);
+                bsdf.add(arena.alloc(DisneySheen::new(c_sheen * (diffuse_weight * sheen_weight))));
+            }
+        }
+
+        // the microfacet distribution for the specular reflection and transmission
+        let aspect = (float(1.0) - self.anisotropic.evaluate(&isect) * float(0.9)).sqrt();
+        let (alpha_x, alpha_y) = alphas(rough, aspect);
+        let distribution = DisneyMicrofacetDistribution::new(alpha_x, alpha_y);
+
+        // specular is Trowbridge-Reitz with a modified Fresnel function
+        let specular_tint = self.specular_tint.evaluate(&isect);
+        let c_spec0 = (Spectrum::new(1.0).lerp(c_tint, specular_tint) * schlick_r0_from_eta(eta)).lerp(c, metallic);
+        let fresnel = DisneyFresnel::new(c_spec0, metallic, eta);
+        bsdf.add(arena.alloc(MicrofacetReflection::new(Spectrum::new(1.0), distribution, fresnel)));
+
+        // clearcoat
+        let clearcoat = self.clearcoat.evaluate(&isect);
+        if clearcoat > 0.0 {
+            let gloss = self.clearcoat_gloss.evaluate(&isect);
+            let alpha = (float(1.0) - gloss) * float(0.1) + gloss * float(0.001);
+            bsdf.add(arena.alloc(DisneyClearcoat::new(clearcoat, alpha)));
+        }
+
+        // specular transmission
+        if spec_trans > 0.0 {
+            // scaled by the square root of the color, so that it is the color after two refractions
+            let t = c.sqrt() * spec_trans;
+
+            if self.thin {
+                // scale roughness based on the index of refraction, Burley 2015, figure 15
+                let scaled = (float(0.65) * eta - float(0.35)) * rough;
+                let (alpha_x, alpha_y) = alphas(scaled, aspect);
+                let distribution = TrowbridgeReitzDistribution::new(alpha_x, alpha_y, true);
+                bsdf.add(arena.alloc(MicrofacetTransmission::new(t, distribution, float(1.0), eta, mode)));
+            } else {
+                bsdf.add(arena.alloc(MicrofacetTransmission::new(t, distribution, float(1.0), eta, mode)));
+            }
+        }
+
+        // diffuse transmission through thin surfaces
+        if self.thin {
+            bsdf.add(arena.alloc(LambertianTransmission::new(c * dt)));
+        }
+
+        isect.bsdf = Some(&*bsdf);
+        isect
+    }
+}
+
+/// The index of refraction whose reflectance at normal incidence is `0.08 * specular`.
+fn specular_to_eta(specular: Float) -> Float {
+    let r0 = num::clamp(specular, float(0.0), float(1.0)) * float(0.08);
+    (float(1.0) + r0.sqrt()) / (float(1.0) - r0.sqrt())
+}
+
+/// The alphas of the microfacet distribution, stretched in x and squashed in y by `aspect`.
+fn alphas(roughness: Float, aspect: Float) -> (Float, Float) {
+    let alpha = roughness * roughness;
+    (max(float(0.001), alpha / aspect), max(float(0.001), alpha * aspect))
+}
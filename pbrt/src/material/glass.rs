@@ -0,0 +1,100 @@
+use std::sync::Arc;
+use crate::prelude::*;
+use super::{ BumpMap, Material };
+use crate::interaction::SurfaceInteraction;
+use crate::bxdf::{
+    Bsdf,
+    FresnelDielectric,
+    MicrofacetReflection,
+    MicrofacetTransmission,
+    SpecularFresnel,
+    SpecularReflection,
+    SpecularTransmission,
+    TransportMode,
+    TrowbridgeReitzDistribution,
+};
+use crate::memory::MemoryArena;
+use crate::texture::Texture;
+
+/// A dielectric, like glass or water, which is perfectly smooth when both roughnesses are zero.
+#[derive(Clone, Debug)]
+pub struct GlassMaterial {
+    kr: Arc<dyn Texture<Spectrum> + Send + Sync>,
+    kt: Arc<dyn Texture<Spectrum> + Send + Sync>,
+    u_roughness: Arc<dyn Texture<Float> + Send + Sync>,
+    v_roughness: Arc<dyn Texture<Float> + Send + Sync>,
+    /// the index of refraction inside the surface, outside is assumed to be a vacuum
+    index: Arc<dyn Texture<Float> + Send + Sync>,
+    bump: Option<BumpMap>,
+    /// whether the roughnesses are in [0, 1], rather than the alphas of the microfacet distribution
+    remap_roughness: bool,
+}
+
+impl GlassMaterial {
+    #[cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
+    pub fn new(
+        kr: Arc<dyn Texture<Spectrum> + Send + Sync>,
+        kt: Arc<dyn Texture<Spectrum> + Send + Sync>,
+        u_roughness: Arc<dyn Texture<Float> + Send + Sync>,
+        v_roughness: Arc<dyn Texture<Float> + Send + Sync>,
+        index: Arc<dyn Texture<Float> + Send + Sync>,
+        bump: Option<BumpMap>,
+        remap_roughness: bool,
+    ) -> Self {
+        Self { kr, kt, u_roughness, v_roughness, index, bump, remap_roughness }
+    }
+}
+
+impl Material for GlassMaterial {
+    fn compute_scattering_functions(&self, isect: SurfaceInteraction<'a>, arena: &'a MemoryArena, mode: TransportMode, allow_multiple_lobes: bool) -> SurfaceInteraction<'a> {
+        let mut isect = match &self.bump {
+            Some(bump) => bump.apply(&isect),
+            None => isect,
+        };
+
+        let eta = self.index.evaluate(&isect);
+        let mut u_roughness = self.u_roughness.evaluate(&isect);
+        let mut v_roughness = self.v_roughness.evaluate(&isect);
+        let r = self.kr.evaluate(&isect).clamp(None, None);
+        let t = self.kt.evaluate(&isect).clamp(None, None);
+
+        let bsdf = arena.alloc(Bsdf::new(&isect, Some(eta)));
+
+        if r.is_black() && t.is_black() {
+            isect.bsdf = Some(&*bsdf);
+            return isect;
+        }
+
+        let is_specular = u_roughness == 0.0 && v_roughness == 0.0;
+        if is_specular && allow_multiple_lobes {
+            // a single lobe that chooses between reflection and transmission by the Fresnel reflectance
+            bsdf.add(arena.alloc(SpecularFresnel::new(r, t, float(1.0), eta, mode)));
+        } else {
+            if self.remap_roughness {
+                u_roughness = TrowbridgeReitzDistribution::roughness_to_alpha(u_roughness);
+                v_roughness = TrowbridgeReitzDistribution::roughness_to_alpha(v_roughness);
+            }
+            let distribution = TrowbridgeReitzDistribution::new(u_roughness, v_roughness, true);
+
+            if !r.is_black() {
+                let fresnel = FresnelDielectric::new(float(1.0), eta);
+                if is_specular {
+                    bsdf.add(arena.alloc(SpecularReflection::new(r, fresnel)));
+                } else {
+                    bsdf.add(arena.alloc(MicrofacetReflection::new(r, distribution, fresnel)));
+                }
+            }
+
+            if !t.is_black() {
+                if is_specular {
+                    bsdf.add(arena.alloc(SpecularTransmission::new(t, float(1.0), eta, mode)));
+                } else {
+                    bsdf.add(arena.alloc(MicrofacetTransmission::new(t, distribution, float(1.0), eta, mode)));
+                }
+            }
+        }
+
+        isect.bsdf = Some(&*bsdf);
+        isect
+    }
+}
@@ -0,0 +1,63 @@
+use std::sync::Arc;
+use crate::prelude::*;
+use super::{ BumpMap, Material };
+use crate::interaction::SurfaceInteraction;
+use crate::bxdf::{ Bsdf, FresnelConductor, MicrofacetReflection, TransportMode, TrowbridgeReitzDistribution };
+use crate::memory::MemoryArena;
+use crate::texture::Texture;
+
+/// A rough conductor, with the complex index of refraction `eta + i * k`.
+#[derive(Clone, Debug)]
+pub struct MetalMaterial {
+    eta: Arc<dyn Texture<Spectrum> + Send + Sync>,
+    k: Arc<dyn Texture<Spectrum> + Send + Sync>,
+    roughness: Arc<dyn Texture<Float> + Send + Sync>,
+    /// override `roughness` in the u and v directions, for anisotropic metals
+    u_roughness: Option<Arc<dyn Texture<Float> + Send + Sync>>,
+    v_roughness: Option<Arc<dyn Texture<Float> + Send + Sync>>,
+    bump: Option<BumpMap>,
+    /// whether the roughnesses are in [0, 1], rather than the alphas of the microfacet distribution
+    remap_roughness: bool,
+}
+
+impl MetalMaterial {
+    #[cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
+    pub fn new(
+        eta: Arc<dyn Texture<Spectrum> + Send + Sync>,
+        k: Arc<dyn Texture<Spectrum> + Send + Sync>,
+        roughness: Arc<dyn Texture<Float> + Send + Sync>,
+        u_roughness: Option<Arc<dyn Texture<Float> + Send + Sync>>,
+        v_roughness: Option<Arc<dyn Texture<Float> + Send + Sync>>,
+        bump: Option<BumpMap>,
+        remap_roughness: bool,
+    ) -> Self {
+        Self { eta, k, roughness, u_roughness, v_roughness, bump, remap_roughness }
+    }
+}
+
+impl Material for MetalMaterial {
+    fn compute_scattering_functions(&self, isect: SurfaceInteraction<'a>, arena: &'a MemoryArena, _mode: TransportMode, _allow_multiple_lobes: bool) -> SurfaceInteraction<'a> {
+        let mut isect = match &self.bump {
+            Some(bump) => bump.apply(&isect),
+            None => isect,
+        };
+
+        let bsdf = arena.alloc(Bsdf::new(&isect, None));
+
+        let roughness = |texture: &Option<Arc<dyn Texture<Float> + Send + Sync>>| {
+            let roughness = texture.as_ref().unwrap_or(&self.roughness).evaluate(&isect);
+            if self.remap_roughness {
+                TrowbridgeReitzDistribution::roughness_to_alpha(roughness)
+            } else {
+                roughness
+            }
+        };
+        let distribution = TrowbridgeReitzDistribution::new(roughness(&self.u_roughness), roughness(&self.v_roughness), true);
+
+        let fresnel = FresnelConductor::new(Spectrum::new(1.0), self.eta.evaluate(&isect), self.k.evaluate(&isect));
+        bsdf.add(arena.alloc(MicrofacetReflection::new(Spectrum::new(1.0), distribution, fresnel)));
+
+        isect.bsdf = Some(&*bsdf);
+        isect
+    }
+}
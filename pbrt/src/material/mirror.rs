@@ -0,0 +1,39 @@
+use std::sync::Arc;
+use crate::prelude::*;
+use super::{ BumpMap, Material };
+use crate::interaction::SurfaceInteraction;
+use crate::bxdf::{ Bsdf, FresnelNoOp, SpecularReflection, TransportMode };
+use crate::memory::MemoryArena;
+use crate::texture::Texture;
+
+/// A perfect mirror, that reflects `kr` of the light in every direction.
+#[derive(Clone, Debug)]
+pub struct MirrorMaterial {
+    kr: Arc<dyn Texture<Spectrum> + Send + Sync>,
+    bump: Option<BumpMap>,
+}
+
+impl MirrorMaterial {
+    pub fn new(kr: Arc<dyn Texture<Spectrum> + Send + Sync>, bump: Option<BumpMap>) -> Self {
+        Self { kr, bump }
+    }
+}
+
+impl Material for MirrorMaterial {
+    fn compute_scattering_functions(&self, isect: SurfaceInteraction<'a>, arena: &'a MemoryArena, _mode: TransportMode, _allow_multiple_lobes: bool) -> SurfaceInteraction<'a> {
+        let mut isect = match &self.bump {
+            Some(bump) => bump.apply(&isect),
+            None => isect,
+        };
+
+        let bsdf = arena.alloc(Bsdf::new(&isect, None));
+
+        let r = self.kr.evaluate(&isect).clamp(None, None);
+        if !r.is_black() {
+            bsdf.add(arena.alloc(SpecularReflection::new(r, FresnelNoOp)));
+        }
+
+        isect.bsdf = Some(&*bsdf);
+        isect
+    }
+}
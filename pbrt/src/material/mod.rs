@@ -1,17 +1,124 @@
 use std::fmt::Debug;
 use std::sync::Arc;
+use cgmath::prelude::*;
 use crate::prelude::*;
 use crate::bxdf::TransportMode;
 use crate::interaction::SurfaceInteraction;
+use crate::memory::MemoryArena;
 use crate::texture::Texture;
 
+mod disney;
+pub use self::disney::DisneyMaterial;
+
+mod glass;
+pub use self::glass::GlassMaterial;
+
 mod matte;
 pub use self::matte::MatteMaterial;
 
+mod metal;
+pub use self::metal::MetalMaterial;
+
+mod mirror;
+pub use self::mirror::MirrorMaterial;
+
+mod plastic;
+pub use self::plastic::PlasticMaterial;
+
+mod substrate;
+pub use self::substrate::SubstrateMaterial;
+
+mod uber;
+pub use self::uber::UberMaterial;
+
 pub trait Material: Debug {
-    fn compute_scattering_functions(&self, isect: SurfaceInteraction<'a>, arena: &(), mode: TransportMode, allow_multiple_lobes: bool) -> SurfaceInteraction<'a>;
+    fn compute_scattering_functions(&self, isect: SurfaceInteraction<'a>, arena: &'a MemoryArena, mode: TransportMode, allow_multiple_lobes: bool) -> SurfaceInteraction<'a>;
+}
+
+/// Perturbs the shading geometry of a surface before a material computes its BSDF.
+#[derive(Clone, Debug)]
+pub enum BumpMap {
+    /// a displacement along the shading normal
+    Displacement(Arc<dyn Texture<Float> + Send + Sync>),
+    /// tangent-space normals, with each of rgb mapped from [0, 1] to [-1, 1]
+    Normal(Arc<dyn Texture<Spectrum> + Send + Sync>),
+}
+
+impl BumpMap {
+    pub fn apply(&self, si: &SurfaceInteraction<'a>) -> SurfaceInteraction<'a> {
+        match self {
+            BumpMap::Displacement(d) => bump(si, d),
+            BumpMap::Normal(normal) => normal_map(si, normal),
+        }
+    }
 }
 
-pub fn bump(_si: &SurfaceInteraction<'a>, _t: &Arc<dyn Texture<Float> + Send + Sync>) -> SurfaceInteraction<'a> {
-    unimplemented!()
+/// Offsets the shading geometry by the displacement `d`, which is estimated
+/// by forward differences at offsets of about a pixel in u and v.
+pub fn bump(si: &SurfaceInteraction<'a>, d: &Arc<dyn Texture<Float> + Send + Sync>) -> SurfaceInteraction<'a> {
+    let mut si_eval = si.clone();
+    let n = Normal::from(si.shading.dpdu.cross(si.shading.dpdv));
+
+    // shift si_eval du in the u direction
+    let mut du = float(0.5) * (si.dudx.abs() + si.dudy.abs());
+    if du == 0.0 {
+        du = float(0.0005);
+    }
+    si_eval.p = si.p + si.shading.dpdu * du;
+    si_eval.uv = si.uv + Vector2f::new(du, float(0.0));
+    si_eval.n = Some((*n + *si.dndu * du).normalize().into());
+    let u_displace = d.evaluate(&si_eval);
+
+    // shift si_eval dv in the v direction
+    let mut dv = float(0.5) * (si.dvdx.abs() + si.dvdy.abs());
+    if dv == 0.0 {
+        dv = float(0.0005);
+    }
+    si_eval.p = si.p + si.shading.dpdv * dv;
+    si_eval.uv = si.uv + Vector2f::new(float(0.0), dv);
+    si_eval.n = Some((*n + *si.dndv * dv).normalize().into());
+    let v_displace = d.evaluate(&si_eval);
+
+    let displace = d.evaluate(si);
+
+    // compute bump-mapped differential geometry
+    let dpdu = si.shading.dpdu + *si.shading.n * ((u_displace - displace) / du) + *si.shading.dndu * displace;
+    let dpdv = si.shading.dpdv + *si.shading.n * ((v_displace - displace) / dv) + *si.shading.dndv * displace;
+
+    let mut si = si.clone();
+    let (dndu, dndv) = (si.shading.dndu, si.shading.dndv);
+    si.set_shading_geometry(dpdu, dpdv, dndu, dndv, false);
+    si
+}
+
+/// Replaces the shading normal with one from `normal`, in the tangent space given by
+/// the shading normal and `dpdu`, and rotates `dpdu` and `dpdv` to be perpendicular to it.
+pub fn normal_map(si: &SurfaceInteraction<'a>, normal: &Arc<dyn Texture<Spectrum> + Send + Sync>) -> SurfaceInteraction<'a> {
+    let rgb = normal.evaluate(si);
+    let ns = Vector3f::new(
+        float(2.0) * rgb[0] - float(1.0),
+        float(2.0) * rgb[1] - float(1.0),
+        float(2.0) * rgb[2] - float(1.0),
+    );
+    if ns.magnitude2() == 0.0 {
+        return si.clone();
+    }
+    let ns = ns.normalize();
+
+    // transform the normal from tangent space to world space
+    let z = *si.shading.n;
+    let x = si.shading.dpdu.normalize();
+    let y = z.cross(x);
+    let ns = (x * ns.x + y * ns.y + z * ns.z).normalize();
+
+    // keep the lengths of the partial derivatives, but make them perpendicular to ns
+    let u_len = si.shading.dpdu.magnitude();
+    let v_len = si.shading.dpdv.magnitude();
+    let dpdu = (si.shading.dpdu - ns * si.shading.dpdu.dot(ns)).normalize() * u_len;
+    let dpdv = ns.cross(dpdu).normalize() * v_len;
+
+    let mut si = si.clone();
+    let (dndu, dndv) = (si.shading.dndu, si.shading.dndv);
+    si.set_shading_geometry(dpdu, dpdv, dndu, dndv, false);
+    si
 }
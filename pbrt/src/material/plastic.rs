@@ -0,0 +1,64 @@
+use std::sync::Arc;
+use crate::prelude::*;
+use super::{ BumpMap, Material };
+use crate::interaction::SurfaceInteraction;
+use crate::bxdf::{ Bsdf, FresnelDielectric, LambertianReflection, MicrofacetReflection, TransportMode, TrowbridgeReitzDistribution };
+use crate::memory::MemoryArena;
+use crate::texture::Texture;
+
+/// A diffuse base with a glossy specular coating.
+#[derive(Clone, Debug)]
+pub struct PlasticMaterial {
+    kd: Arc<dyn Texture<Spectrum> + Send + Sync>,
+    ks: Arc<dyn Texture<Spectrum> + Send + Sync>,
+    roughness: Arc<dyn Texture<Float> + Send + Sync>,
+    bump: Option<BumpMap>,
+    /// whether `roughness` is in [0, 1], rather than the alpha of the microfacet distribution
+    remap_roughness: bool,
+}
+
+impl PlasticMaterial {
+    pub fn new(
+        kd: Arc<dyn Texture<Spectrum> + Send + Sync>,
+        ks: Arc<dyn Texture<Spectrum> + Send + Sync>,
+        roughness: Arc<dyn Texture<Float> + Send + Sync>,
+        bump: Option<BumpMap>,
+        remap_roughness: bool,
+    ) -> Self {
+        Self { kd, ks, roughness, bump, remap_roughness }
+    }
+}
+
+impl Material for PlasticMaterial {
+    fn compute_scattering_functions(&self, isect: SurfaceInteraction<'a>, arena: &'a MemoryArena, _mode: TransportMode, _allow_multiple_lobes: bool) -> SurfaceInteraction<'a> {
+        let mut isect = match &self.bump {
+            Some(bump) => bump.apply(&isect),
+            None => isect,
+        };
+
+        let bsdf = arena.alloc(Bsdf::new(&isect, None));
+
+        // initialize diffuse component of plastic material
+        let kd = self.kd.evaluate(&isect).clamp(None, None);
+        if !kd.is_black() {
+            bsdf.add(arena.alloc(LambertianReflection::new(kd)));
+        }
+
+        // initialize specular component of plastic material
+        let ks = self.ks.evaluate(&isect).clamp(None, None);
+        if !ks.is_black() {
+            let fresnel = FresnelDielectric::new(float(1.5), float(1.0));
+
+            let mut roughness = self.roughness.evaluate(&isect);
+            if self.remap_roughness {
+                roughness = TrowbridgeReitzDistribution::roughness_to_alpha(roughness);
+            }
+            let distribution = TrowbridgeReitzDistribution::new(roughness, roughness, true);
+
+            bsdf.add(arena.alloc(MicrofacetReflection::new(ks, distribution, fresnel)));
+        }
+
+        isect.bsdf = Some(&*bsdf);
+        isect
+    }
+}
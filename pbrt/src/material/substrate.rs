@@ -0,0 +1,61 @@
+use std::sync::Arc;
+use crate::prelude::*;
+use super::{ BumpMap, Material };
+use crate::interaction::SurfaceInteraction;
+use crate::bxdf::{ Bsdf, FresnelBlend, TransportMode, TrowbridgeReitzDistribution };
+use crate::memory::MemoryArena;
+use crate::texture::Texture;
+
+/// A glossy coating over a diffuse base, whose reflection grows stronger at grazing angles.
+#[derive(Clone, Debug)]
+pub struct SubstrateMaterial {
+    kd: Arc<dyn Texture<Spectrum> + Send + Sync>,
+    ks: Arc<dyn Texture<Spectrum> + Send + Sync>,
+    u_roughness: Arc<dyn Texture<Float> + Send + Sync>,
+    v_roughness: Arc<dyn Texture<Float> + Send + Sync>,
+    bump: Option<BumpMap>,
+    /// whether the roughnesses are in [0, 1], rather than the alphas of the microfacet distribution
+    remap_roughness: bool,
+}
+
+impl SubstrateMaterial {
+    pub fn new(
+        kd: Arc<dyn Texture<Spectrum> + Send + Sync>,
+        ks: Arc<dyn Texture<Spectrum> + Send + Sync>,
+        u_roughness: Arc<dyn Texture<Float> + Send + Sync>,
+        v_roughness: Arc<dyn Texture<Float> + Send + Sync>,
+        bump: Option<BumpMap>,
+        remap_roughness: bool,
+    ) -> Self {
+        Self { kd, ks, u_roughness, v_roughness, bump, remap_roughness }
+    }
+}
+
+impl Material for SubstrateMaterial {
+    fn compute_scattering_functions(&self, isect: SurfaceInteraction<'a>, arena: &'a MemoryArena, _mode: TransportMode, _allow_multiple_lobes: bool) -> SurfaceInteraction<'a> {
+        let mut isect = match &self.bump {
+            Some(bump) => bump.apply(&isect),
+            None => isect,
+        };
+
+        let bsdf = arena.alloc(Bsdf::new(&isect, None));
+
+        let d = self.kd.evaluate(&isect).clamp(None, None);
+        let s = self.ks.evaluate(&isect).clamp(None, None);
+
+        if !d.is_black() || !s.is_black() {
+            let mut u_roughness = self.u_roughness.evaluate(&isect);
+            let mut v_roughness = self.v_roughness.evaluate(&isect);
+            if self.remap_roughness {
+                u_roughness = TrowbridgeReitzDistribution::roughness_to_alpha(u_roughness);
+                v_roughness = TrowbridgeReitzDistribution::roughness_to_alpha(v_roughness);
+            }
+            let distribution = TrowbridgeReitzDistribution::new(u_roughness, v_roughness, true);
+
+            bsdf.add(arena.alloc(FresnelBlend::new(d, s, distribution)));
+        }
+
+        isect.bsdf = Some(&*bsdf);
+        isect
+    }
+}
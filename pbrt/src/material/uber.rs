@@ -0,0 +1,114 @@
+use std::sync::Arc;
+use crate::prelude::*;
+use super::{ BumpMap, Material };
+use crate::interaction::SurfaceInteraction;
+use crate::bxdf::{
+    Bsdf,
+    FresnelDielectric,
+    LambertianReflection,
+    MicrofacetReflection,
+    SpecularReflection,
+    SpecularTransmission,
+    TransportMode,
+    TrowbridgeReitzDistribution,
+};
+use crate::memory::MemoryArena;
+use crate::texture::Texture;
+
+/// A combination of diffuse, glossy and specular reflection and specular transmission,
+/// for the materials of scenes converted from other formats.
+///
+/// Light that isn't stopped by `opacity` passes straight through the surface.
+#[derive(Clone, Debug)]
+pub struct UberMaterial {
+    kd: Arc<dyn Texture<Spectrum> + Send + Sync>,
+    ks: Arc<dyn Texture<Spectrum> + Send + Sync>,
+    kr: Arc<dyn Texture<Spectrum> + Send + Sync>,
+    kt: Arc<dyn Texture<Spectrum> + Send + Sync>,
+    roughness: Arc<dyn Texture<Float> + Send + Sync>,
+    /// override `roughness` in the u and v directions, for anisotropic glossy reflection
+    u_roughness: Option<Arc<dyn Texture<Float> + Send + Sync>>,
+    v_roughness: Option<Arc<dyn Texture<Float> + Send + Sync>>,
+    eta: Arc<dyn Texture<Float> + Send + Sync>,
+    opacity: Arc<dyn Texture<Spectrum> + Send + Sync>,
+    bump: Option<BumpMap>,
+    /// whether the roughnesses are in [0, 1], rather than the alphas of the microfacet distribution
+    remap_roughness: bool,
+}
+
+impl UberMaterial {
+    #[cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
+    pub fn new(
+        kd: Arc<dyn Texture<Spectrum> + Send + Sync>,
+        ks: Arc<dyn Texture<Spectrum> + Send + Sync>,
+        kr: Arc<dyn Texture<Spectrum> + Send + Sync>,
+        kt: Arc<dyn Texture<Spectrum> + Send + Sync>,
+        roughness: Arc<dyn Texture<Float> + Send + Sync>,
+        u_roughness: Option<Arc<dyn Texture<Float> + Send + Sync>>,
+        v_roughness: Option<Arc<dyn Texture<Float> + Send + Sync>>,
+        eta: Arc<dyn Texture<Float> + Send + Sync>,
+        opacity: Arc<dyn Texture<Spectrum> + Send + Sync>,
+        bump: Option<BumpMap>,
+        remap_roughness: bool,
+    ) -> Self {
+        Self { kd, ks, kr, kt, roughness, u_roughness, v_roughness, eta, opacity, bump, remap_roughness }
+    }
+}
+
+impl Material for UberMaterial {
+    fn compute_scattering_functions(&self, isect: SurfaceInteraction<'a>, arena: &'a MemoryArena, mode: TransportMode, _allow_multiple_lobes: bool) -> SurfaceInteraction<'a> {
+        let mut isect = match &self.bump {
+            Some(bump) => bump.apply(&isect),
+            None => isect,
+        };
+
+        let eta = self.eta.evaluate(&isect);
+        let op = self.opacity.evaluate(&isect).clamp(None, None);
+        let t = (Spectrum::new(1.0) - op).clamp(None, None);
+
+        // the light that passes through the surface doesn't refract
+        let bsdf = if !t.is_black() {
+            let bsdf = arena.alloc(Bsdf::new(&isect, None));
+            bsdf.add(arena.alloc(SpecularTransmission::new(t, float(1.0), float(1.0), mode)));
+            bsdf
+        } else {
+            arena.alloc(Bsdf::new(&isect, Some(eta)))
+        };
+
+        let kd = op * self.kd.evaluate(&isect).clamp(None, None);
+        if !kd.is_black() {
+            bsdf.add(arena.alloc(LambertianReflection::new(kd)));
+        }
+
+        let ks = op * self.ks.evaluate(&isect).clamp(None, None);
+        if !ks.is_black() {
+            let fresnel = FresnelDielectric::new(float(1.0), eta);
+
+            let roughness = |texture: &Option<Arc<dyn Texture<Float> + Send + Sync>>| {
+                let roughness = texture.as_ref().unwrap_or(&self.roughness).evaluate(&isect);
+                if self.remap_roughness {
+                    TrowbridgeReitzDistribution::roughness_to_alpha(roughness)
+                } else {
+                    roughness
+                }
+            };
+            let distribution = TrowbridgeReitzDistribution::new(roughness(&self.u_roughness), roughness(&self.v_roughness), true);
+
+            bsdf.add(arena.alloc(MicrofacetReflection::new(ks, distribution, fresnel)));
+        }
+
+        let kr = op * self.kr.evaluate(&isect).clamp(None, None);
+        if !kr.is_black() {
+            let fresnel = FresnelDielectric::new(float(1.0), eta);
+            bsdf.add(arena.alloc(SpecularReflection::new(kr, fresnel)));
+        }
+
+        let kt = op * self.kt.evaluate(&isect).clamp(None, None);
+        if !kt.is_black() {
+            bsdf.add(arena.alloc(SpecularTransmission::new(kt, float(1.0), eta, mode)));
+        }
+
+        isect.bsdf = Some(&*bsdf);
+        isect
+    }
+}
@@ -304,13 +304,11 @@ impl Bounds3f {
         (center.into_point(), radius)
     }
 
-    pub fn intersect_p(&self, ray: Ray) -> Option<(Option<Float>, Option<Float>)> {
+    /// The parametric range of `ray`, up to `ray.max`, that is inside the bounds.
+    pub fn intersect_p(&self, ray: Ray) -> Option<(Float, Float)> {
         let mut t0 = float(0.0);
         let mut t1 = ray.max;
 
-        let hit_t0: Option<Float> = None;
-        let hit_t1: Option<Float> = None;
-
         for i in 0..3 {
             // update interval for ith bb slab
             let inv_ray_dir = 1.0 / ray.direction[i].raw();
@@ -332,10 +330,7 @@ impl Bounds3f {
             }
         }
 
-        hit_t0.map(|_| t0);
-        hit_t1.map(|_| t1);
-
-        Some((hit_t0, hit_t1))
+        Some((t0, t1))
     }
 
     pub fn intersect_p_precomputed(&self, ray: Ray, inv_dir: Vector3f, dir_is_negative: [bool; 3]) -> bool {
@@ -1,6 +1,7 @@
 use cgmath::prelude::*;
 use shrinkwraprs::Shrinkwrap;
 use crate::prelude::*;
+use crate::medium::MediumId;
 
 pub fn offset_ray_origin(p: &Point3f, p_err: &Vector3f, n: &Normal, w: &Vector3f) -> Point3f {
     let d = (**n).abs().dot(*p_err);
@@ -45,7 +46,8 @@ pub struct Ray {
     pub direction: Vector3f,
     pub max: Float,
     pub time: Float,
-    pub medium: Option<()>,
+    /// the medium that the origin of the ray is in
+    pub medium: Option<MediumId>,
 }
 
 impl Ray {
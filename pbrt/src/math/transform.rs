@@ -86,7 +86,7 @@ impl Transform {
 
         let err = |n: usize| {
             (gammaf(3) + float(1.0)) *
-            (m[0][n].abs() * p_err.x + m[1][n].abs() * p_err.x + m[2][n].abs() * p_err.z) +
+            (m[0][n].abs() * p_err.x + m[1][n].abs() * p_err.y + m[2][n].abs() * p_err.z) +
             (gammaf(3)) *
             ((m[0][n] * x).abs() + (m[1][n] * y).abs() + (m[2][n] * z).abs() + m[3][n].abs())
         };
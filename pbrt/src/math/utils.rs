@@ -27,6 +27,39 @@ pub fn gamma_correct(value: Float) -> Float {
     }
 }
 
+/// Converts an sRGB value back to linear.
+#[inline(always)]
+#[cfg_attr(feature = "cargo-clippy", allow(unreadable_literal))]
+pub fn inverse_gamma_correct(value: Float) -> Float {
+    if value <= 0.04045 {
+        value / float(12.92)
+    } else {
+        ((value + float(0.055)) / float(1.055)).powf(float(2.4))
+    }
+}
+
+/// Builds two vectors that form an orthonormal basis with the **normalised** `v1`.
+pub fn coordinate_system(v1: Vector3f) -> (Vector3f, Vector3f) {
+    let v2 = if v1.x.abs() > v1.y.abs() {
+        Vector3f::new(-v1.z, float(0.0), v1.x) / (v1.x * v1.x + v1.z * v1.z).sqrt()
+    } else {
+        Vector3f::new(float(0.0), v1.z, -v1.y) / (v1.y * v1.y + v1.z * v1.z).sqrt()
+    };
+
+    (v2, v1.cross(v2))
+}
+
+/// The remainder of `a / b` that has the same sign as `b`.
+#[inline(always)]
+pub fn modulo(a: i32, b: i32) -> i32 {
+    let result = a % b;
+    if (result < 0) != (b < 0) && result != 0 {
+        result + b
+    } else {
+        result
+    }
+}
+
 #[inline(always)]
 pub fn f32_to_bits(f: impl Into<f32>) -> u32 {
     let f: f32 = f.into();
@@ -183,3 +216,55 @@ pub fn partition_by<T, B: Copy + PartialOrd>(v: &mut [T], f: impl Fn(&T) -> B) {
         partition_by(left, f);
     }
 }
+
+/// Approximation of the error function, accurate to about 1e-7.
+#[cfg_attr(feature = "cargo-clippy", allow(unreadable_literal))]
+pub fn erf(x: Float) -> Float {
+    let a1 = 0.254829592;
+    let a2 = -0.284496736;
+    let a3 = 1.421413741;
+    let a4 = -1.453152027;
+    let a5 = 1.061405429;
+    let p = 0.3275911;
+
+    let sign = x.raw().signum();
+    let x = x.raw().abs();
+
+    let t = 1.0 / (1.0 + p * x);
+    let y = 1.0 - (((((a5 * t + a4) * t) + a3) * t + a2) * t + a1) * t * (-x * x).exp();
+
+    float(sign * y)
+}
+
+/// Approximation of the inverse of `erf`.
+#[cfg_attr(feature = "cargo-clippy", allow(unreadable_literal))]
+pub fn erf_inv(x: Float) -> Float {
+    let x = num::clamp(x.raw(), -0.99999, 0.99999);
+    let mut w = -((1.0 - x) * (1.0 + x)).ln();
+
+    let p = if w < 5.0 {
+        w -= 2.5;
+        let mut p = 2.81022636e-08;
+        p = 3.43273939e-07 + p * w;
+        p = -3.5233877e-06 + p * w;
+        p = -4.39150654e-06 + p * w;
+        p = 0.00021858087 + p * w;
+        p = -0.00125372503 + p * w;
+        p = -0.00417768164 + p * w;
+        p = 0.246640727 + p * w;
+        1.50140941 + p * w
+    } else {
+        w = w.sqrt() - 3.0;
+        let mut p = -0.000200214257;
+        p = 0.000100950558 + p * w;
+        p = 0.00134934322 + p * w;
+        p = -0.00367342844 + p * w;
+        p = 0.00573950773 + p * w;
+        p = -0.0076224613 + p * w;
+        p = 0.00943887047 + p * w;
+        p = 1.00167406 + p * w;
+        2.83297682 + p * w
+    };
+
+    float(p * x)
+}
@@ -0,0 +1,183 @@
+use std::cmp::max;
+use cgmath::prelude::*;
+use crate::prelude::*;
+use crate::interaction::MediumInteraction;
+use crate::memory::MemoryArena;
+use crate::sampler::Sampler;
+use super::{ HenyeyGreenstein, Medium, MediumInterface };
+
+/// A medium whose density varies through space, given by a 3d grid of samples that
+/// covers `[0, 1]^3` in medium space, and is zero outside of it.
+///
+/// The density scales `sigma_a` and `sigma_s`. Distances are sampled by delta tracking,
+/// and transmittance is estimated by ratio tracking, against the maximum density.
+/// Both need a single extinction coefficient, so `sigma_a + sigma_s` has to be grey.
+#[derive(Clone, Debug)]
+pub struct GridDensityMedium {
+    sigma_s: Spectrum,
+    sigma_t: Float,
+    g: Float,
+    resolution: [usize; 3],
+    density: Vec<Float>,
+    max_density: Float,
+    world_to_medium: Transform,
+}
+
+impl GridDensityMedium {
+    /// The extinction coefficient `sigma_a + sigma_s`, if it's the same in every channel,
+    /// give or take rounding.
+    pub fn grey_sigma_t(sigma_a: Spectrum, sigma_s: Spectrum) -> Option<Float> {
+        let sigma_t = sigma_a + sigma_s;
+        let first = sigma_t[0];
+        if sigma_t.iter().all(|c| (*c - first).abs() <= first * float(1e-4)) {
+            Some(first)
+        } else {
+            None
+        }
+    }
+
+    /// `density` has `resolution[0] * resolution[1] * resolution[2]` values,
+    /// indexed by `(z * ny + y) * nx + x`.
+    pub fn new(
+        sigma_a: Spectrum,
+        sigma_s: Spectrum,
+        g: Float,
+        resolution: [usize; 3],
+        density: Vec<Float>,
+        medium_to_world: &Transform,
+    ) -> Self {
+        assert_eq!(density.len(), resolution[0] * resolution[1] * resolution[2]);
+        let sigma_t = Self::grey_sigma_t(sigma_a, sigma_s)
+            .expect("GridDensityMedium needs the same sigma_a + sigma_s in every channel");
+        let max_density = density.iter().cloned().max().unwrap_or_else(|| float(0.0));
+
+        Self {
+            sigma_s,
+            sigma_t,
+            g,
+            resolution,
+            density,
+            max_density,
+            world_to_medium: medium_to_world.inverse(),
+        }
+    }
+
+    /// The density of the voxel at `p`, or zero outside of the grid.
+    fn d(&self, p: [i32; 3]) -> Float {
+        let inside = (0..3).all(|i| p[i] >= 0 && (p[i] as usize) < self.resolution[i]);
+        if !inside {
+            return float(0.0);
+        }
+
+        let (nx, ny) = (self.resolution[0], self.resolution[1]);
+        let (x, y, z) = (p[0] as usize, p[1] as usize, p[2] as usize);
+        self.density[(z * ny + y) * nx + x]
+    }
+
+    /// Trilinearly interpolates the density at `p`, in medium space.
+    fn density(&self, p: Point3f) -> Float {
+        // the samples are at the centers of the voxels
+        let mut pi = [0; 3];
+        let mut d = [float(0.0); 3];
+        for i in 0..3 {
+            let s = p[i] * float(self.resolution[i] as f32) - float(0.5);
+            let floor = s.floor();
+            pi[i] = floor.raw() as i32;
+            d[i] = s - floor;
+        }
+
+        let at = |x, y, z| self.d([pi[0] + x, pi[1] + y, pi[2] + z]);
+        let d00 = lerp(d[0], at(0, 0, 0), at(1, 0, 0));
+        let d10 = lerp(d[0], at(0, 1, 0), at(1, 1, 0));
+        let d01 = lerp(d[0], at(0, 0, 1), at(1, 0, 1));
+        let d11 = lerp(d[0], at(0, 1, 1), at(1, 1, 1));
+        let d0 = lerp(d[1], d00, d10);
+        let d1 = lerp(d[1], d01, d11);
+        lerp(d[2], d0, d1)
+    }
+
+    /// Transforms `ray` to medium space, and finds the range of it that overlaps the grid.
+    ///
+    /// The direction of the ray is normalized in world space first, so that distances
+    /// along it are the same as in world space.
+    fn medium_ray(&self, ray: &Ray) -> Option<(Ray, Float, Float)> {
+        if self.max_density == 0.0 || self.sigma_t == 0.0 {
+            return None;
+        }
+
+        let length = ray.direction.magnitude();
+        let mut normalized = Ray::new(ray.origin, ray.direction / length);
+        normalized.max = ray.max * length;
+        let medium_ray = self.world_to_medium.transform_ray(normalized);
+
+        let bounds = Bounds3f::new(Point3f::new(float(0.0), float(0.0), float(0.0)), Point3f::new(float(1.0), float(1.0), float(1.0)));
+        bounds.intersect_p(medium_ray)
+            .map(|(t_min, t_max)| (medium_ray, t_min, t_max))
+    }
+
+    /// The distance to the next tentative collision, against the maximum density.
+    fn step(&self, sampler: &mut dyn Sampler) -> Float {
+        -(float(1.0) - sampler.get_1d()).ln() / (self.max_density * self.sigma_t)
+    }
+}
+
+impl Medium for GridDensityMedium {
+    fn tr(&self, ray: &Ray, sampler: &mut dyn Sampler) -> Spectrum {
+        let (medium_ray, t_min, t_max) = match self.medium_ray(ray) {
+            Some(segment) => segment,
+            None => return Spectrum::new(1.0),
+        };
+
+        // ratio tracking, each tentative collision removes the fraction of light that a real one would
+        let mut tr = float(1.0);
+        let mut t = t_min;
+        loop {
+            t += self.step(sampler);
+            if t >= t_max {
+                break;
+            }
+
+            let density = self.density(medium_ray.position(t));
+            tr *= float(1.0) - max(float(0.0), density / self.max_density);
+
+            // russian roulette once the transmittance is low
+            if tr < 0.1 {
+                let q = max(float(0.05), float(1.0) - tr);
+                if sampler.get_1d() < q {
+                    return Spectrum::new(0.0);
+                }
+                tr /= float(1.0) - q;
+            }
+        }
+
+        Spectrum::new(tr)
+    }
+
+    fn sample(&self, ray: &Ray, sampler: &mut dyn Sampler, arena: &'a MemoryArena) -> (Spectrum, Option<MediumInteraction<'a>>) {
+        let (medium_ray, t_min, t_max) = match self.medium_ray(ray) {
+            Some(segment) => segment,
+            None => return (Spectrum::new(1.0), None),
+        };
+
+        // delta tracking, a tentative collision is real with a probability of the relative density
+        let mut t = t_min;
+        loop {
+            t += self.step(sampler);
+            if t >= t_max {
+                return (Spectrum::new(1.0), None);
+            }
+
+            if self.density(medium_ray.position(t)) / self.max_density > sampler.get_1d() {
+                let phase = arena.alloc(HenyeyGreenstein::new(self.g));
+                // `t` is a distance, and `ray`'s direction isn't necessarily normalized
+                let p = ray.position(t / ray.direction.magnitude());
+                let isect = MediumInteraction::new(p, -ray.direction, ray.time, MediumInterface::new(ray.medium), phase);
+                return (self.sigma_s / self.sigma_t, Some(isect));
+            }
+        }
+    }
+}
+
+fn lerp(t: Float, a: Float, b: Float) -> Float {
+    (float(1.0) - t) * a + t * b
+}
@@ -0,0 +1,75 @@
+use std::cmp::min;
+use cgmath::prelude::*;
+use crate::prelude::*;
+use crate::interaction::MediumInteraction;
+use crate::memory::MemoryArena;
+use crate::sampler::Sampler;
+use super::{ HenyeyGreenstein, Medium, MediumInterface };
+
+/// A medium with the same density everywhere.
+#[derive(Clone, Debug)]
+pub struct HomogeneousMedium {
+    sigma_a: Spectrum,
+    sigma_s: Spectrum,
+    sigma_t: Spectrum,
+    g: Float,
+}
+
+impl HomogeneousMedium {
+    /// `sigma_a` and `sigma_s` are the absorption and scattering coefficients, per unit distance,
+    /// and `g` is the asymmetry parameter of the Henyey-Greenstein phase function.
+    pub fn new(sigma_a: Spectrum, sigma_s: Spectrum, g: Float) -> Self {
+        Self {
+            sigma_a,
+            sigma_s,
+            sigma_t: sigma_a + sigma_s,
+            g,
+        }
+    }
+
+    /// The transmittance over a distance of `d`.
+    fn tr_distance(&self, d: Float) -> Spectrum {
+        // avoid multiplying infinity by zero for a ray that never ends in a medium that doesn't absorb
+        let d = min(d, float(FloatPrim::MAX));
+        (self.sigma_t * -d).exp()
+    }
+}
+
+impl Medium for HomogeneousMedium {
+    fn tr(&self, ray: &Ray, _sampler: &mut dyn Sampler) -> Spectrum {
+        self.tr_distance(ray.max * ray.direction.magnitude())
+    }
+
+    fn sample(&self, ray: &Ray, sampler: &mut dyn Sampler, arena: &'a MemoryArena) -> (Spectrum, Option<MediumInteraction<'a>>) {
+        // sample a channel, and a distance along the ray with an exponential distribution for that channel
+        let n = self.sigma_t.len();
+        let channel = min((sampler.get_1d() * float(n as f32)).raw() as usize, n - 1);
+        let length = ray.direction.magnitude();
+
+        let t = if self.sigma_t[channel] > 0.0 {
+            let dist = -(float(1.0) - sampler.get_1d()).ln() / self.sigma_t[channel];
+            min(dist / length, ray.max)
+        } else {
+            ray.max
+        };
+        let sampled_medium = t < ray.max;
+
+        let isect = if sampled_medium {
+            let phase = arena.alloc(HenyeyGreenstein::new(self.g));
+            Some(MediumInteraction::new(ray.position(t), -ray.direction, ray.time, MediumInterface::new(ray.medium), phase))
+        } else {
+            None
+        };
+
+        // the pdf is the average of the pdfs for each channel
+        let tr = self.tr_distance(t * length);
+        let density = if sampled_medium { self.sigma_t * tr } else { tr };
+        let pdf = density.iter().fold(float(0.0), |sum, d| sum + *d) / float(n as f32);
+        if pdf == 0.0 {
+            return (tr, isect);
+        }
+
+        let beta = if sampled_medium { tr * self.sigma_s / pdf } else { tr / pdf };
+        (beta, isect)
+    }
+}
@@ -0,0 +1,61 @@
+use std::fmt::Debug;
+use crate::prelude::*;
+use crate::interaction::MediumInteraction;
+use crate::memory::MemoryArena;
+use crate::sampler::Sampler;
+
+mod grid;
+pub use self::grid::GridDensityMedium;
+
+mod homogeneous;
+pub use self::homogeneous::HomogeneousMedium;
+
+mod phase;
+pub use self::phase::{ phase_hg, HenyeyGreenstein, PhaseFunction };
+
+/// Refers to one of the `Scene`'s media, by its index in `Scene::media`.
+///
+/// Rays and interactions hold ids rather than the media themselves, so that they can stay `Copy`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct MediumId(pub usize);
+
+/// The media on either side of a surface, where `None` is a vacuum.
+/// The outside is the side that the surface normal points towards.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct MediumInterface {
+    pub inside: Option<MediumId>,
+    pub outside: Option<MediumId>,
+}
+
+impl MediumInterface {
+    /// The same medium on both sides, for points that aren't on a boundary.
+    pub fn new(medium: Option<MediumId>) -> Self {
+        Self {
+            inside: medium,
+            outside: medium,
+        }
+    }
+
+    pub fn between(inside: Option<MediumId>, outside: Option<MediumId>) -> Self {
+        Self { inside, outside }
+    }
+
+    pub fn is_transition(&self) -> bool {
+        self.inside != self.outside
+    }
+}
+
+/// A region of space that light is absorbed and scattered by as it passes through.
+pub trait Medium: Debug {
+    /// The fraction of light that makes it from the origin of `ray` to `ray.max`,
+    /// without being absorbed or scattered away.
+    fn tr(&self, ray: &Ray, sampler: &mut dyn Sampler) -> Spectrum;
+
+    /// Samples a point along `ray`, up to `ray.max`, where light is scattered.
+    ///
+    /// Returns the `MediumInteraction` if the point is inside the medium, or `None` if the ray
+    /// makes it all of the way to `ray.max`, along with the weight for the sample.
+    /// This is the transmittance up to the point, times the scattering coefficient if it's
+    /// inside the medium, divided by the probability of sampling it.
+    fn sample(&self, ray: &Ray, sampler: &mut dyn Sampler, arena: &'a MemoryArena) -> (Spectrum, Option<MediumInteraction<'a>>);
+}
@@ -0,0 +1,60 @@
+use std::cmp::max;
+use std::fmt::Debug;
+use cgmath::prelude::*;
+use crate::prelude::*;
+
+/// The distribution of directions that light is scattered into by a medium,
+/// which is the volumetric equivalent of a `Bsdf`.
+///
+/// Both `wo` and `wi` point away from the scattering point.
+pub trait PhaseFunction: Debug {
+    fn p(&self, wo: Vector3f, wi: Vector3f) -> Float;
+
+    /// Samples `wi`, returning the value of the phase function, which is also its pdf, and `wi`.
+    fn sample_p(&self, wo: Vector3f, u: Point2f) -> (Float, Vector3f);
+}
+
+/// The Henyey-Greenstein phase function, where `g` in `(-1, 1)` is the average cosine
+/// of the scattering angle.
+/// Positive values scatter light forwards, and negative values scatter it back.
+#[derive(Copy, Clone, Debug)]
+pub struct HenyeyGreenstein {
+    g: Float,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(g: Float) -> Self {
+        Self { g }
+    }
+}
+
+impl PhaseFunction for HenyeyGreenstein {
+    fn p(&self, wo: Vector3f, wi: Vector3f) -> Float {
+        phase_hg(wo.dot(wi), self.g)
+    }
+
+    fn sample_p(&self, wo: Vector3f, u: Point2f) -> (Float, Vector3f) {
+        let g = self.g;
+
+        // compute cos(theta) for the Henyey-Greenstein sample
+        let cos_theta = if g.abs() < 1e-3 {
+            float(1.0) - float(2.0) * u[0]
+        } else {
+            let sqr_term = (float(1.0) - g * g) / (float(1.0) + g - float(2.0) * g * u[0]);
+            -(float(1.0) + g * g - sqr_term * sqr_term) / (float(2.0) * g)
+        };
+
+        // compute the direction wi for the sample
+        let sin_theta = max(float(0.0), float(1.0) - cos_theta * cos_theta).sqrt();
+        let phi = float(2.0) * Float::pi() * u[1];
+        let (v1, v2) = coordinate_system(wo);
+        let wi = spherical_direction_from_axis(sin_theta, cos_theta, phi, v1, v2, wo);
+
+        (phase_hg(cos_theta, g), wi)
+    }
+}
+
+pub fn phase_hg(cos_theta: Float, g: Float) -> Float {
+    let denom = float(1.0) + g * g + float(2.0) * g * cos_theta;
+    Float::inv_4_pi() * (float(1.0) - g * g) / (denom * denom.sqrt())
+}
@@ -0,0 +1,170 @@
+use std::alloc::{ self, Layout };
+use std::cell::RefCell;
+use std::cmp::max;
+use std::mem;
+use std::ptr::{ self, NonNull };
+
+const DEFAULT_BLOCK_SIZE: usize = 256 * 1024;
+const BLOCK_ALIGN: usize = 16;
+
+struct Block {
+    ptr: NonNull<u8>,
+    size: usize,
+}
+
+impl Block {
+    fn new(size: usize) -> Self {
+        let layout = Layout::from_size_align(size, BLOCK_ALIGN).unwrap();
+        let ptr = unsafe { alloc::alloc(layout) };
+
+        match NonNull::new(ptr) {
+            Some(ptr) => Self { ptr, size },
+            None => alloc::handle_alloc_error(layout),
+        }
+    }
+}
+
+impl Drop for Block {
+    fn drop(&mut self) {
+        let layout = Layout::from_size_align(self.size, BLOCK_ALIGN).unwrap();
+        unsafe { alloc::dealloc(self.ptr.as_ptr(), layout) };
+    }
+}
+
+/// Destructors for values that were allocated in the arena and need dropping.
+struct Destructor {
+    ptr: *mut u8,
+    drop: unsafe fn(*mut u8),
+}
+
+unsafe fn drop_value<T>(ptr: *mut u8) {
+    ptr::drop_in_place(ptr as *mut T);
+}
+
+struct ArenaBlocks {
+    current: Option<Block>,
+    /// how much of `current` has been handed out
+    offset: usize,
+    used: Vec<Block>,
+    available: Vec<Block>,
+    destructors: Vec<Destructor>,
+}
+
+/// A bump allocator for short-lived values, like the `Bsdf`s and `Bxdf`s for a single camera sample.
+///
+/// Allocating only needs `&self`, and everything is freed at once by `reset`,
+/// which keeps the blocks around to be reused.
+/// Each rendering thread should have its own arena.
+pub struct MemoryArena {
+    block_size: usize,
+    blocks: RefCell<ArenaBlocks>,
+}
+
+impl MemoryArena {
+    pub fn new() -> Self {
+        Self::with_block_size(DEFAULT_BLOCK_SIZE)
+    }
+
+    pub fn with_block_size(block_size: usize) -> Self {
+        Self {
+            block_size,
+            blocks: RefCell::new(ArenaBlocks {
+                current: None,
+                offset: 0,
+                used: vec![],
+                available: vec![],
+                destructors: vec![],
+            }),
+        }
+    }
+
+    #[cfg_attr(feature = "cargo-clippy", allow(mut_from_ref))]
+    pub fn alloc<T>(&self, value: T) -> &mut T {
+        let ptr = self.alloc_raw(Layout::new::<T>()) as *mut T;
+
+        unsafe {
+            ptr::write(ptr, value);
+        }
+
+        if mem::needs_drop::<T>() {
+            self.blocks.borrow_mut().destructors.push(Destructor {
+                ptr: ptr as *mut u8,
+                drop: drop_value::<T>,
+            });
+        }
+
+        unsafe { &mut *ptr }
+    }
+
+    fn alloc_raw(&self, layout: Layout) -> *mut u8 {
+        // zero-sized types don't need any memory
+        if layout.size() == 0 {
+            return layout.align() as *mut u8;
+        }
+
+        assert!(layout.align() <= BLOCK_ALIGN, "MemoryArena can't allocate with alignment {}", layout.align());
+
+        let mut blocks = self.blocks.borrow_mut();
+        let offset = (blocks.offset + layout.align() - 1) & !(layout.align() - 1);
+
+        let fits = blocks.current.as_ref()
+            .map_or(false, |current| offset + layout.size() <= current.size);
+
+        let offset = if fits {
+            offset
+        } else {
+            // add current block to used list, and get a new one
+            if let Some(current) = blocks.current.take() {
+                blocks.used.push(current);
+            }
+
+            let block = match blocks.available.iter().position(|b| b.size >= layout.size()) {
+                Some(i) => blocks.available.remove(i),
+                None => Block::new(max(layout.size(), self.block_size)),
+            };
+
+            blocks.current = Some(block);
+            0
+        };
+
+        blocks.offset = offset + layout.size();
+        let current = blocks.current.as_ref().unwrap();
+        unsafe { current.ptr.as_ptr().add(offset) }
+    }
+
+    /// Frees everything that has been allocated, so the memory can be reused.
+    pub fn reset(&mut self) {
+        let blocks = self.blocks.get_mut();
+
+        for destructor in blocks.destructors.drain(..).rev() {
+            unsafe { (destructor.drop)(destructor.ptr) };
+        }
+
+        blocks.offset = 0;
+        let used = mem::replace(&mut blocks.used, vec![]);
+        blocks.available.extend(used);
+    }
+
+    /// The total number of bytes that the arena has allocated from the system.
+    pub fn total_allocated(&self) -> usize {
+        let blocks = self.blocks.borrow();
+
+        blocks.current.iter()
+            .chain(&blocks.used)
+            .chain(&blocks.available)
+            .map(|b| b.size)
+            .sum()
+    }
+}
+
+impl Default for MemoryArena {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for MemoryArena {
+    fn drop(&mut self) {
+        self.reset();
+    }
+}
@@ -0,0 +1,666 @@
+use std::collections::HashMap;
+use std::path::{ Path, PathBuf };
+use std::sync::{ Arc, Mutex };
+use cgmath::prelude::*;
+use cgmath::{ Deg, Matrix4, Vector4 };
+use crate::prelude::*;
+use crate::integrator::Integrator;
+use crate::light::Light;
+use crate::material::Material;
+use crate::math::{ AnimatedTransform, Transform };
+use crate::medium::{ Medium, MediumId, MediumInterface };
+use crate::primitive::{ GeometricPrimitive, Primitive, TransformedPrimitive };
+use crate::scene::Scene;
+use super::create::*;
+use super::params::{ FloatTextures, SpectrumTextures };
+use super::{ Location, ParamSet, ParseError, TextureParams };
+
+/// The state that is saved by `AttributeBegin` and restored by `AttributeEnd`.
+#[derive(Clone)]
+struct GraphicsState {
+    material: Option<Arc<dyn Material + Send + Sync>>,
+    named_materials: HashMap<String, Option<Arc<dyn Material + Send + Sync>>>,
+    float_textures: FloatTextures,
+    spectrum_textures: SpectrumTextures,
+    reverse_orientation: bool,
+    /// the `AreaLightSource` to attach to shapes
+    area_light: Option<(String, ParamSet)>,
+    /// the media inside and outside of shapes, and around the camera
+    medium_interface: MediumInterface,
+}
+
+impl GraphicsState {
+    fn new(location: Location) -> Result<Self, ParseError> {
+        let float_textures = HashMap::new();
+        let spectrum_textures = HashMap::new();
+
+        // the default material is a grey matte
+        let params = ParamSet::new(location, vec![]);
+        let material = make_material("matte", &TextureParams {
+            params: &params,
+            float_textures: &float_textures,
+            spectrum_textures: &spectrum_textures,
+        })?;
+
+        Ok(Self {
+            material,
+            named_materials: HashMap::new(),
+            float_textures,
+            spectrum_textures,
+            reverse_orientation: false,
+            area_light: None,
+            medium_interface: MediumInterface::default(),
+        })
+    }
+
+    fn texture_params(&'a self, params: &'a ParamSet) -> TextureParams<'a> {
+        TextureParams {
+            params,
+            float_textures: &self.float_textures,
+            spectrum_textures: &self.spectrum_textures,
+        }
+    }
+}
+
+/// A directive whose object can't be created until the whole scene description is read.
+struct Deferred {
+    name: String,
+    params: ParamSet,
+}
+
+impl Deferred {
+    fn new(name: &str, location: &Location) -> Self {
+        Self {
+            name: name.to_string(),
+            params: ParamSet::new(location.clone(), vec![]),
+        }
+    }
+}
+
+/// Receives the directives of a scene file and builds the `Scene` and `Integrator` they describe.
+pub struct SceneBuilder {
+    ctm: Transform,
+    named_coordinate_systems: HashMap<String, Transform>,
+    graphics_state: GraphicsState,
+    pushed_graphics_states: Vec<GraphicsState>,
+    pushed_transforms: Vec<Transform>,
+    in_world: bool,
+
+    camera: Deferred,
+    camera_to_world: Transform,
+    camera_medium: Option<MediumId>,
+    /// where the camera's aperture image is looked up
+    camera_base_dir: PathBuf,
+    sampler: Deferred,
+    film: Deferred,
+    filter: Deferred,
+    integrator: Deferred,
+    accelerator: Deferred,
+
+    primitives: Vec<Arc<dyn Primitive + Send>>,
+    lights: Vec<Arc<dyn Light + Send + Sync>>,
+    media: Vec<Arc<dyn Medium + Send + Sync>>,
+    named_media: HashMap<String, MediumId>,
+    instances: HashMap<String, Arc<dyn Primitive + Send + Sync>>,
+    current_instance: Option<(String, Vec<Arc<dyn Primitive + Send>>)>,
+}
+
+impl SceneBuilder {
+    pub fn new(location: &Location) -> Result<Self, ParseError> {
+        Ok(Self {
+            ctm: Transform::identity(),
+            named_coordinate_systems: HashMap::new(),
+            graphics_state: GraphicsState::new(location.clone())?,
+            pushed_graphics_states: vec![],
+            pushed_transforms: vec![],
+            in_world: false,
+
+            camera: Deferred::new("perspective", location),
+            camera_to_world: Transform::identity(),
+            camera_medium: None,
+            camera_base_dir: PathBuf::new(),
+            sampler: Deferred::new("halton", location),
+            film: Deferred::new("image", location),
+            filter: Deferred::new("box", location),
+            integrator: Deferred::new("path", location),
+            accelerator: Deferred::new("bvh", location),
+
+            primitives: vec![],
+            lights: vec![],
+            media: vec![],
+            named_media: HashMap::new(),
+            instances: HashMap::new(),
+            current_instance: None,
+        })
+    }
+
+    fn concat(&mut self, matrix: Matrix4<Float>, location: &Location) -> Result<(), ParseError> {
+        let matrix = self.ctm.matrix * matrix;
+        self.set_transform(matrix, location)
+    }
+
+    fn set_transform(&mut self, matrix: Matrix4<Float>, location: &Location) -> Result<(), ParseError> {
+        if matrix.invert().is_none() {
+            return Err(ParseError::new(location.clone(), "transform isn't invertible"));
+        }
+        self.ctm = Transform::new(matrix);
+        Ok(())
+    }
+
+    fn verify_world(&self, directive: &str, location: &Location) -> Result<(), ParseError> {
+        if self.in_world {
+            Ok(())
+        } else {
+            Err(ParseError::new(location.clone(), format!("{} is only allowed inside WorldBegin/WorldEnd", directive)))
+        }
+    }
+
+    fn verify_options(&self, directive: &str, location: &Location) -> Result<(), ParseError> {
+        if self.in_world {
+            Err(ParseError::new(location.clone(), format!("{} isn't allowed inside WorldBegin/WorldEnd", directive)))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn identity(&mut self) {
+        self.ctm = Transform::identity();
+    }
+
+    pub fn translate(&mut self, d: Vector3f, location: &Location) -> Result<(), ParseError> {
+        self.concat(Matrix4::from_translation(d), location)
+    }
+
+    pub fn scale(&mut self, s: Vector3f, location: &Location) -> Result<(), ParseError> {
+        self.concat(Matrix4::from_nonuniform_scale(s.x, s.y, s.z), location)
+    }
+
+    pub fn rotate(&mut self, angle: Float, axis: Vector3f, location: &Location) -> Result<(), ParseError> {
+        if axis.magnitude2() == 0.0 {
+            return Err(ParseError::new(location.clone(), "rotation axis can't be zero"));
+        }
+        self.concat(Matrix4::from_axis_angle(axis.normalize(), Deg(angle)), location)
+    }
+
+    pub fn look_at(&mut self, eye: Point3f, look: Point3f, up: Vector3f, location: &Location) -> Result<(), ParseError> {
+        let error = |message| ParseError::new(location.clone(), message);
+
+        let dir = look - eye;
+        if dir.magnitude2() == 0.0 || up.magnitude2() == 0.0 {
+            return Err(error("LookAt needs distinct eye and look points, and a non-zero up vector"));
+        }
+        let dir = dir.normalize();
+
+        let right = up.normalize().cross(dir);
+        if right.magnitude2() == 0.0 {
+            return Err(error("LookAt up vector is parallel to the viewing direction"));
+        }
+        let right = right.normalize();
+        let new_up = dir.cross(right);
+
+        let camera_to_world = Matrix4::from_cols(
+            right.extend(float(0.0)),
+            new_up.extend(float(0.0)),
+            dir.extend(float(0.0)),
+            Vector4::new(eye.x, eye.y, eye.z, float(1.0)),
+        );
+
+        // checked to be invertible above
+        self.concat(camera_to_world.invert().unwrap(), location)
+    }
+
+    pub fn concat_transform(&mut self, m: &[Float], location: &Location) -> Result<(), ParseError> {
+        self.concat(matrix_from_slice(m), location)
+    }
+
+    pub fn transform(&mut self, m: &[Float], location: &Location) -> Result<(), ParseError> {
+        self.set_transform(matrix_from_slice(m), location)
+    }
+
+    pub fn coordinate_system(&mut self, name: String) {
+        self.named_coordinate_systems.insert(name, self.ctm);
+    }
+
+    pub fn coord_sys_transform(&mut self, name: &str, location: &Location) -> Result<(), ParseError> {
+        match self.named_coordinate_systems.get(name) {
+            Some(ctm) => {
+                self.ctm = *ctm;
+                Ok(())
+            },
+            None => Err(ParseError::new(location.clone(), format!("unknown coordinate system \"{}\"", name))),
+        }
+    }
+
+    pub fn reverse_orientation(&mut self) {
+        self.graphics_state.reverse_orientation = !self.graphics_state.reverse_orientation;
+    }
+
+    pub fn camera(&mut self, name: &str, params: ParamSet, base_dir: &Path) -> Result<(), ParseError> {
+        self.verify_options("Camera", &params.location)?;
+
+        self.camera_to_world = self.ctm.inverse();
+        self.camera_medium = self.graphics_state.medium_interface.outside;
+        self.camera_base_dir = base_dir.to_path_buf();
+        self.named_coordinate_systems.insert(String::from("camera"), self.camera_to_world);
+        self.camera = Deferred { name: name.to_string(), params };
+        Ok(())
+    }
+
+    pub fn sampler(&mut self, name: &str, params: ParamSet) -> Result<(), ParseError> {
+        self.verify_options("Sampler", &params.location)?;
+        self.sampler = Deferred { name: name.to_string(), params };
+        Ok(())
+    }
+
+    pub fn film(&mut self, name: &str, params: ParamSet) -> Result<(), ParseError> {
+        self.verify_options("Film", &params.location)?;
+        self.film = Deferred { name: name.to_string(), params };
+        Ok(())
+    }
+
+    pub fn pixel_filter(&mut self, name: &str, params: ParamSet) -> Result<(), ParseError> {
+        self.verify_options("PixelFilter", &params.location)?;
+        self.filter = Deferred { name: name.to_string(), params };
+        Ok(())
+    }
+
+    pub fn integrator(&mut self, name: &str, params: ParamSet) -> Result<(), ParseError> {
+        self.verify_options("Integrator", &params.location)?;
+        self.integrator = Deferred { name: name.to_string(), params };
+        Ok(())
+    }
+
+    pub fn accelerator(&mut self, name: &str, params: ParamSet) -> Result<(), ParseError> {
+        self.verify_options("Accelerator", &params.location)?;
+        self.accelerator = Deferred { name: name.to_string(), params };
+        Ok(())
+    }
+
+    pub fn make_named_medium(&mut self, name: String, params: ParamSet, base_dir: &Path) -> Result<(), ParseError> {
+        let ty = params.string("type")?
+            .ok_or_else(|| params.error("MakeNamedMedium needs \"string type\""))?;
+        let medium = make_medium(&ty, &params, &self.ctm, base_dir)?;
+
+        let id = MediumId(self.media.len());
+        self.media.push(medium);
+        self.named_media.insert(name, id);
+        Ok(())
+    }
+
+    /// Sets the media for the shapes that follow, and for the camera if it comes before `WorldBegin`.
+    /// An empty name is a vacuum.
+    pub fn medium_interface(&mut self, inside: &str, outside: &str, location: &Location) -> Result<(), ParseError> {
+        let lookup = |name: &str| {
+            if name.is_empty() {
+                return Ok(None);
+            }
+            match self.named_media.get(name) {
+                Some(&id) => Ok(Some(id)),
+                None => Err(ParseError::new(location.clone(), format!("unknown named medium \"{}\"", name))),
+            }
+        };
+
+        self.graphics_state.medium_interface = MediumInterface::between(lookup(inside)?, lookup(outside)?);
+        Ok(())
+    }
+
+    pub fn world_begin(&mut self, location: &Location) -> Result<(), ParseError> {
+        self.verify_options("WorldBegin", location)?;
+        self.in_world = true;
+        self.ctm = Transform::identity();
+        self.named_coordinate_systems.insert(String::from("world"), self.ctm);
+        Ok(())
+    }
+
+    pub fn attribute_begin(&mut self, location: &Location) -> Result<(), ParseError> {
+        self.verify_world("AttributeBegin", location)?;
+        self.pushed_graphics_states.push(self.graphics_state.clone());
+        self.pushed_transforms.push(self.ctm);
+        Ok(())
+    }
+
+    pub fn attribute_end(&mut self, location: &Location) -> Result<(), ParseError> {
+        self.verify_world("AttributeEnd", location)?;
+        match (self.pushed_graphics_states.pop(), self.pushed_transforms.pop()) {
+            (Some(graphics_state), Some(ctm)) => {
+                self.graphics_state = graphics_state;
+                self.ctm = ctm;
+                Ok(())
+            },
+            _ => Err(ParseError::new(location.clone(), "unmatched AttributeEnd")),
+        }
+    }
+
+    pub fn transform_begin(&mut self, location: &Location) -> Result<(), ParseError> {
+        self.verify_world("TransformBegin", location)?;
+        self.pushed_transforms.push(self.ctm);
+        Ok(())
+    }
+
+    pub fn transform_end(&mut self, location: &Location) -> Result<(), ParseError> {
+        self.verify_world("TransformEnd", location)?;
+        match self.pushed_transforms.pop() {
+            Some(ctm) => {
+                self.ctm = ctm;
+                Ok(())
+            },
+            None => Err(ParseError::new(location.clone(), "unmatched TransformEnd")),
+        }
+    }
+
+    pub fn texture(&mut self, name: String, ty: &str, class: &str, params: ParamSet, base_dir: &Path) -> Result<(), ParseError> {
+        self.verify_world("Texture", &params.location)?;
+
+        match ty {
+            "float" => {
+                let texture = make_float_texture(class, &self.graphics_state.texture_params(&params), base_dir)?;
+                self.graphics_state.float_textures.insert(name, texture);
+            },
+            "spectrum" | "color" => {
+                let texture = make_spectrum_texture(class, &self.graphics_state.texture_params(&params), base_dir)?;
+                self.graphics_state.spectrum_textures.insert(name, texture);
+            },
+            _ => return Err(params.error(format!("unknown texture type \"{}\"", ty))),
+        }
+
+        Ok(())
+    }
+
+    pub fn material(&mut self, name: &str, params: ParamSet) -> Result<(), ParseError> {
+        self.verify_world("Material", &params.location)?;
+        let material = make_material(name, &self.graphics_state.texture_params(&params))?;
+        self.graphics_state.material = material;
+        Ok(())
+    }
+
+    pub fn make_named_material(&mut self, name: String, params: ParamSet) -> Result<(), ParseError> {
+        self.verify_world("MakeNamedMaterial", &params.location)?;
+
+        let ty = params.string("type")?
+            .ok_or_else(|| params.error("MakeNamedMaterial needs \"string type\""))?;
+        let material = make_material(&ty, &self.graphics_state.texture_params(&params))?;
+        self.graphics_state.named_materials.insert(name, material);
+        Ok(())
+    }
+
+    pub fn named_material(&mut self, name: &str, location: &Location) -> Result<(), ParseError> {
+        self.verify_world("NamedMaterial", location)?;
+
+        match self.graphics_state.named_materials.get(name) {
+            Some(material) => {
+                self.graphics_state.material = material.clone();
+                Ok(())
+            },
+            None => Err(ParseError::new(location.clone(), format!("unknown named material \"{}\"", name))),
+        }
+    }
+
+    pub fn light_source(&mut self, name: &str, params: ParamSet, base_dir: &Path) -> Result<(), ParseError> {
+        self.verify_world("LightSource", &params.location)?;
+        let light = make_light(name, &params, &self.ctm, base_dir)?;
+        self.lights.push(light);
+        Ok(())
+    }
+
+    pub fn area_light_source(&mut self, name: &str, params: ParamSet) -> Result<(), ParseError> {
+        self.verify_world("AreaLightSource", &params.location)?;
+        self.graphics_state.area_light = Some((name.to_string(), params));
+        Ok(())
+    }
+
+    pub fn shape(&mut self, name: &str, params: ParamSet, base_dir: &Path) -> Result<(), ParseError> {
+        self.verify_world("Shape", &params.location)?;
+
+        let shapes = make_shape(
+            name,
+            &params,
+            Arc::new(self.ctm),
+            self.graphics_state.reverse_orientation,
+            base_dir,
+        )?;
+        let mut primitives: Vec<Arc<dyn Primitive + Send>> = Vec::with_capacity(shapes.len());
+        for shape in shapes {
+            let area_light = match &self.graphics_state.area_light {
+                Some((name, light_params)) => {
+                    if self.current_instance.is_some() {
+                        return Err(params.error("area lights can't be used inside ObjectBegin/ObjectEnd"));
+                    }
+
+                    let light = make_area_light(name, light_params, shape.clone())?;
+                    self.lights.push(light.clone());
+                    Some(light)
+                },
+                None => None,
+            };
+
+            primitives.push(Arc::new(GeometricPrimitive {
+                shape,
+                material: self.graphics_state.material.clone(),
+                area_light,
+                medium_interface: self.graphics_state.medium_interface,
+            }));
+        }
+
+        match &mut self.current_instance {
+            Some((_, instance)) => instance.extend(primitives),
+            None => self.primitives.extend(primitives),
+        }
+        Ok(())
+    }
+
+    pub fn object_begin(&mut self, name: String, location: &Location) -> Result<(), ParseError> {
+        self.verify_world("ObjectBegin", location)?;
+        if self.current_instance.is_some() {
+            return Err(ParseError::new(location.clone(), "ObjectBegin can't be nested"));
+        }
+
+        self.attribute_begin(location)?;
+        self.current_instance = Some((name, vec![]));
+        Ok(())
+    }
+
+    pub fn object_end(&mut self, location: &Location) -> Result<(), ParseError> {
+        self.verify_world("ObjectEnd", location)?;
+
+        let (name, primitives) = self.current_instance.take()
+            .ok_or_else(|| ParseError::new(location.clone(), "ObjectEnd without ObjectBegin"))?;
+        if primitives.is_empty() {
+            return Err(ParseError::new(location.clone(), format!("object \"{}\" has no shapes", name)));
+        }
+
+        let params = ParamSet::new(location.clone(), vec![]);
+        let bvh = make_accelerator("bvh", &params, primitives)?;
+        self.instances.insert(name, Arc::new(bvh));
+
+        self.attribute_end(location)
+    }
+
+    pub fn object_instance(&mut self, name: &str, location: &Location) -> Result<(), ParseError> {
+        self.verify_world("ObjectInstance", location)?;
+        if self.current_instance.is_some() {
+            return Err(ParseError::new(location.clone(), "ObjectInstance can't be used inside ObjectBegin/ObjectEnd"));
+        }
+
+        let primitive = self.instances.get(name)
+            .ok_or_else(|| ParseError::new(location.clone(), format!("unknown object \"{}\"", name)))?
+            .clone();
+
+        let instance_to_world = Arc::new(self.ctm);
+        let instance = TransformedPrimitive {
+            primitive,
+            primitive_to_world: Arc::new(AnimatedTransform::new(
+                instance_to_world.clone(),
+                float(0.0),
+                instance_to_world,
+                float(1.0),
+            )),
+        };
+        self.primitives.push(Arc::new(instance));
+        Ok(())
+    }
+
+    /// Creates the `Scene` and `Integrator` once `WorldEnd` is reached.
+    pub fn world_end(self, location: &Location) -> Result<(Scene, Box<dyn Integrator>), ParseError> {
+        self.verify_world("WorldEnd", location)?;
+        if !self.pushed_transforms.is_empty() {
+            return Err(ParseError::new(location.clone(), "missing AttributeEnd or TransformEnd before WorldEnd"));
+        }
+        if self.current_instance.is_some() {
+            return Err(ParseError::new(location.clone(), "missing ObjectEnd before WorldEnd"));
+        }
+        if self.primitives.is_empty() {
+            return Err(ParseError::new(location.clone(), "scene has no shapes"));
+        }
+
+        let filter = make_filter(&self.filter.name, &self.filter.params)?;
+        let film = make_film(&self.film.name, &self.film.params, filter)?;
+        let sample_bounds = film.sample_bounds();
+        let film = Arc::new(Mutex::new(film));
+
+        let camera = make_camera(&self.camera.name, &self.camera.params, self.camera_to_world, film, self.camera_medium, &self.camera_base_dir)?;
+        let sampler = make_sampler(&self.sampler.name, &self.sampler.params, sample_bounds)?;
+        let integrator = make_integrator(&self.integrator.name, &self.integrator.params, camera, sampler)?;
+
+        let aggregate = make_accelerator(&self.accelerator.name, &self.accelerator.params, self.primitives)?;
+        let scene = Scene::with_media(Arc::new(aggregate), self.lights, self.media);
+
+        Ok((scene, integrator))
+    }
+}
+
+/// pbrt gives matrices in column-major order, the same as `cgmath`.
+fn matrix_from_slice(m: &[Float]) -> Matrix4<Float> {
+    Matrix4::new(
+        m[0], m[1], m[2], m[3],
+        m[4], m[5], m[6], m[7],
+        m[8], m[9], m[10], m[11],
+        m[12], m[13], m[14], m[15],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::parse_source;
+
+    fn same_material(a: &Option<Arc<dyn Material + Send + Sync>>, b: &Option<Arc<dyn Material + Send + Sync>>) -> bool {
+        match (a, b) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
+    fn assert_close(a: Float, b: FloatPrim) {
+        assert!((a.raw() - b).abs() < 1e-4, "{:?} != {}", a, b);
+    }
+
+    fn translation(x: FloatPrim, y: FloatPrim, z: FloatPrim) -> Matrix4<Float> {
+        Matrix4::from_translation(Vector3f::new(float(x), float(y), float(z)))
+    }
+
+    #[test]
+    fn attribute_end_restores_state() {
+        let builder = parse_source(r#"
+            WorldBegin
+            Translate 1 2 3
+            Material "mirror"
+            AttributeBegin
+                Translate 4 5 6
+                Scale 2 2 2
+                Material "matte"
+                TransformBegin
+                    Rotate 90 0 0 1
+                TransformEnd
+            AttributeEnd
+        "#).ok().unwrap();
+
+        assert_eq!(builder.ctm.matrix, translation(1.0, 2.0, 3.0));
+        assert!(builder.pushed_transforms.is_empty() && builder.pushed_graphics_states.is_empty());
+        assert!(format!("{:?}", builder.graphics_state.material).contains("MirrorMaterial"));
+    }
+
+    #[test]
+    fn named_material() {
+        let builder = parse_source(r#"
+            WorldBegin
+            MakeNamedMaterial "red" "string type" "matte" "rgb Kd" [1 0 0]
+            MakeNamedMaterial "chrome" "string type" "metal"
+            NamedMaterial "red"
+            AttributeBegin
+                NamedMaterial "chrome"
+            AttributeEnd
+        "#).ok().unwrap();
+
+        let named = &builder.graphics_state.named_materials;
+        assert!(same_material(&builder.graphics_state.material, &named["red"]));
+        assert!(!same_material(&builder.graphics_state.material, &named["chrome"]));
+        assert!(format!("{:?}", named["chrome"]).contains("MetalMaterial"));
+    }
+
+    #[test]
+    fn object_instance() {
+        let builder = parse_source(r#"
+            WorldBegin
+            ObjectBegin "pair"
+                Shape "sphere"
+                Translate 2 0 0
+                Shape "sphere" "float radius" 0.5
+            ObjectEnd
+            Translate 0 0 10
+            ObjectInstance "pair"
+            Translate 0 10 0
+            ObjectInstance "pair"
+        "#).ok().unwrap();
+
+        // the object's own transform is undone by ObjectEnd
+        assert_eq!(builder.ctm.matrix, translation(0.0, 10.0, 10.0));
+
+        let object = builder.instances["pair"].world_bound();
+        assert_close(object.min.x, -1.0);
+        assert_close(object.max.x, 2.5);
+
+        assert_eq!(builder.primitives.len(), 2);
+        let first = builder.primitives[0].world_bound();
+        assert_close(first.min.x, -1.0);
+        assert_close(first.max.x, 2.5);
+        assert_close(first.min.z, 9.0);
+        assert_close(first.max.z, 11.0);
+        let second = builder.primitives[1].world_bound();
+        assert_close(second.min.y, 9.0);
+        assert_close(second.max.y, 11.0);
+    }
+
+    #[test]
+    fn unknown_names() {
+        let err = parse_source("WorldBegin\nObjectInstance \"missing\"\n").err().unwrap();
+        assert_eq!(err.location.line, 2);
+        assert!(err.to_string().contains("unknown object \"missing\""), "{}", err);
+
+        let err = parse_source("WorldBegin\n\nNamedMaterial \"missing\"\n").err().unwrap();
+        assert_eq!(err.location.line, 3);
+    }
+
+    #[test]
+    fn heterogeneous_medium() {
+        let builder = parse_source(r#"
+            MakeNamedMedium "smoke" "string type" "heterogeneous"
+                "integer nx" 2 "integer ny" 1 "integer nz" 1 "float density" [0 1]
+            MakeNamedMedium "tinted" "string type" "heterogeneous"
+                "rgb sigma_a" [0.5 0.25 0] "rgb sigma_s" [0.5 0.75 1] "float density" [1]
+        "#).ok().unwrap();
+        assert!(builder.named_media.contains_key("smoke") && builder.named_media.contains_key("tinted"));
+
+        // the grid medium can't use a different extinction coefficient for each channel
+        let err = parse_source(r#"
+            MakeNamedMedium "milk" "string type" "heterogeneous"
+                "rgb sigma_a" [1 2 3] "float density" [1]
+        "#).err().unwrap();
+        assert!(err.to_string().contains("same in every channel"), "{}", err);
+
+        let err = parse_source(r#"
+            MakeNamedMedium "huge" "string type" "heterogeneous"
+                "integer nx" 2147483647 "integer ny" 2147483647 "integer nz" 2147483647 "float density" [1]
+        "#).err().unwrap();
+        assert!(err.to_string().contains("too large"), "{}", err);
+    }
+}
@@ -0,0 +1,797 @@
+use std::cmp::{ max, min };
+use std::path::Path;
+use std::sync::{ Arc, Mutex };
+use cgmath::{ Deg, Matrix4, Vector4 };
+use cgmath::prelude::*;
+use num;
+use crate::prelude::*;
+use crate::aggregate::{ BvhAccel, SplitMethod };
+use crate::camera::{ Aperture, Camera, OrthographicCamera, PerspectiveCamera };
+use crate::film::{ ExrPixelType, Film };
+use crate::filter::{ BoxFilter, Filter, GaussianFilter, LanczosSincFilter, MitchellFilter, TriangleFilter };
+use crate::integrator::{ DirectLightingIntegrator, Integrator, LightStrategy, NormalIntegrator, PathIntegrator, VolPathIntegrator, WhittedIntegrator };
+use crate::light::{ DiffuseAreaLight, DistantLight, GoniometricLight, InfiniteAreaLight, Light, PointLight, ProjectionLight, SpotLight };
+use crate::loader::{ read_image, DensityGrid, IesProfile, PlyMesh };
+use crate::material::{ BumpMap, DisneyMaterial, GlassMaterial, Material, MatteMaterial, MetalMaterial, MirrorMaterial, PlasticMaterial, SubstrateMaterial, UberMaterial };
+use crate::math::{ AnimatedTransform, Transform };
+use crate::medium::{ GridDensityMedium, HomogeneousMedium, Medium, MediumId };
+use crate::primitive::Primitive;
+use crate::sampler::{ HaltonSampler, PaddedSobolSampler, Sampler, SobolSampler, StratifiedSampler, ZeroTwoSequenceSampler };
+use crate::shape::{ create_triangle_mesh, Cylinder, Shape, ShapeData, Sphere };
+use crate::spectrum::SpectrumType;
+use crate::texture::{ ConstantTexture, ImageTexel, ImageTexture, ImageWrap, Texture, TextureMapping2d, UvMapping2d };
+use super::{ ParamSet, ParseError, ParseErrorKind, TextureParams };
+
+pub fn make_shape(
+    name: &str,
+    params: &ParamSet,
+    object_to_world: Arc<Transform>,
+    reverse_orientation: bool,
+    base_dir: &Path,
+) -> Result<Vec<Arc<dyn Shape + Send + Sync>>, ParseError> {
+    let shapes: Vec<Arc<dyn Shape + Send + Sync>> = match name {
+        "sphere" => {
+            let radius = params.float("radius", float(1.0))?;
+            let z_min = params.float("zmin", -radius)?;
+            let z_max = params.float("zmax", radius)?;
+            let phi_max = params.float("phimax", float(360.0))?;
+
+            let data = ShapeData::new(object_to_world, reverse_orientation);
+            vec![Arc::new(Sphere::new(radius, z_min, z_max, Deg(phi_max), data))]
+        },
+        "cylinder" => {
+            let radius = params.float("radius", float(1.0))?;
+            let z_min = params.float("zmin", float(-1.0))?;
+            let z_max = params.float("zmax", float(1.0))?;
+            let phi_max = params.float("phimax", float(360.0))?;
+
+            let data = ShapeData::new(object_to_world, reverse_orientation);
+            vec![Arc::new(Cylinder::new(radius, z_min, z_max, Deg(phi_max), data))]
+        },
+        "trianglemesh" => {
+            let p = params.point3s("P")?
+                .ok_or_else(|| params.error("trianglemesh needs \"point3 P\""))?;
+
+            let indices = match params.ints("indices")? {
+                Some(indices) => indices,
+                // a single triangle can leave out its indices
+                None if p.len() == 3 => vec![0, 1, 2],
+                None => return Err(params.error("trianglemesh needs \"integer indices\"")),
+            };
+            if indices.len() % 3 != 0 {
+                return Err(params.error(format!("trianglemesh has {} indices, which isn't a multiple of 3", indices.len())));
+            }
+            if let Some(i) = indices.iter().find(|&&i| i < 0 || i as usize >= p.len()) {
+                return Err(params.error(format!("trianglemesh index {} is out of range ({} vertices)", i, p.len())));
+            }
+            let indices = indices.into_iter().map(|i| i as usize).collect();
+
+            let n = params.normals("N")?;
+            let s = params.vector3s("S")?;
+            let uv = match params.point2s("uv")? {
+                Some(uv) => Some(uv),
+                None => params.point2s("st")?,
+            };
+
+            let check = |name: &str, len: Option<usize>| match len {
+                Some(len) if len != p.len() => Err(params.error(format!(
+                    "trianglemesh has {} \"{}\" values, but {} vertices", len, name, p.len()))),
+                _ => Ok(()),
+            };
+            check("N", n.as_ref().map(|n| n.len()))?;
+            check("S", s.as_ref().map(|s| s.len()))?;
+            check("uv", uv.as_ref().map(|uv| uv.len()))?;
+
+            create_triangle_mesh(object_to_world, reverse_orientation, indices, p, n, s, uv)
+        },
+        "plymesh" => {
+            let filename = params.string("filename")?
+                .ok_or_else(|| params.error("plymesh needs \"string filename\""))?;
+
+            let mesh = PlyMesh::open(base_dir.join(filename))
+                .map_err(|err| ParseError {
+                    location: params.location.clone(),
+                    kind: ParseErrorKind::Ply(err),
+                })?;
+
+            mesh.into_shapes(object_to_world, reverse_orientation)
+        },
+        _ => return Err(params.error(format!("unknown shape \"{}\"", name))),
+    };
+
+    Ok(shapes)
+}
+
+/// Returns `None` for the `"none"` material, which is used for the boundaries of media.
+#[cfg_attr(feature = "cargo-clippy", allow(unreadable_literal))]
+pub fn make_material(name: &str, tp: &TextureParams<'_>) -> Result<Option<Arc<dyn Material + Send + Sync>>, ParseError> {
+    let material: Arc<dyn Material + Send + Sync> = match name {
+        "" | "none" => return Ok(None),
+        "matte" => {
+            let kd = tp.spectrum_texture("Kd", Spectrum::new(0.5))?;
+            let sigma = tp.float_texture("sigma", float(0.0))?;
+            let bump = make_bump_map(tp)?;
+
+            Arc::new(MatteMaterial::new(kd, sigma, bump))
+        },
+        "plastic" => {
+            let kd = tp.spectrum_texture("Kd", Spectrum::new(0.25))?;
+            let ks = tp.spectrum_texture("Ks", Spectrum::new(0.25))?;
+            let roughness = tp.float_texture("roughness", float(0.1))?;
+            let bump = make_bump_map(tp)?;
+            let remap_roughness = tp.params.bool("remaproughness", true)?;
+
+            Arc::new(PlasticMaterial::new(kd, ks, roughness, bump, remap_roughness))
+        },
+        "metal" => {
+            // pbrt's default is copper
+            let eta = tp.spectrum_texture("eta", Spectrum::from_rgb([float(0.200438), float(0.924033), float(1.10221)], SpectrumType::Reflectance))?;
+            let k = tp.spectrum_texture("k", Spectrum::from_rgb([float(3.91295), float(2.45285), float(2.14219)], SpectrumType::Reflectance))?;
+            let roughness = tp.float_texture("roughness", float(0.01))?;
+            let u_roughness = tp.float_texture_opt("uroughness")?;
+            let v_roughness = tp.float_texture_opt("vroughness")?;
+            let bump = make_bump_map(tp)?;
+            let remap_roughness = tp.params.bool("remaproughness", true)?;
+
+            Arc::new(MetalMaterial::new(eta, k, roughness, u_roughness, v_roughness, bump, remap_roughness))
+        },
+        "mirror" => {
+            let kr = tp.spectrum_texture("Kr", Spectrum::new(0.9))?;
+            let bump = make_bump_map(tp)?;
+
+            Arc::new(MirrorMaterial::new(kr, bump))
+        },
+        "glass" => {
+            let kr = tp.spectrum_texture("Kr", Spectrum::new(1.0))?;
+            let kt = tp.spectrum_texture("Kt", Spectrum::new(1.0))?;
+            let u_roughness = tp.float_texture("uroughness", float(0.0))?;
+            let v_roughness = tp.float_texture("vroughness", float(0.0))?;
+            // "index" is the older name for "eta"
+            let index = match tp.float_texture_opt("eta")? {
+                Some(eta) => eta,
+                None => tp.float_texture("index", float(1.5))?,
+            };
+            let bump = make_bump_map(tp)?;
+            let remap_roughness = tp.params.bool("remaproughness", true)?;
+
+            Arc::new(GlassMaterial::new(kr, kt, u_roughness, v_roughness, index, bump, remap_roughness))
+        },
+        "substrate" => {
+            let kd = tp.spectrum_texture("Kd", Spectrum::new(0.5))?;
+            let ks = tp.spectrum_texture("Ks", Spectrum::new(0.5))?;
+            let u_roughness = tp.float_texture("uroughness", float(0.1))?;
+            let v_roughness = tp.float_texture("vroughness", float(0.1))?;
+            let bump = make_bump_map(tp)?;
+            let remap_roughness = tp.params.bool("remaproughness", true)?;
+
+            Arc::new(SubstrateMaterial::new(kd, ks, u_roughness, v_roughness, bump, remap_roughness))
+        },
+        "uber" => {
+            let kd = tp.spectrum_texture("Kd", Spectrum::new(0.25))?;
+            let ks = tp.spectrum_texture("Ks", Spectrum::new(0.25))?;
+            let kr = tp.spectrum_texture("Kr", Spectrum::new(0.0))?;
+            let kt = tp.spectrum_texture("Kt", Spectrum::new(0.0))?;
+            let roughness = tp.float_texture("roughness", float(0.1))?;
+            let u_roughness = tp.float_texture_opt("uroughness")?;
+            let v_roughness = tp.float_texture_opt("vroughness")?;
+            // "index" is the older name for "eta"
+            let eta = match tp.float_texture_opt("eta")? {
+                Some(eta) => eta,
+                None => tp.float_texture("index", float(1.5))?,
+            };
+            let opacity = tp.spectrum_texture("opacity", Spectrum::new(1.0))?;
+            let bump = make_bump_map(tp)?;
+            let remap_roughness = tp.params.bool("remaproughness", true)?;
+
+            Arc::new(UberMaterial::new(kd, ks, kr, kt, roughness, u_roughness, v_roughness, eta, opacity, bump, remap_roughness))
+        },
+        "disney" => {
+            let color = tp.spectrum_texture("color", Spectrum::new(0.5))?;
+            let metallic = tp.float_texture("metallic", float(0.0))?;
+            let roughness = tp.float_texture("roughness", float(0.5))?;
+            let specular = tp.float_texture("specular", float(0.5))?;
+            let specular_tint = tp.float_texture("speculartint", float(0.0))?;
+            let anisotropic = tp.float_texture("anisotropic", float(0.0))?;
+            let sheen = tp.float_texture("sheen", float(0.0))?;
+            let sheen_tint = tp.float_texture("sheentint", float(0.5))?;
+            let clearcoat = tp.float_texture("clearcoat", float(0.0))?;
+            let clearcoat_gloss = tp.float_texture("clearcoatgloss", float(1.0))?;
+            let spec_trans = tp.float_texture("spectrans", float(0.0))?;
+            let flatness = tp.float_texture("flatness", float(0.0))?;
+            let diff_trans = tp.float_texture("difftrans", float(1.0))?;
+            let thin = tp.params.bool("thin", false)?;
+            let bump = make_bump_map(tp)?;
+
+            Arc::new(DisneyMaterial::new(
+                color,
+                metallic,
+                roughness,
+                specular,
+                specular_tint,
+                anisotropic,
+                sheen,
+                sheen_tint,
+                clearcoat,
+                clearcoat_gloss,
+                spec_trans,
+                flatness,
+                diff_trans,
+                thin,
+                bump,
+            ))
+        },
+        _ => return Err(tp.params.error(format!("unknown material \"{}\"", name))),
+    };
+
+    Ok(Some(material))
+}
+
+/// A material's `"bumpmap"` displacement texture, or its `"normalmap"` texture,
+/// which has to be an RGB image read without gamma correction, so 8-bit images need `"bool gamma" false`.
+fn make_bump_map(tp: &TextureParams<'_>) -> Result<Option<BumpMap>, ParseError> {
+    match (tp.float_texture_opt("bumpmap")?, tp.spectrum_texture_opt("normalmap")?) {
+        (Some(_), Some(_)) => Err(tp.params.error("a material can't have both a bump map and a normal map")),
+        (Some(d), None) => Ok(Some(BumpMap::Displacement(d))),
+        (None, Some(ref normal)) if normal.is_gamma_corrected() =>
+            Err(tp.params.error("normal map textures can't be gamma corrected, use \"bool gamma\" false")),
+        (None, Some(normal)) => Ok(Some(BumpMap::Normal(normal))),
+        (None, None) => Ok(None),
+    }
+}
+
+fn make_mapping(params: &ParamSet) -> Result<Box<dyn TextureMapping2d + Send + Sync>, ParseError> {
+    match params.string_or("mapping", "uv")?.as_str() {
+        "uv" => {
+            let su = params.float("uscale", float(1.0))?;
+            let sv = params.float("vscale", float(1.0))?;
+            let du = params.float("udelta", float(0.0))?;
+            let dv = params.float("vdelta", float(0.0))?;
+            Ok(Box::new(UvMapping2d::new(su, sv, du, dv)))
+        },
+        mapping => Err(params.error(format!("unknown texture mapping \"{}\"", mapping))),
+    }
+}
+
+fn make_image_texture<T: ImageTexel>(params: &ParamSet, base_dir: &Path) -> Result<ImageTexture<T>, ParseError> {
+    let mapping = make_mapping(params)?;
+
+    let filename = params.string("filename")?
+        .ok_or_else(|| params.error("imagemap needs \"string filename\""))?;
+    let max_anisotropy = params.float("maxanisotropy", float(8.0))?;
+    let do_trilinear = params.bool("trilinear", false)?;
+    let wrap = match params.string_or("wrap", "repeat")?.as_str() {
+        "repeat" => ImageWrap::Repeat,
+        "black" => ImageWrap::Black,
+        "clamp" => ImageWrap::Clamp,
+        wrap => return Err(params.error(format!("unknown wrap mode \"{}\"", wrap))),
+    };
+    let scale = params.float("scale", float(1.0))?;
+
+    // 8-bit images are usually stored in sRGB
+    let lower = filename.to_lowercase();
+    let is_8bit = [".png", ".jpg", ".jpeg", ".tga"].iter().any(|ext| lower.ends_with(ext));
+    let gamma = params.bool("gamma", is_8bit)?;
+
+    ImageTexture::new(mapping, base_dir.join(filename), do_trilinear, max_anisotropy, wrap, scale, gamma)
+        .map_err(|err| ParseError {
+            location: params.location.clone(),
+            kind: ParseErrorKind::Image(err),
+        })
+}
+
+pub fn make_float_texture(name: &str, tp: &TextureParams<'_>, base_dir: &Path) -> Result<Arc<dyn Texture<Float> + Send + Sync>, ParseError> {
+    match name {
+        "constant" => Ok(Arc::new(ConstantTexture::new(tp.params.float("value", float(1.0))?))),
+        "imagemap" => Ok(Arc::new(make_image_texture::<Float>(tp.params, base_dir)?)),
+        _ => Err(tp.params.error(format!("unknown float texture \"{}\"", name))),
+    }
+}
+
+pub fn make_spectrum_texture(name: &str, tp: &TextureParams<'_>, base_dir: &Path) -> Result<Arc<dyn Texture<Spectrum> + Send + Sync>, ParseError> {
+    match name {
+        "constant" => {
+            let value = tp.params.spectrum("value", Spectrum::new(1.0), SpectrumType::Reflectance)?;
+            Ok(Arc::new(ConstantTexture::new(value)))
+        },
+        "imagemap" => Ok(Arc::new(make_image_texture::<Spectrum>(tp.params, base_dir)?)),
+        _ => Err(tp.params.error(format!("unknown spectrum texture \"{}\"", name))),
+    }
+}
+
+/// A light space that is at `from`, with `+z` pointing towards `to`.
+fn look_along(light_to_world: &Transform, from: Point3f, to: Point3f) -> Transform {
+    let dir = (to - from).normalize();
+    let (du, dv) = coordinate_system(dir);
+    let zero = float(0.0);
+    let dir_to_z_inverse = Matrix4::from_cols(du.extend(zero), dv.extend(zero), dir.extend(zero), Vector4::new(zero, zero, zero, float(1.0)));
+
+    let translate = Matrix4::from_translation(from.into_vector());
+    Transform::new(light_to_world.matrix * translate * dir_to_z_inverse)
+}
+
+/// Reads an image with linear values, 8-bit images are converted from sRGB like pbrt's `ReadImage`.
+fn read_image_param(params: &ParamSet, base_dir: &Path, filename: &str) -> Result<(Point2i, Vec<Spectrum>), ParseError> {
+    let path = base_dir.join(filename);
+    let (resolution, mut pixels) = read_image(&path)
+        .map_err(|err| ParseError {
+            location: params.location.clone(),
+            kind: ParseErrorKind::Image(err),
+        })?;
+
+    let is_8bit = path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .map_or(false, |ext| ext == "png" || ext == "jpg" || ext == "jpeg");
+    if is_8bit {
+        for pixel in &mut pixels {
+            for c in pixel.iter_mut() {
+                *c = inverse_gamma_correct(*c);
+            }
+        }
+    }
+
+    Ok((resolution, pixels))
+}
+
+pub fn make_light(name: &str, params: &ParamSet, light_to_world: &Transform, base_dir: &Path) -> Result<Arc<dyn Light + Send + Sync>, ParseError> {
+    let scale = params.spectrum("scale", Spectrum::new(1.0), SpectrumType::Illumination)?;
+
+    match name {
+        "point" => {
+            let i = params.spectrum("I", Spectrum::new(1.0), SpectrumType::Illumination)?;
+            let from = params.point3("from", Point3f::zero())?;
+
+            let translate = Matrix4::from_translation(from.into_vector());
+            let light_to_world = Transform::new(light_to_world.matrix * translate);
+
+            Ok(Arc::new(PointLight::new(i * scale, Arc::new(light_to_world))))
+        },
+        "spot" => {
+            let i = params.spectrum("I", Spectrum::new(1.0), SpectrumType::Illumination)?;
+            let cone_angle = params.float("coneangle", float(30.0))?;
+            let cone_delta = params.float("conedelta", float(5.0))?;
+            let from = params.point3("from", Point3f::zero())?;
+            let to = params.point3("to", Point3f::new(float(0.0), float(0.0), float(1.0)))?;
+            if from == to {
+                return Err(params.error("spot light \"from\" and \"to\" can't be the same"));
+            }
+
+            let light_to_world = look_along(light_to_world, from, to);
+            Ok(Arc::new(SpotLight::new(i * scale, Deg(cone_angle), Deg(cone_angle - cone_delta), Arc::new(light_to_world))))
+        },
+        "distant" => {
+            let l = params.spectrum("L", Spectrum::new(1.0), SpectrumType::Illumination)?;
+            let from = params.point3("from", Point3f::zero())?;
+            let to = params.point3("to", Point3f::new(float(0.0), float(0.0), float(1.0)))?;
+            if from == to {
+                return Err(params.error("distant light \"from\" and \"to\" can't be the same"));
+            }
+
+            Ok(Arc::new(DistantLight::new(l * scale, from - to, Arc::new(*light_to_world))))
+        },
+        "goniometric" => {
+            let i = params.spectrum("I", Spectrum::new(1.0), SpectrumType::Illumination)?;
+            let light_to_world = Arc::new(*light_to_world);
+
+            let light = match params.string("mapname")? {
+                Some(ref filename) if filename.to_lowercase().ends_with(".ies") => {
+                    let profile = IesProfile::open(base_dir.join(filename))
+                        .map_err(|err| ParseError {
+                            location: params.location.clone(),
+                            kind: ParseErrorKind::Ies(err),
+                        })?;
+
+                    GoniometricLight::from_ies(i * scale, &profile, light_to_world)
+                },
+                Some(filename) => {
+                    let (resolution, texels) = read_image_param(params, base_dir, &filename)?;
+                    GoniometricLight::new(i * scale, resolution, texels, light_to_world)
+                },
+                None => GoniometricLight::new(i * scale, Point2i::new(1, 1), vec![Spectrum::new(1.0)], light_to_world),
+            };
+
+            Ok(Arc::new(light))
+        },
+        "projection" => {
+            let i = params.spectrum("I", Spectrum::new(1.0), SpectrumType::Illumination)?;
+            let fov = params.float("fov", float(45.0))?;
+            let image = match params.string("mapname")? {
+                Some(filename) => Some(read_image_param(params, base_dir, &filename)?),
+                None => None,
+            };
+
+            Ok(Arc::new(ProjectionLight::new(i * scale, image, Deg(fov), Arc::new(*light_to_world))))
+        },
+        "infinite" => {
+            let l = params.spectrum("L", Spectrum::new(1.0), SpectrumType::Illumination)?;
+            let n_samples = params.int("samples", params.int("nsamples", 1)?)?;
+            if n_samples <= 0 {
+                return Err(params.error("\"samples\" must be positive"));
+            }
+            let light_to_world = Arc::new(*light_to_world);
+
+            match params.string("mapname")? {
+                Some(filename) => {
+                    let (resolution, texels) = read_image_param(params, base_dir, &filename)?;
+
+                    Ok(Arc::new(InfiniteAreaLight::new(l * scale, n_samples as u32, resolution, texels, light_to_world)))
+                },
+                None => Ok(Arc::new(InfiniteAreaLight::constant(l * scale, n_samples as u32, light_to_world))),
+            }
+        },
+        _ => Err(params.error(format!("unknown light \"{}\"", name))),
+    }
+}
+
+pub fn make_area_light(name: &str, params: &ParamSet, shape: Arc<dyn Shape + Send + Sync>) -> Result<Arc<dyn Light + Send + Sync>, ParseError> {
+    match name {
+        "diffuse" => {
+            let l = params.spectrum("L", Spectrum::new(1.0), SpectrumType::Illumination)?;
+            let scale = params.spectrum("scale", Spectrum::new(1.0), SpectrumType::Illumination)?;
+            let two_sided = params.bool("twosided", false)?;
+            let n_samples = params.int("samples", params.int("nsamples", 1)?)?;
+            if n_samples <= 0 {
+                return Err(params.error("area light samples must be positive"));
+            }
+
+            Ok(Arc::new(DiffuseAreaLight::new(l * scale, n_samples as u32, shape, two_sided)))
+        },
+        _ => Err(params.error(format!("unknown area light \"{}\"", name))),
+    }
+}
+
+pub fn make_medium(name: &str, params: &ParamSet, medium_to_world: &Transform, base_dir: &Path) -> Result<Arc<dyn Medium + Send + Sync>, ParseError> {
+    // pbrt's defaults are the coefficients of skim milk, in mm^-1, but a grid medium
+    // needs the same extinction coefficient in every channel, so its defaults are grey
+    let (default_a, default_s) = match name {
+        "heterogeneous" => (Spectrum::new(float(1.0)), Spectrum::new(float(1.0))),
+        _ => (
+            Spectrum::from_rgb([float(0.0011), float(0.0024), float(0.014)], SpectrumType::Reflectance),
+            Spectrum::from_rgb([float(2.55), float(3.21), float(3.77)], SpectrumType::Reflectance),
+        ),
+    };
+    let sigma_a = params.spectrum("sigma_a", default_a, SpectrumType::Reflectance)?;
+    let sigma_s = params.spectrum("sigma_s", default_s, SpectrumType::Reflectance)?;
+    let scale = params.float("scale", float(1.0))?;
+    let g = params.float("g", float(0.0))?;
+    if g <= -1.0 || g >= 1.0 {
+        return Err(params.error("\"g\" must be between -1 and 1"));
+    }
+
+    match name {
+        "homogeneous" => Ok(Arc::new(HomogeneousMedium::new(sigma_a * scale, sigma_s * scale, g))),
+        "heterogeneous" => {
+            if GridDensityMedium::grey_sigma_t(sigma_a, sigma_s).is_none() {
+                return Err(params.error("heterogeneous media need \"sigma_a\" + \"sigma_s\" to be the same in every channel"));
+            }
+
+            // the densities come from a file, or are given inline with their resolution
+            let (resolution, density) = match params.string("filename")? {
+                Some(filename) => {
+                    let grid = DensityGrid::open(base_dir.join(filename))
+                        .map_err(|err| ParseError {
+                            location: params.location.clone(),
+                            kind: ParseErrorKind::Density(err),
+                        })?;
+                    (grid.resolution, grid.density)
+                },
+                None => {
+                    let (nx, ny, nz) = (params.int("nx", 1)?, params.int("ny", 1)?, params.int("nz", 1)?);
+                    if nx <= 0 || ny <= 0 || nz <= 0 {
+                        return Err(params.error("\"nx\", \"ny\" and \"nz\" must be positive"));
+                    }
+                    let resolution = [nx as usize, ny as usize, nz as usize];
+                    let n = resolution[0].checked_mul(resolution[1])
+                        .and_then(|n| n.checked_mul(resolution[2]))
+                        .ok_or_else(|| params.error("\"nx\" * \"ny\" * \"nz\" is too large"))?;
+
+                    let density = params.floats("density")?
+                        .ok_or_else(|| params.error("heterogeneous medium needs \"float density\" or \"string filename\""))?;
+                    if density.len() != n {
+                        return Err(params.error(format!("\"density\" has {} values, expected nx * ny * nz", density.len())));
+                    }
+                    if density.iter().any(|&d| d < 0.0) {
+                        return Err(params.error("\"density\" can't be negative"));
+                    }
+                    (resolution, density)
+                },
+            };
+
+            // the grid covers the box from p0 to p1 in the medium's coordinate system
+            let p0 = params.point3("p0", Point3f::new(float(0.0), float(0.0), float(0.0)))?;
+            let p1 = params.point3("p1", Point3f::new(float(1.0), float(1.0), float(1.0)))?;
+            let d = p1 - p0;
+            let grid_to_medium = Matrix4::from_translation(p0.into_vector()) * Matrix4::from_nonuniform_scale(d.x, d.y, d.z);
+            if grid_to_medium.invert().is_none() {
+                return Err(params.error("\"p0\" and \"p1\" must be different in every dimension"));
+            }
+            let medium_to_world = Transform::new(medium_to_world.matrix * grid_to_medium);
+
+            Ok(Arc::new(GridDensityMedium::new(sigma_a * scale, sigma_s * scale, g, resolution, density, &medium_to_world)))
+        },
+        _ => Err(params.error(format!("unknown medium \"{}\"", name))),
+    }
+}
+
+pub fn make_filter(name: &str, params: &ParamSet) -> Result<Box<dyn Filter + Send>, ParseError> {
+    let default = match name {
+        "box" => float(0.5),
+        "gaussian" => float(1.5),
+        "triangle" | "mitchell" => float(2.0),
+        "sinc" => float(4.0),
+        _ => return Err(params.error(format!("unknown filter \"{}\"", name))),
+    };
+    let radius = Vector2f::new(
+        params.float("xwidth", default)?,
+        params.float("ywidth", default)?,
+    );
+    if radius.x <= 0.0 || radius.y <= 0.0 {
+        return Err(params.error("filter widths must be positive"));
+    }
+
+    Ok(match name {
+        "box" => Box::new(BoxFilter::new(radius)),
+        "gaussian" => Box::new(GaussianFilter::new(radius, params.float("alpha", float(2.0))?)),
+        "mitchell" => {
+            let b = params.float("B", float(1.0 / 3.0))?;
+            let c = params.float("C", float(1.0 / 3.0))?;
+            Box::new(MitchellFilter::new(radius, b, c))
+        },
+        "sinc" => Box::new(LanczosSincFilter::new(radius, params.float("tau", float(3.0))?)),
+        _ => Box::new(TriangleFilter::new(radius)),
+    })
+}
+
+pub fn make_film(name: &str, params: &ParamSet, filter: Box<dyn Filter + Send>) -> Result<Film, ParseError> {
+    if name != "image" {
+        return Err(params.error(format!("unknown film \"{}\"", name)));
+    }
+
+    let x_res = params.int("xresolution", 1280)?;
+    let y_res = params.int("yresolution", 720)?;
+    if x_res <= 0 || y_res <= 0 {
+        return Err(params.error("film resolution must be positive"));
+    }
+
+    let crop_window = match params.floats("cropwindow")? {
+        Some(c) => {
+            if c.len() != 4 {
+                return Err(params.error("\"cropwindow\" needs 4 values"));
+            }
+            Bounds2f::new(
+                Point2f::new(clamp_01(min(c[0], c[1])), clamp_01(min(c[2], c[3]))),
+                Point2f::new(clamp_01(max(c[0], c[1])), clamp_01(max(c[2], c[3]))),
+            )
+        },
+        None => Bounds2f::new(
+            Point2f::new(float(0.0), float(0.0)),
+            Point2f::new(float(1.0), float(1.0)),
+        ),
+    };
+
+    let filename = params.string_or("filename", "pbrt.exr")?;
+
+    let exr_pixel_type = match params.string_or("exrpixeltype", "float")?.as_str() {
+        "half" => ExrPixelType::Half,
+        "float" => ExrPixelType::Float,
+        other => return Err(params.error(format!("unknown \"exrpixeltype\" \"{}\", expected \"half\" or \"float\"", other))),
+    };
+    let exr_alpha = match params.string_or("exrchannels", "rgb")?.as_str() {
+        "rgb" => false,
+        "rgba" => true,
+        other => return Err(params.error(format!("unknown \"exrchannels\" \"{}\", expected \"rgb\" or \"rgba\"", other))),
+    };
+
+    let mut film = Film::new(
+        Point2i::new(x_res, y_res),
+        crop_window,
+        filter,
+        params.float("diagonal", float(35.0))?,
+        params.float("scale", float(1.0))?,
+        filename,
+    );
+    film.exr_pixel_type = exr_pixel_type;
+    film.exr_alpha = exr_alpha;
+
+    Ok(film)
+}
+
+fn clamp_01(f: Float) -> Float {
+    num::clamp(f, float(0.0), float(1.0))
+}
+
+/// `"aperture"` is `"circular"`, `"blades"` or the filename of an image mask.
+fn make_aperture(params: &ParamSet, base_dir: &Path) -> Result<Aperture, ParseError> {
+    match params.string_or("aperture", "circular")?.as_str() {
+        "circular" => Ok(Aperture::Circular),
+        "blades" => {
+            let blades = params.int("blades", 6)?;
+            if blades < 3 {
+                return Err(params.error("an aperture needs at least 3 blades"));
+            }
+            let rotation = params.float("bladerotation", float(0.0))?;
+
+            Ok(Aperture::blades(blades as u32, Deg(rotation)))
+        },
+        filename => {
+            let (resolution, pixels) = read_image_param(params, base_dir, filename)?;
+
+            let luminance = pixels.iter().map(|p| p.y()).collect::<Vec<_>>();
+            if luminance.iter().all(|y| *y <= 0.0) {
+                return Err(params.error(format!("aperture image \"{}\" is black", filename)));
+            }
+
+            Ok(Aperture::image(&luminance, resolution.x as usize, resolution.y as usize))
+        },
+    }
+}
+
+pub fn make_camera(
+    name: &str,
+    params: &ParamSet,
+    camera_to_world: Transform,
+    film: Arc<Mutex<Film>>,
+    medium: Option<MediumId>,
+    base_dir: &Path,
+) -> Result<Arc<dyn Camera + Send + Sync>, ParseError> {
+    let shutter_open = params.float("shutteropen", float(0.0))?;
+    let shutter_close = params.float("shutterclose", float(1.0))?;
+    let lens_radius = params.float("lensradius", float(0.0))?;
+    let focal_distance = params.float("focaldistance", float(1e6))?;
+    let aperture = make_aperture(params, base_dir)?;
+
+    let resolution = film.lock().unwrap().full_resolution;
+    let aspect = params.float("frameaspectratio", float(resolution.x) / float(resolution.y))?;
+
+    // pbrt's default screen window, with the shorter axis spanning [-1, 1]
+    let (sx, sy) = if aspect > 1.0 {
+        (aspect, float(1.0))
+    } else {
+        (float(1.0), float(1.0) / aspect)
+    };
+    let screen_window = match params.floats("screenwindow")? {
+        Some(s) => {
+            if s.len() != 4 {
+                return Err(params.error("\"screenwindow\" needs 4 values"));
+            }
+            Bounds2f::new(Point2f::new(s[0], s[2]), Point2f::new(s[1], s[3]))
+        },
+        None => Bounds2f::new(Point2f::new(-sx, -sy), Point2f::new(sx, sy)),
+    };
+
+    let animated = |transform: Transform| {
+        let transform = Arc::new(transform);
+        AnimatedTransform::new(transform.clone(), shutter_open, transform, shutter_close)
+    };
+
+    let camera: Arc<dyn Camera + Send + Sync> = match name {
+        "perspective" => {
+            let fov = params.float("fov", float(90.0))?;
+
+            // pbrt's cameras look down +z, `PerspectiveCamera` looks down -z
+            let flip = Matrix4::from_nonuniform_scale(float(1.0), float(1.0), float(-1.0));
+            let camera_to_world = Transform::new(camera_to_world.matrix * flip);
+
+            Arc::new(PerspectiveCamera::new(
+                animated(camera_to_world),
+                screen_window,
+                shutter_open,
+                shutter_close,
+                lens_radius,
+                focal_distance,
+                aperture,
+                Deg(fov),
+                film,
+                medium,
+            ))
+        },
+        "orthographic" => {
+            Arc::new(OrthographicCamera::new(
+                animated(camera_to_world),
+                screen_window,
+                shutter_open,
+                shutter_close,
+                lens_radius,
+                focal_distance,
+                aperture,
+                film,
+                medium,
+            ))
+        },
+        _ => return Err(params.error(format!("unknown camera \"{}\"", name))),
+    };
+
+    Ok(camera)
+}
+
+pub fn make_sampler(name: &str, params: &ParamSet, sample_bounds: Bounds2i) -> Result<Box<dyn Sampler + Send>, ParseError> {
+    let pixel_samples = || -> Result<u64, ParseError> {
+        let samples = params.int("pixelsamples", 16)?;
+        if samples <= 0 {
+            return Err(params.error("sample counts must be positive"));
+        }
+        Ok(samples as u64)
+    };
+    let dimensions = || -> Result<u32, ParseError> {
+        let dimensions = params.int("dimensions", 4)?;
+        if dimensions <= 0 {
+            return Err(params.error("sample counts must be positive"));
+        }
+        Ok(dimensions as u32)
+    };
+
+    match name {
+        "halton" => {
+            let sample_at_center = params.bool("samplepixelcenter", false)?;
+            Ok(Box::new(HaltonSampler::new(pixel_samples()?, sample_bounds, sample_at_center)))
+        },
+        "sobol" => Ok(Box::new(SobolSampler::new(pixel_samples()?, sample_bounds))),
+        "02sequence" | "lowdiscrepancy" => Ok(Box::new(ZeroTwoSequenceSampler::new(pixel_samples()?, dimensions()?, 0))),
+        "paddedsobol" => Ok(Box::new(PaddedSobolSampler::new(pixel_samples()?, dimensions()?, 0))),
+        "stratified" => {
+            let x_samples = params.int("xsamples", 4)?;
+            let y_samples = params.int("ysamples", 4)?;
+            let jitter = params.bool("jitter", true)?;
+            let dimensions = params.int("dimensions", 4)?;
+            if x_samples <= 0 || y_samples <= 0 || dimensions <= 0 {
+                return Err(params.error("sample counts must be positive"));
+            }
+
+            Ok(Box::new(StratifiedSampler::new(x_samples as u32, y_samples as u32, jitter, dimensions as u32, 0)))
+        },
+        _ => Err(params.error(format!("unknown sampler \"{}\"", name))),
+    }
+}
+
+pub fn make_integrator(
+    name: &str,
+    params: &ParamSet,
+    camera: Arc<dyn Camera + Send + Sync>,
+    sampler: Box<dyn Sampler + Send>,
+) -> Result<Box<dyn Integrator>, ParseError> {
+    let max_depth = params.int("maxdepth", 5)?;
+
+    let strategy = |default: &str| -> Result<LightStrategy, ParseError> {
+        match params.string_or("strategy", default)?.as_str() {
+            "all" => Ok(LightStrategy::UniformSampleAll),
+            "one" => Ok(LightStrategy::UniformSampleOne),
+            s => Err(params.error(format!("unknown light strategy \"{}\"", s))),
+        }
+    };
+
+    let integrator: Box<dyn Integrator> = match name {
+        "whitted" => Box::new(WhittedIntegrator::new(max_depth, camera, sampler)),
+        "directlighting" => Box::new(DirectLightingIntegrator::new(max_depth, strategy("all")?, camera, sampler)),
+        "path" => {
+            let rr_threshold = params.float("rrthreshold", float(1.0))?;
+            Box::new(PathIntegrator::new(max_depth, rr_threshold, strategy("one")?, camera, sampler))
+        },
+        "volpath" => {
+            let rr_threshold = params.float("rrthreshold", float(1.0))?;
+            Box::new(VolPathIntegrator::new(max_depth, rr_threshold, strategy("one")?, camera, sampler))
+        },
+        "normal" => Box::new(NormalIntegrator::new(camera, sampler)),
+        _ => return Err(params.error(format!("unknown integrator \"{}\"", name))),
+    };
+
+    Ok(integrator)
+}
+
+pub fn make_accelerator(
+    name: &str,
+    params: &ParamSet,
+    primitives: Vec<Arc<dyn Primitive + Send>>,
+) -> Result<BvhAccel, ParseError> {
+    if name != "bvh" {
+        return Err(params.error(format!("unknown accelerator \"{}\"", name)));
+    }
+
+    let split_method = match params.string_or("splitmethod", "sah")?.as_str() {
+        "sah" => SplitMethod::SAH,
+        "hlbvh" => SplitMethod::HLBVH,
+        "middle" => SplitMethod::Middle,
+        "equal" => SplitMethod::EqualCounts,
+        s => return Err(params.error(format!("unknown bvh split method \"{}\"", s))),
+    };
+
+    Ok(BvhAccel::new(primitives, split_method))
+}
@@ -0,0 +1,70 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use crate::loader::{ DensityError, IesError, ImageError, PlyError };
+
+/// Where in a scene file something happened.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Location {
+    pub file: PathBuf,
+    pub line: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file.display(), self.line)
+    }
+}
+
+#[derive(Debug)]
+pub enum ParseErrorKind {
+    Io(io::Error),
+    Ply(PlyError),
+    Image(ImageError),
+    Ies(IesError),
+    Density(DensityError),
+    /// a syntax error, an unknown directive, or a bad parameter
+    Message(String),
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub location: Location,
+    pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+    pub fn new(location: Location, message: impl Into<String>) -> Self {
+        Self {
+            location,
+            kind: ParseErrorKind::Message(message.into()),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ParseErrorKind::Io(err) => write!(f, "{}: io error: {}", self.location, err),
+            ParseErrorKind::Ply(err) => write!(f, "{}: error reading ply: {}", self.location, err),
+            ParseErrorKind::Image(err) => write!(f, "{}: error reading image: {}", self.location, err),
+            ParseErrorKind::Ies(err) => write!(f, "{}: error reading ies: {}", self.location, err),
+            ParseErrorKind::Density(err) => write!(f, "{}: error reading density grid: {}", self.location, err),
+            ParseErrorKind::Message(message) => write!(f, "{}: {}", self.location, message),
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn cause(&self) -> Option<&dyn Error> {
+        match &self.kind {
+            ParseErrorKind::Io(err) => Some(err),
+            ParseErrorKind::Ply(err) => Some(err),
+            ParseErrorKind::Image(err) => Some(err),
+            ParseErrorKind::Ies(err) => Some(err),
+            ParseErrorKind::Density(err) => Some(err),
+            ParseErrorKind::Message(_) => None,
+        }
+    }
+}
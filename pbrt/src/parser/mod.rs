@@ -0,0 +1,463 @@
+use std::fs;
+use std::path::{ Path, PathBuf };
+use crate::prelude::*;
+use crate::integrator::Integrator;
+use crate::scene::Scene;
+
+mod builder;
+pub use self::builder::SceneBuilder;
+
+mod create;
+
+mod error;
+pub use self::error::{ Location, ParseError, ParseErrorKind };
+
+mod params;
+pub use self::params::{ Param, ParamSet, ParamValue, TextureParams };
+
+mod tokenizer;
+pub use self::tokenizer::{ tokenize, Spanned, Token };
+
+/// How deeply `Include`s can be nested, which stops runaway recursion.
+const MAX_INCLUDE_DEPTH: usize = 32;
+
+/// Parses a pbrt-v3 scene file, and any files it includes,
+/// returning the described `Scene` and the `Integrator` to render it with.
+pub fn parse_file(path: impl AsRef<Path>) -> Result<(Scene, Box<dyn Integrator>), ParseError> {
+    let path = path.as_ref();
+    let location = Location { file: path.to_path_buf(), line: 0 };
+    let mut builder = SceneBuilder::new(&location)?;
+
+    let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let mut parser = Parser::new(path, base_dir, vec![], location)?;
+    match parser.parse(&mut builder)? {
+        Some(end) => builder.world_end(&end),
+        None => Err(ParseError::new(parser.location(), "missing WorldEnd")),
+    }
+}
+
+struct Parser {
+    file: PathBuf,
+    /// the directory of the main scene file, which every relative path is resolved against
+    base_dir: PathBuf,
+    /// the canonical paths of the files that are being parsed, from the main file down to this one
+    include_stack: Vec<PathBuf>,
+    tokens: Vec<Spanned>,
+    position: usize,
+}
+
+impl Parser {
+    /// `include_stack` holds the files that included this one,
+    /// and `location` is where it was included from, for reporting io errors.
+    fn new(file: &Path, base_dir: PathBuf, include_stack: Vec<PathBuf>, location: Location) -> Result<Self, ParseError> {
+        let src = fs::read_to_string(file)
+            .map_err(|err| ParseError { location, kind: ParseErrorKind::Io(err) })?;
+
+        Self::from_source(file, &src, base_dir, include_stack)
+    }
+
+    fn from_source(file: &Path, src: &str, base_dir: PathBuf, mut include_stack: Vec<PathBuf>) -> Result<Self, ParseError> {
+        let tokens = tokenize(src)
+            .map_err(|(line, message)| ParseError::new(Location { file: file.to_path_buf(), line }, message))?;
+
+        include_stack.push(canonical(file));
+        Ok(Self {
+            file: file.to_path_buf(),
+            base_dir,
+            include_stack,
+            tokens,
+            position: 0,
+        })
+    }
+
+    /// The location of the most recently read token.
+    fn location(&self) -> Location {
+        let line = match self.position {
+            0 => self.tokens.first().map(|t| t.line).unwrap_or(1),
+            p => self.tokens[p - 1].line,
+        };
+
+        Location { file: self.file.clone(), line }
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.location(), message)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|t| &t.token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).map(|t| t.token.clone());
+        if token.is_some() {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn string(&mut self, what: &str) -> Result<String, ParseError> {
+        match self.next() {
+            Some(Token::String(s)) => Ok(s),
+            _ => Err(self.error(format!("expected {} as a quoted string", what))),
+        }
+    }
+
+    fn number(&mut self) -> Result<Float, ParseError> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(n),
+            _ => Err(self.error("expected a number")),
+        }
+    }
+
+    /// Reads `n` numbers, which may optionally be surrounded by brackets.
+    fn numbers(&mut self, n: usize) -> Result<Vec<Float>, ParseError> {
+        let bracketed = self.peek() == Some(&Token::OpenBracket);
+        if bracketed {
+            self.next();
+        }
+
+        let numbers = (0..n)
+            .map(|_| self.number())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if bracketed && self.next() != Some(Token::CloseBracket) {
+            return Err(self.error(format!("expected ']' after {} numbers", n)));
+        }
+
+        Ok(numbers)
+    }
+
+    fn vector(&mut self) -> Result<Vector3f, ParseError> {
+        let v = self.numbers(3)?;
+        Ok(Vector3f::new(v[0], v[1], v[2]))
+    }
+
+    /// Reads the list of `"type name" value` pairs that follow a directive.
+    fn params(&mut self) -> Result<ParamSet, ParseError> {
+        let location = self.location();
+        let mut params = vec![];
+
+        while let Some(Token::String(_)) = self.peek() {
+            let declaration = self.string("parameter declaration")?;
+            let (ty, name) = {
+                let mut parts = declaration.split_whitespace();
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some(ty), Some(name), None) => (ty.to_string(), name.to_string()),
+                    _ => return Err(self.error(format!("invalid parameter declaration \"{}\"", declaration))),
+                }
+            };
+
+            let mut values = vec![];
+            if self.peek() == Some(&Token::OpenBracket) {
+                self.next();
+                loop {
+                    match self.next() {
+                        Some(Token::CloseBracket) => break,
+                        Some(token) => values.push(token),
+                        None => return Err(self.error(format!("unterminated values for \"{}\"", name))),
+                    }
+                }
+            } else {
+                match self.next() {
+                    Some(token) => values.push(token),
+                    None => return Err(self.error(format!("missing value for \"{}\"", name))),
+                }
+            }
+
+            let value = self.param_value(&ty, &name, values)?;
+            params.push(Param { ty, name, value });
+        }
+
+        Ok(ParamSet::new(location, params))
+    }
+
+    fn param_value(&self, ty: &str, name: &str, values: Vec<Token>) -> Result<ParamValue, ParseError> {
+        let mismatch = || self.error(format!("parameter \"{}\" has values that don't match its type \"{}\"", name, ty));
+
+        let value = match ty {
+            "string" | "texture" => ParamValue::Strings(values.into_iter()
+                .map(|v| match v {
+                    Token::String(s) => Ok(s),
+                    _ => Err(mismatch()),
+                })
+                .collect::<Result<_, _>>()?),
+            "bool" => ParamValue::Bools(values.into_iter()
+                .map(|v| match v {
+                    Token::String(ref s) | Token::Identifier(ref s) if s == "true" => Ok(true),
+                    Token::String(ref s) | Token::Identifier(ref s) if s == "false" => Ok(false),
+                    _ => Err(mismatch()),
+                })
+                .collect::<Result<_, _>>()?),
+            "float" | "integer" | "point" | "point2" | "point3" | "vector" | "vector2" | "vector3" |
+            "normal" | "normal3" | "rgb" | "color" | "spectrum" | "blackbody" => ParamValue::Numbers(values.into_iter()
+                .map(|v| match v {
+                    Token::Number(n) => Ok(n),
+                    _ => Err(mismatch()),
+                })
+                .collect::<Result<_, _>>()?),
+            _ => return Err(self.error(format!("unknown parameter type \"{}\"", ty))),
+        };
+
+        Ok(value)
+    }
+
+    /// Feeds every directive in the file to `builder`, following `Include`s.
+    ///
+    /// Returns the location of `WorldEnd` if it was reached.
+    fn parse(&mut self, builder: &mut SceneBuilder) -> Result<Option<Location>, ParseError> {
+        while let Some(token) = self.next() {
+            let directive = match token {
+                Token::Identifier(directive) => directive,
+                _ => return Err(self.error("expected a directive")),
+            };
+            let location = self.location();
+
+            match directive.as_str() {
+                "Identity" => builder.identity(),
+                "Translate" => {
+                    let d = self.vector()?;
+                    builder.translate(d, &location)?;
+                },
+                "Scale" => {
+                    let s = self.vector()?;
+                    builder.scale(s, &location)?;
+                },
+                "Rotate" => {
+                    let angle = self.number()?;
+                    let axis = self.vector()?;
+                    builder.rotate(angle, axis, &location)?;
+                },
+                "LookAt" => {
+                    let v = self.numbers(9)?;
+                    builder.look_at(
+                        Point3f::new(v[0], v[1], v[2]),
+                        Point3f::new(v[3], v[4], v[5]),
+                        Vector3f::new(v[6], v[7], v[8]),
+                        &location,
+                    )?;
+                },
+                "ConcatTransform" => {
+                    let m = self.numbers(16)?;
+                    builder.concat_transform(&m, &location)?;
+                },
+                "Transform" => {
+                    let m = self.numbers(16)?;
+                    builder.transform(&m, &location)?;
+                },
+                "CoordinateSystem" => {
+                    let name = self.string("coordinate system name")?;
+                    builder.coordinate_system(name);
+                },
+                "CoordSysTransform" => {
+                    let name = self.string("coordinate system name")?;
+                    builder.coord_sys_transform(&name, &location)?;
+                },
+                "ReverseOrientation" => builder.reverse_orientation(),
+                "Camera" | "Sampler" | "Film" | "PixelFilter" | "Integrator" | "Accelerator" |
+                "Shape" | "Material" | "LightSource" | "AreaLightSource" => {
+                    let name = self.string(&format!("{} type", directive))?;
+                    let params = self.params()?;
+
+                    match directive.as_str() {
+                        "Camera" => builder.camera(&name, params, &self.base_dir)?,
+                        "Sampler" => builder.sampler(&name, params)?,
+                        "Film" => builder.film(&name, params)?,
+                        "PixelFilter" => builder.pixel_filter(&name, params)?,
+                        "Integrator" => builder.integrator(&name, params)?,
+                        "Accelerator" => builder.accelerator(&name, params)?,
+                        "Shape" => builder.shape(&name, params, &self.base_dir)?,
+                        "Material" => builder.material(&name, params)?,
+                        "LightSource" => builder.light_source(&name, params, &self.base_dir)?,
+                        _ => builder.area_light_source(&name, params)?,
+                    }
+                },
+                "MakeNamedMaterial" => {
+                    let name = self.string("material name")?;
+                    let params = self.params()?;
+                    builder.make_named_material(name, params)?;
+                },
+                "MakeNamedMedium" => {
+                    let name = self.string("medium name")?;
+                    let params = self.params()?;
+                    builder.make_named_medium(name, params, &self.base_dir)?;
+                },
+                "MediumInterface" => {
+                    // the outside medium is optional, and the same as the inside if it's missing
+                    let inside = self.string("medium name")?;
+                    let outside = match self.peek() {
+                        Some(Token::String(_)) => self.string("medium name")?,
+                        _ => inside.clone(),
+                    };
+                    builder.medium_interface(&inside, &outside, &location)?;
+                },
+                "NamedMaterial" => {
+                    let name = self.string("material name")?;
+                    builder.named_material(&name, &location)?;
+                },
+                "Texture" => {
+                    let name = self.string("texture name")?;
+                    let ty = self.string("texture type")?;
+                    let class = self.string("texture class")?;
+                    let params = self.params()?;
+                    builder.texture(name, &ty, &class, params, &self.base_dir)?;
+                },
+                "WorldBegin" => builder.world_begin(&location)?,
+                "WorldEnd" => return Ok(Some(location)),
+                "AttributeBegin" => builder.attribute_begin(&location)?,
+                "AttributeEnd" => builder.attribute_end(&location)?,
+                "TransformBegin" => builder.transform_begin(&location)?,
+                "TransformEnd" => builder.transform_end(&location)?,
+                "ObjectBegin" => {
+                    let name = self.string("object name")?;
+                    builder.object_begin(name, &location)?;
+                },
+                "ObjectEnd" => builder.object_end(&location)?,
+                "ObjectInstance" => {
+                    let name = self.string("object name")?;
+                    builder.object_instance(&name, &location)?;
+                },
+                "Include" => {
+                    let file = self.base_dir.join(self.string("file name")?);
+                    if self.include_stack.len() >= MAX_INCLUDE_DEPTH {
+                        return Err(ParseError::new(location, format!("includes are nested more than {} deep", MAX_INCLUDE_DEPTH)));
+                    }
+                    if self.include_stack.contains(&canonical(&file)) {
+                        return Err(ParseError::new(location, format!("include cycle, \"{}\" is already being parsed", file.display())));
+                    }
+
+                    let mut included = Parser::new(&file, self.base_dir.clone(), self.include_stack.clone(), location)?;
+                    if let Some(end) = included.parse(builder)? {
+                        return Ok(Some(end));
+                    }
+                },
+                _ => return Err(ParseError::new(location, format!("unknown directive \"{}\"", directive))),
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// The path with symbolic links and `..`s resolved, so that include cycles can be found.
+fn canonical(file: &Path) -> PathBuf {
+    fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf())
+}
+
+/// Parses `src` as if it were the file `test.pbrt`, and returns the builder with the parsed state.
+#[cfg(test)]
+fn parse_source(src: &str) -> Result<SceneBuilder, ParseError> {
+    let file = Path::new("test.pbrt");
+    let mut builder = SceneBuilder::new(&Location { file: file.to_path_buf(), line: 0 })?;
+    let mut parser = Parser::from_source(file, src, PathBuf::new(), vec![])?;
+    parser.parse(&mut builder)?;
+    Ok(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::env;
+    use std::sync::Arc;
+    use crate::spectrum::SpectrumType;
+    use crate::texture::{ ConstantTexture, Texture };
+    use super::*;
+    use super::params::{ FloatTextures, SpectrumTextures };
+
+    fn params(src: &str) -> ParamSet {
+        let mut parser = Parser::from_source(Path::new("test.pbrt"), src, PathBuf::new(), vec![]).unwrap();
+        parser.params().unwrap()
+    }
+
+    fn parse_error(src: &str) -> ParseError {
+        match parse_source(src) {
+            Ok(_) => panic!("expected an error parsing {:?}", src),
+            Err(err) => err,
+        }
+    }
+
+    fn assert_location(err: &ParseError, line: usize) {
+        assert_eq!(err.location, Location { file: PathBuf::from("test.pbrt"), line });
+        assert!(err.to_string().starts_with(&format!("test.pbrt:{}: ", line)), "{}", err);
+    }
+
+    #[test]
+    fn inline_material_params() {
+        let float_textures = FloatTextures::new();
+        let spectrum_textures = SpectrumTextures::new();
+
+        let matte = params(r#""rgb Kd" [.5 .5 .25] "float sigma" 20"#);
+        let tp = TextureParams { params: &matte, float_textures: &float_textures, spectrum_textures: &spectrum_textures };
+        assert!(create::make_material("matte", &tp).unwrap().is_some());
+        let kd = Spectrum::from_rgb([float(0.5), float(0.5), float(0.25)], SpectrumType::Reflectance);
+        assert_eq!(
+            format!("{:?}", tp.spectrum_texture_opt("Kd").unwrap().unwrap()),
+            format!("{:?}", ConstantTexture::<Spectrum>::new(kd)),
+        );
+        assert_eq!(
+            format!("{:?}", tp.float_texture_opt("sigma").unwrap().unwrap()),
+            format!("{:?}", ConstantTexture::<Float>::new(20.0)),
+        );
+
+        let glass = params(r#""float eta" 1.25 "float uroughness" 0.1"#);
+        let tp = TextureParams { params: &glass, float_textures: &float_textures, spectrum_textures: &spectrum_textures };
+        assert!(create::make_material("glass", &tp).unwrap().is_some());
+        assert_eq!(
+            format!("{:?}", tp.float_texture_opt("eta").unwrap().unwrap()),
+            format!("{:?}", ConstantTexture::<Float>::new(1.25)),
+        );
+    }
+
+    #[test]
+    fn texture_material_params() {
+        let mut spectrum_textures: SpectrumTextures = HashMap::new();
+        let checks: Arc<dyn Texture<Spectrum> + Send + Sync> = Arc::new(ConstantTexture::<Spectrum>::new(Spectrum::new(0.5)));
+        spectrum_textures.insert("checks".to_string(), checks.clone());
+        let float_textures = FloatTextures::new();
+
+        let matte = params(r#""texture Kd" "checks""#);
+        let tp = TextureParams { params: &matte, float_textures: &float_textures, spectrum_textures: &spectrum_textures };
+        assert!(Arc::ptr_eq(&tp.spectrum_texture_opt("Kd").unwrap().unwrap(), &checks));
+
+        let missing = params(r#""texture Kd" "missing""#);
+        let tp = TextureParams { params: &missing, float_textures: &float_textures, spectrum_textures: &spectrum_textures };
+        assert!(create::make_material("matte", &tp).is_err());
+    }
+
+    #[test]
+    fn unknown_directive() {
+        let err = parse_error("WorldBegin\n\nShape \"sphere\"\nFrobnicate 1 2 3\n");
+        assert_location(&err, 4);
+        assert!(err.to_string().contains("unknown directive \"Frobnicate\""), "{}", err);
+    }
+
+    #[test]
+    fn bad_params() {
+        // the values don't match the parameter's type
+        let err = parse_error("WorldBegin\nMaterial \"matte\"\n    \"float sigma\" \"rough\"\n");
+        assert_location(&err, 3);
+
+        // the right type, but the wrong number of values, is reported where the parameters start
+        let err = parse_error("WorldBegin\nAttributeBegin\nMaterial \"matte\" \"rgb Kd\" [1 0]\nAttributeEnd\n");
+        assert_location(&err, 3);
+        assert!(err.to_string().contains("\"Kd\""), "{}", err);
+
+        let err = parse_error("WorldBegin\nAttributeEnd\n");
+        assert_location(&err, 2);
+    }
+
+    #[test]
+    fn include_cycle() {
+        let dir = env::temp_dir().join("pbrt-parser-include-cycle");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.pbrt"), "WorldBegin\nInclude \"b.pbrt\"\n").unwrap();
+        fs::write(dir.join("b.pbrt"), "\nInclude \"a.pbrt\"\n").unwrap();
+
+        let err = match parse_file(dir.join("a.pbrt")) {
+            Ok(_) => panic!("expected an include cycle error"),
+            Err(err) => err,
+        };
+        assert_eq!(err.location, Location { file: dir.join("b.pbrt"), line: 2 });
+        assert!(err.to_string().contains("include cycle"), "{}", err);
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::prelude::*;
+use crate::spectrum::SpectrumType;
+use crate::texture::{ ConstantTexture, Texture };
+use super::{ Location, ParseError };
+
+#[derive(Clone, Debug)]
+pub enum ParamValue {
+    Numbers(Vec<Float>),
+    Strings(Vec<String>),
+    Bools(Vec<bool>),
+}
+
+/// A single `"type name" value` parameter.
+#[derive(Clone, Debug)]
+pub struct Param {
+    pub ty: String,
+    pub name: String,
+    pub value: ParamValue,
+}
+
+/// The parameters given to a directive, along with where it was written.
+#[derive(Clone, Debug)]
+pub struct ParamSet {
+    pub location: Location,
+    params: Vec<Param>,
+}
+
+impl ParamSet {
+    pub fn new(location: Location, params: Vec<Param>) -> Self {
+        Self { location, params }
+    }
+
+    pub fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.location.clone(), message)
+    }
+
+    fn find(&self, name: &str, types: &[&str]) -> Result<Option<&Param>, ParseError> {
+        let param = match self.params.iter().rev().find(|p| p.name == name) {
+            Some(param) => param,
+            None => return Ok(None),
+        };
+
+        if types.contains(&param.ty.as_str()) {
+            Ok(Some(param))
+        } else {
+            Err(self.error(format!("parameter \"{}\" should be a {}, not a {}", name, types[0], param.ty)))
+        }
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.params.iter().any(|p| p.name == name)
+    }
+
+    fn numbers(&self, name: &str, types: &[&str], stride: usize) -> Result<Option<&[Float]>, ParseError> {
+        let param = match self.find(name, types)? {
+            Some(param) => param,
+            None => return Ok(None),
+        };
+
+        match &param.value {
+            ParamValue::Numbers(values) if values.len() % stride == 0 && !values.is_empty() => Ok(Some(values)),
+            ParamValue::Numbers(values) => Err(self.error(format!(
+                "parameter \"{}\" needs a multiple of {} values, found {}", name, stride, values.len()))),
+            _ => Err(self.error(format!("parameter \"{}\" should have numeric values", name))),
+        }
+    }
+
+    fn single<T>(&self, name: &str, values: Option<Vec<T>>) -> Result<Option<T>, ParseError> {
+        match values {
+            Some(mut values) => {
+                if values.len() != 1 {
+                    return Err(self.error(format!("parameter \"{}\" needs a single value, found {}", name, values.len())));
+                }
+                Ok(values.pop())
+            },
+            None => Ok(None),
+        }
+    }
+
+    pub fn floats(&self, name: &str) -> Result<Option<Vec<Float>>, ParseError> {
+        Ok(self.numbers(name, &["float"], 1)?.map(|v| v.to_vec()))
+    }
+
+    pub fn float(&self, name: &str, default: Float) -> Result<Float, ParseError> {
+        Ok(self.single(name, self.floats(name)?)?.unwrap_or(default))
+    }
+
+    pub fn ints(&self, name: &str) -> Result<Option<Vec<i32>>, ParseError> {
+        match self.numbers(name, &["integer"], 1)? {
+            Some(values) => values.iter()
+                .map(|v| {
+                    if v.fract() == 0.0 {
+                        Ok(v.raw() as i32)
+                    } else {
+                        Err(self.error(format!("parameter \"{}\" should have integer values, found {}", name, v)))
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn int(&self, name: &str, default: i32) -> Result<i32, ParseError> {
+        Ok(self.single(name, self.ints(name)?)?.unwrap_or(default))
+    }
+
+    pub fn bool(&self, name: &str, default: bool) -> Result<bool, ParseError> {
+        let values = match self.find(name, &["bool"])? {
+            Some(param) => match &param.value {
+                ParamValue::Bools(values) => Some(values.clone()),
+                _ => return Err(self.error(format!("parameter \"{}\" should be \"true\" or \"false\"", name))),
+            },
+            None => None,
+        };
+
+        Ok(self.single(name, values)?.unwrap_or(default))
+    }
+
+    pub fn strings(&self, name: &str) -> Result<Option<Vec<String>>, ParseError> {
+        match self.find(name, &["string"])? {
+            Some(param) => match &param.value {
+                ParamValue::Strings(values) => Ok(Some(values.clone())),
+                _ => Err(self.error(format!("parameter \"{}\" should have string values", name))),
+            },
+            None => Ok(None),
+        }
+    }
+
+    pub fn string(&self, name: &str) -> Result<Option<String>, ParseError> {
+        self.single(name, self.strings(name)?)
+    }
+
+    pub fn string_or(&self, name: &str, default: &str) -> Result<String, ParseError> {
+        Ok(self.string(name)?.unwrap_or_else(|| default.to_string()))
+    }
+
+    pub fn point2s(&self, name: &str) -> Result<Option<Vec<Point2f>>, ParseError> {
+        // pbrt-v3 files often give uvs as plain floats
+        Ok(self.numbers(name, &["point2", "float"], 2)?
+            .map(|v| v.chunks(2).map(|p| Point2f::new(p[0], p[1])).collect()))
+    }
+
+    pub fn point3s(&self, name: &str) -> Result<Option<Vec<Point3f>>, ParseError> {
+        Ok(self.numbers(name, &["point3", "point"], 3)?
+            .map(|v| v.chunks(3).map(|p| Point3f::new(p[0], p[1], p[2])).collect()))
+    }
+
+    pub fn point3(&self, name: &str, default: Point3f) -> Result<Point3f, ParseError> {
+        Ok(self.single(name, self.point3s(name)?)?.unwrap_or(default))
+    }
+
+    pub fn vector3s(&self, name: &str) -> Result<Option<Vec<Vector3f>>, ParseError> {
+        Ok(self.numbers(name, &["vector3", "vector"], 3)?
+            .map(|v| v.chunks(3).map(|p| Vector3f::new(p[0], p[1], p[2])).collect()))
+    }
+
+    pub fn vector3(&self, name: &str, default: Vector3f) -> Result<Vector3f, ParseError> {
+        Ok(self.single(name, self.vector3s(name)?)?.unwrap_or(default))
+    }
+
+    pub fn normals(&self, name: &str) -> Result<Option<Vec<Normal>>, ParseError> {
+        Ok(self.numbers(name, &["normal3", "normal"], 3)?
+            .map(|v| v.chunks(3).map(|p| Normal::new(p[0], p[1], p[2])).collect()))
+    }
+
+    pub fn spectrum(&self, name: &str, default: Spectrum, ty: SpectrumType) -> Result<Spectrum, ParseError> {
+        if let Some(param) = self.params.iter().rev().find(|p| p.name == name) {
+            if param.ty == "spectrum" || param.ty == "blackbody" {
+                return Err(self.error(format!("parameter \"{}\": {} values aren't supported, use rgb", name, param.ty)));
+            }
+        }
+
+        let values = self.numbers(name, &["rgb", "color"], 3)?
+            .map(|v| v.chunks(3).map(|c| Spectrum::from_rgb([c[0], c[1], c[2]], ty)).collect());
+
+        Ok(self.single(name, values)?.unwrap_or(default))
+    }
+
+    /// The name of the texture given to a `"texture name"` parameter.
+    ///
+    /// Returns `None` if the parameter has another type, so that callers can read its value instead.
+    pub fn texture(&self, name: &str) -> Result<Option<String>, ParseError> {
+        match self.params.iter().rev().find(|p| p.name == name) {
+            Some(param) if param.ty == "texture" => match &param.value {
+                ParamValue::Strings(values) if values.len() == 1 => Ok(Some(values[0].clone())),
+                _ => Err(self.error(format!("parameter \"{}\" needs a single texture name", name))),
+            },
+            _ => Ok(None),
+        }
+    }
+}
+
+pub type FloatTextures = HashMap<String, Arc<dyn Texture<Float> + Send + Sync>>;
+pub type SpectrumTextures = HashMap<String, Arc<dyn Texture<Spectrum> + Send + Sync>>;
+
+/// Parameters for materials and textures,
+/// which can either be given directly or refer to a named texture.
+pub struct TextureParams<'a> {
+    pub params: &'a ParamSet,
+    pub float_textures: &'a FloatTextures,
+    pub spectrum_textures: &'a SpectrumTextures,
+}
+
+impl<'a> TextureParams<'a> {
+    pub fn float_texture(&self, name: &str, default: Float) -> Result<Arc<dyn Texture<Float> + Send + Sync>, ParseError> {
+        Ok(match self.float_texture_opt(name)? {
+            Some(texture) => texture,
+            None => Arc::new(ConstantTexture::new(default)),
+        })
+    }
+
+    pub fn float_texture_opt(&self, name: &str) -> Result<Option<Arc<dyn Texture<Float> + Send + Sync>>, ParseError> {
+        if let Some(texture) = self.params.texture(name)? {
+            return match self.float_textures.get(&texture) {
+                Some(texture) => Ok(Some(texture.clone())),
+                None => Err(self.params.error(format!("unknown float texture \"{}\"", texture))),
+            };
+        }
+
+        if self.params.has(name) {
+            let value = self.params.float(name, float(0.0))?;
+            Ok(Some(Arc::new(ConstantTexture::new(value))))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn spectrum_texture(&self, name: &str, default: Spectrum) -> Result<Arc<dyn Texture<Spectrum> + Send + Sync>, ParseError> {
+        Ok(match self.spectrum_texture_opt(name)? {
+            Some(texture) => texture,
+            None => Arc::new(ConstantTexture::new(default)),
+        })
+    }
+
+    pub fn spectrum_texture_opt(&self, name: &str) -> Result<Option<Arc<dyn Texture<Spectrum> + Send + Sync>>, ParseError> {
+        if let Some(texture) = self.params.texture(name)? {
+            return match self.spectrum_textures.get(&texture) {
+                Some(texture) => Ok(Some(texture.clone())),
+                None => Err(self.params.error(format!("unknown spectrum texture \"{}\"", texture))),
+            };
+        }
+
+        if self.params.has(name) {
+            let value = self.params.spectrum(name, Spectrum::new(0.0), SpectrumType::Reflectance)?;
+            Ok(Some(Arc::new(ConstantTexture::new(value))))
+        } else {
+            Ok(None)
+        }
+    }
+}
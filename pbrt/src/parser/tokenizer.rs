@@ -0,0 +1,95 @@
+use crate::prelude::*;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token {
+    /// a bare word, such as a directive name or `true`/`false`
+    Identifier(String),
+    /// a quoted string, without the quotes
+    String(String),
+    Number(Float),
+    OpenBracket,
+    CloseBracket,
+}
+
+/// A token and the line it started on.
+#[derive(Clone, Debug)]
+pub struct Spanned {
+    pub token: Token,
+    pub line: usize,
+}
+
+/// Splits the contents of a scene file into tokens.
+///
+/// On failure, returns the line of the error along with a message.
+pub fn tokenize(src: &str) -> Result<Vec<Spanned>, (usize, String)> {
+    let mut tokens = Vec::new();
+    let mut chars = src.chars().peekable();
+    let mut line = 1;
+
+    while let Some(c) = chars.next() {
+        let start = line;
+        let token = match c {
+            '\n' => {
+                line += 1;
+                continue;
+            },
+            c if c.is_whitespace() => continue,
+            '#' => {
+                // skip comments until the end of the line
+                while let Some(&c) = chars.peek() {
+                    if c == '\n' {
+                        break;
+                    }
+                    chars.next();
+                }
+                continue;
+            },
+            '[' => Token::OpenBracket,
+            ']' => Token::CloseBracket,
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\n') | None => return Err((start, String::from("unterminated string"))),
+                        Some('\\') => {
+                            let escaped = match chars.next() {
+                                Some('n') => '\n',
+                                Some('t') => '\t',
+                                Some(c) => c,
+                                None => return Err((start, String::from("unterminated string"))),
+                            };
+                            s.push(escaped);
+                        },
+                        Some(c) => s.push(c),
+                    }
+                }
+                Token::String(s)
+            },
+            c => {
+                let mut s = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '"' || c == '[' || c == ']' || c == '#' {
+                        break;
+                    }
+                    s.push(c);
+                    chars.next();
+                }
+
+                if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' {
+                    let number = s.parse::<FloatPrim>()
+                        .ok()
+                        .filter(|n| n.is_finite())
+                        .ok_or_else(|| (start, format!("invalid number '{}'", s)))?;
+                    Token::Number(float(number))
+                } else {
+                    Token::Identifier(s)
+                }
+            },
+        };
+
+        tokens.push(Spanned { token, line: start });
+    }
+
+    Ok(tokens)
+}
@@ -4,15 +4,18 @@ use crate::bxdf::TransportMode;
 use super::Primitive;
 use crate::light::Light;
 use crate::material::Material;
+use crate::medium::MediumInterface;
+use crate::memory::MemoryArena;
 use crate::shape::Shape;
 use crate::interaction::SurfaceInteraction;
 
 #[derive(Debug)]
 pub struct GeometricPrimitive {
     pub shape: Arc<dyn Shape + Send + Sync>,
-    pub material: Option<Box<dyn Material + Send + Sync>>,
+    pub material: Option<Arc<dyn Material + Send + Sync>>,
     pub area_light: Option<Arc<dyn Light + Send + Sync>>,
-    pub medium_interface: Option<()>,
+    /// the media on either side of the shape, only used when it's a transition between media
+    pub medium_interface: MediumInterface,
 }
 
 impl Primitive for GeometricPrimitive {
@@ -22,7 +25,12 @@ impl Primitive for GeometricPrimitive {
             isect.primitive = Some(self);
             isect.shape = Some(&*self.shape);
 
-            // Initialize mediumInterface
+            // a shape that isn't a boundary between media is inside the medium of the ray
+            isect.medium_interface = if self.medium_interface.is_transition() {
+                self.medium_interface
+            } else {
+                MediumInterface::new(ray.medium)
+            };
 
             Some(isect)
         } else {
@@ -47,11 +55,11 @@ impl Primitive for GeometricPrimitive {
             .map(|m| m.as_ref())
     }
 
-    fn compute_scattering_functions(&'a self, isect: SurfaceInteraction<'a>, arena: &(), mode: TransportMode, allow_multiple_lobes: bool) -> SurfaceInteraction<'a> {
-        if let Some(material) = &self.material {
-            material.compute_scattering_functions(isect, arena, mode, allow_multiple_lobes)
-        } else {
-            panic!("GeometricPrimitive doesn't have material")
+    fn compute_scattering_functions(&'a self, isect: SurfaceInteraction<'a>, arena: &'a MemoryArena, mode: TransportMode, allow_multiple_lobes: bool) -> SurfaceInteraction<'a> {
+        match &self.material {
+            Some(material) => material.compute_scattering_functions(isect, arena, mode, allow_multiple_lobes),
+            // a shape without a material only marks a boundary between media, and has no `Bsdf`
+            None => isect,
         }
     }
 }
@@ -4,6 +4,7 @@ use crate::bxdf::TransportMode;
 use crate::light::Light;
 use crate::material::Material;
 use crate::math::*;
+use crate::memory::MemoryArena;
 use crate::interaction::SurfaceInteraction;
 
 mod geometric;
@@ -23,5 +24,5 @@ pub trait Primitive: Debug + Send + Sync {
 
     fn get_material(&self) -> Option<&(dyn Material + Send + Sync)>;
 
-    fn compute_scattering_functions(&'a self, isect: SurfaceInteraction<'a>, arena: &(), mode: TransportMode, allow_multiple_lobes: bool) -> SurfaceInteraction<'a>;
+    fn compute_scattering_functions(&'a self, isect: SurfaceInteraction<'a>, arena: &'a MemoryArena, mode: TransportMode, allow_multiple_lobes: bool) -> SurfaceInteraction<'a>;
 }
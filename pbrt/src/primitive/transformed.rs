@@ -6,6 +6,7 @@ use crate::interaction::SurfaceInteraction;
 use crate::light::Light;
 use crate::material::Material;
 use crate::math::AnimatedTransform;
+use crate::memory::MemoryArena;
 
 #[derive(Clone, Debug)]
 pub struct TransformedPrimitive {
@@ -20,7 +21,6 @@ impl Primitive for TransformedPrimitive {
 
         if let Some(mut isect) = self.primitive.intersect(&mut i_ray) {
             ray.max = i_ray.max;
-            isect.primitive = Some(&*self.primitive);
 
             if !interpolated.is_identity() {
                 isect = interpolated.transform_surface_interaction(&isect);
@@ -52,7 +52,7 @@ impl Primitive for TransformedPrimitive {
         panic!("TransformedPrimitive::get_material should never be called")
     }
 
-    fn compute_scattering_functions(&'a self, _: SurfaceInteraction<'a>, _: &(), _: TransportMode, _: bool) -> SurfaceInteraction<'a> {
+    fn compute_scattering_functions(&'a self, _: SurfaceInteraction<'a>, _: &'a MemoryArena, _: TransportMode, _: bool) -> SurfaceInteraction<'a> {
         panic!("TransformedPrimitive::compute_scattering_functions should never be called");
 
     }
@@ -246,13 +246,26 @@ pub struct GlobalSamplerData {
 }
 
 impl GlobalSamplerData {
+    pub fn new(samples_per_pixel: u64) -> Self {
+        Self {
+            base: BaseSamplerData::new(samples_per_pixel),
+            dimension: 0,
+            interval_sample_index: 0,
+            array_end_dimension: 0,
+        }
+    }
+
+    pub fn samples_per_pixel(&self) -> u64 {
+        self.base.samples_per_pixel
+    }
+
     pub fn start_pixel(&mut self, sampler: &mut impl GlobalSampler, pixel: Point2i) {
         self.base.start_pixel(pixel);
         self.dimension = 0;
-        self.interval_sample_index = sampler.get_index_for_sample(0);
+        self.interval_sample_index = sampler.get_index_for_sample(pixel, 0);
 
         // compute array end dim for dimensions used for array samples
-        self.array_end_dimension = ARRAY_START_DIMENSION + self.base.samples_array_1d.len() as u32 + self.base.samples_array_2d.len() as u32;
+        self.array_end_dimension = ARRAY_START_DIMENSION + self.base.samples_array_1d.len() as u32 + 2 * self.base.samples_array_2d.len() as u32;
 
         // compute 1d array samples for globalsampler
         for (i, size) in self.base.samples_array_1d_sizes.iter().enumerate() {
@@ -260,8 +273,8 @@ impl GlobalSamplerData {
             let n = n as usize;
 
             for j in 0..n {
-                let index = sampler.get_index_for_sample(j as u64);
-                self.base.samples_array_1d[i][j] = sampler.sample_dimension(index, ARRAY_START_DIMENSION + 1);
+                let index = sampler.get_index_for_sample(pixel, j as u64);
+                self.base.samples_array_1d[i][j] = sampler.sample_dimension(pixel, index, ARRAY_START_DIMENSION + i as u32);
             }
         }
 
@@ -272,10 +285,10 @@ impl GlobalSamplerData {
             let n = n as usize;
 
             for j in 0..n {
-                let index = sampler.get_index_for_sample(j as u64);
+                let index = sampler.get_index_for_sample(pixel, j as u64);
                 self.base.samples_array_2d[i][j] = Point2f::new(
-                    sampler.sample_dimension(index, dim as u32),
-                    sampler.sample_dimension(index, dim as u32 + 1),
+                    sampler.sample_dimension(pixel, index, dim as u32),
+                    sampler.sample_dimension(pixel, index, dim as u32 + 1),
                 );
             }
             dim += 2;
@@ -284,13 +297,13 @@ impl GlobalSamplerData {
 
     pub fn start_next_sample(&mut self, sampler: &mut impl GlobalSampler) -> bool {
         self.dimension = 0;
-        self.interval_sample_index = sampler.get_index_for_sample(self.base.current_pixel_sample_index + 1);
+        self.interval_sample_index = sampler.get_index_for_sample(self.base.current_pixel, self.base.current_pixel_sample_index + 1);
         self.base.start_next_sample()
     }
 
     pub fn set_sample_number(&mut self, sampler: &mut impl GlobalSampler, num: u64) -> bool {
         self.dimension = 0;
-        self.interval_sample_index = sampler.get_index_for_sample(num);
+        self.interval_sample_index = sampler.get_index_for_sample(self.base.current_pixel, num);
         self.base.set_sample_number(num)
     }
 
@@ -298,7 +311,7 @@ impl GlobalSamplerData {
         if self.dimension >= ARRAY_START_DIMENSION && self.dimension < self.array_end_dimension {
             self.dimension = self.array_end_dimension;
         }
-        let sample = sampler.sample_dimension(self.interval_sample_index, self.dimension);
+        let sample = sampler.sample_dimension(self.base.current_pixel, self.interval_sample_index, self.dimension);
         self.dimension += 1;
         sample
     }
@@ -307,16 +320,41 @@ impl GlobalSamplerData {
         if self.dimension + 1 >= ARRAY_START_DIMENSION && self.dimension < self.array_end_dimension {
             self.dimension = self.array_end_dimension;
         }
-        let x = sampler.sample_dimension(self.interval_sample_index, self.dimension);
-        let y = sampler.sample_dimension(self.interval_sample_index, self.dimension + 1);
+        let pixel = self.base.current_pixel;
+        let x = sampler.sample_dimension(pixel, self.interval_sample_index, self.dimension);
+        let y = sampler.sample_dimension(pixel, self.interval_sample_index, self.dimension + 1);
 
         self.dimension += 2;
 
         Point2f::new(x, y)
     }
+
+    pub fn get_1d_vec(&mut self, n: u32) -> Option<Vec<Float>> {
+        self.base.get_1d_array(n)
+    }
+
+    pub fn get_2d_vec(&mut self, n: u32) -> Option<Vec<Point2f>> {
+        self.base.get_2d_array(n)
+    }
+
+    pub fn request_1d_vec(&mut self, n: u32) {
+        self.base.samples_array_1d_sizes.push(n);
+        self.base.samples_array_1d.push(vec![float(0.0); n as usize * self.base.samples_per_pixel as usize]);
+    }
+
+    pub fn request_2d_vec(&mut self, n: u32) {
+        self.base.samples_array_2d_sizes.push(n);
+        self.base.samples_array_2d.push(vec![Point2f::zero(); n as usize * self.base.samples_per_pixel as usize]);
+    }
 }
 
+/// A sampler whose samples come from one sequence that covers the whole image,
+/// rather than a separate set of samples for each pixel.
 pub trait GlobalSampler {
-    fn get_index_for_sample(&self, num: u64) -> u64;
-    fn sample_dimension(&self, index: u64, dimension: u32) -> Float;
+    /// The index into the global sequence of the `num`th sample in `pixel`.
+    fn get_index_for_sample(&mut self, pixel: Point2i, num: u64) -> u64;
+
+    /// The value of `dimension` for the sample at the global `index`.
+    /// The first two dimensions are the offset within `pixel`.
+    fn sample_dimension(&self, pixel: Point2i, index: u64, dimension: u32) -> Float;
 }
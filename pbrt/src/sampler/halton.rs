@@ -0,0 +1,191 @@
+use std::cmp::min;
+use lazy_static::lazy_static;
+use xoshiro::Xoroshiro128StarStar;
+use crate::prelude::*;
+use crate::sampling::low_discrepancy::{
+    compute_radical_inverse_permutations,
+    inverse_radical_inverse,
+    radical_inverse,
+    scrambled_radical_inverse,
+    PRIME_SUMS,
+    PRIME_TABLE_SIZE,
+};
+use super::*;
+
+/// The largest area that the first two dimensions are scaled up to cover,
+/// larger images reuse the same pattern of samples.
+const MAX_RESOLUTION: i32 = 128;
+
+lazy_static! {
+    /// These are shared by every sampler, so that each tile sees the same sequence.
+    static ref RADICAL_INVERSE_PERMUTATIONS: Vec<u16> = {
+        let mut rng = Xoroshiro128StarStar::from_seed_u64(0);
+        compute_radical_inverse_permutations(&mut rng)
+    };
+}
+
+/// Generates samples from the Halton sequence, with the digits of each dimension
+/// after the first two scrambled by a random permutation.
+pub struct HaltonSampler {
+    global: GlobalSamplerData,
+    sequence: HaltonSequence,
+    sample_bounds: Bounds2i,
+}
+
+impl HaltonSampler {
+    pub fn new(samples_per_pixel: u64, sample_bounds: Bounds2i, sample_at_pixel_center: bool) -> Self {
+        Self {
+            global: GlobalSamplerData::new(samples_per_pixel),
+            sequence: HaltonSequence::new(sample_bounds, sample_at_pixel_center),
+            sample_bounds,
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn create_new(&self, _seed: i32) -> Box<dyn Sampler + Send + 'static> {
+        let sampler = Self::new(
+            self.global.samples_per_pixel(),
+            self.sample_bounds,
+            self.sequence.sample_at_pixel_center,
+        );
+        Box::new(sampler)
+    }
+
+    fn samples_per_pixel(&self) -> u64 {
+        self.global.samples_per_pixel()
+    }
+
+    fn start_pixel(&mut self, pixel: Point2i) {
+        self.global.start_pixel(&mut self.sequence, pixel)
+    }
+
+    fn set_sample_number(&mut self, n: u64) -> bool {
+        self.global.set_sample_number(&mut self.sequence, n)
+    }
+
+    fn start_next_sample(&mut self) -> bool {
+        self.global.start_next_sample(&mut self.sequence)
+    }
+
+    fn get_1d(&mut self) -> Float {
+        self.global.get_1d(&mut self.sequence)
+    }
+
+    fn get_2d(&mut self) -> Point2f {
+        self.global.get_2d(&mut self.sequence)
+    }
+
+    fn request_1d_vec(&mut self, n: u32) {
+        self.global.request_1d_vec(n)
+    }
+
+    fn request_2d_vec(&mut self, n: u32) {
+        self.global.request_2d_vec(n)
+    }
+
+    fn get_1d_vec(&mut self, n: u32) -> Option<Vec<Float>> {
+        self.global.get_1d_vec(n)
+    }
+
+    fn get_2d_vec(&mut self, n: u32) -> Option<Vec<Point2f>> {
+        self.global.get_2d_vec(n)
+    }
+}
+
+struct HaltonSequence {
+    /// `2^j` and `3^k`, the smallest powers that cover the sample bounds
+    base_scales: [u64; 2],
+    base_exponents: [u32; 2],
+    /// the number of samples between two samples in the same pixel
+    sample_stride: u64,
+    mult_inverse: [u64; 2],
+    sample_at_pixel_center: bool,
+    // the offset for the last pixel is cached, since it's needed for every sample in the pixel
+    pixel_for_offset: Option<Point2i>,
+    offset_for_current_pixel: u64,
+}
+
+impl HaltonSequence {
+    fn new(sample_bounds: Bounds2i, sample_at_pixel_center: bool) -> Self {
+        let res = sample_bounds.diagonal();
+
+        let mut base_scales = [1; 2];
+        let mut base_exponents = [0; 2];
+        for (i, &base) in [2, 3].iter().enumerate() {
+            let res = min(res[i], MAX_RESOLUTION) as u64;
+            while base_scales[i] < res {
+                base_scales[i] *= base;
+                base_exponents[i] += 1;
+            }
+        }
+
+        Self {
+            base_scales,
+            base_exponents,
+            sample_stride: base_scales[0] * base_scales[1],
+            mult_inverse: [
+                multiplicative_inverse(base_scales[1] as i64, base_scales[0] as i64),
+                multiplicative_inverse(base_scales[0] as i64, base_scales[1] as i64),
+            ],
+            sample_at_pixel_center,
+            pixel_for_offset: None,
+            offset_for_current_pixel: 0,
+        }
+    }
+}
+
+impl GlobalSampler for HaltonSequence {
+    fn get_index_for_sample(&mut self, pixel: Point2i, num: u64) -> u64 {
+        if self.pixel_for_offset != Some(pixel) {
+            // find the first sample whose first two dimensions, scaled by `base_scales`,
+            // land in the pixel, by the chinese remainder theorem
+            self.offset_for_current_pixel = 0;
+            if self.sample_stride > 1 {
+                let pm = [modulo(pixel.x, MAX_RESOLUTION), modulo(pixel.y, MAX_RESOLUTION)];
+                for (i, &base) in [2, 3].iter().enumerate() {
+                    let dim_offset = inverse_radical_inverse(base, pm[i] as u64, self.base_exponents[i]);
+                    self.offset_for_current_pixel +=
+                        dim_offset * (self.sample_stride / self.base_scales[i]) * self.mult_inverse[i];
+                }
+                self.offset_for_current_pixel %= self.sample_stride;
+            }
+            self.pixel_for_offset = Some(pixel);
+        }
+
+        self.offset_for_current_pixel + num * self.sample_stride
+    }
+
+    fn sample_dimension(&self, _pixel: Point2i, index: u64, dimension: u32) -> Float {
+        if self.sample_at_pixel_center && dimension < 2 {
+            return float(0.5);
+        }
+
+        match dimension {
+            0 => radical_inverse(0, index >> self.base_exponents[0]),
+            1 => radical_inverse(1, index / self.base_scales[1]),
+            _ => {
+                // dimensions past the table of primes wrap around, rather than panicking
+                let dimension = dimension as usize % PRIME_TABLE_SIZE;
+                let perm = &RADICAL_INVERSE_PERMUTATIONS[PRIME_SUMS[dimension]..];
+                scrambled_radical_inverse(dimension, index, perm)
+            },
+        }
+    }
+}
+
+/// The `x` that satisfies `a * x = 1 (mod n)`.
+fn multiplicative_inverse(a: i64, n: i64) -> u64 {
+    let (x, _) = extended_gcd(a, n);
+    (((x % n) + n) % n) as u64
+}
+
+fn extended_gcd(a: i64, b: i64) -> (i64, i64) {
+    if b == 0 {
+        return (1, 0);
+    }
+
+    let d = a / b;
+    let (xp, yp) = extended_gcd(b, a % b);
+    (yp, xp - d * yp)
+}
@@ -15,9 +15,23 @@ pub const ONE_MINUS_EPSILON: FloatPrim = hexf64!("0x1.fffffffffffffp-1");
 mod base;
 pub use self::base::*;
 
+mod halton;
+pub use self::halton::HaltonSampler;
+
+mod padded;
+
+mod padded_sobol;
+pub use self::padded_sobol::PaddedSobolSampler;
+
+mod sobol;
+pub use self::sobol::SobolSampler;
+
 mod stratified;
 pub use self::stratified::StratifiedSampler;
 
+mod zero_two_sequence;
+pub use self::zero_two_sequence::ZeroTwoSequenceSampler;
+
 pub trait Sampler {
     fn create_new(&self, seed: i32) -> Box<dyn Sampler + Send + 'static>;
 
@@ -0,0 +1,72 @@
+use rand::Rng;
+use crate::prelude::*;
+use crate::sampling::low_discrepancy::{ sample_from_bits, scramble_index, sobol_sample_bits };
+use super::PixelSamplerData;
+
+/// Fills in the samples for a pixel, taking each dimension (or pair of dimensions)
+/// from the first two dimensions of the Sobol sequence, which are a (0, 2)-sequence.
+///
+/// Each dimension visits the sequence in its own random order, to decorrelate the dimensions,
+/// but the order only mixes samples within aligned blocks of powers of two,
+/// so every power of two prefix of the samples is still stratified.
+/// `scramble` randomizes the sample values, given a random seed.
+pub(super) fn generate_padded_samples(data: &mut PixelSamplerData, scramble: fn(u32, u32) -> u32) {
+    let samples_per_pixel = data.samples_per_pixel();
+    // enough bits to permute the indices of all of the samples
+    let index_bits = 64 - (samples_per_pixel - 1).leading_zeros();
+
+    let value_1d = |index: u64, seed: u32| sample_from_bits(scramble(sobol_sample_bits(index, 0, 0), seed));
+    let value_2d = |index: u64, seeds: (u32, u32)| Point2f::new(
+        sample_from_bits(scramble(sobol_sample_bits(index, 0, 0), seeds.0)),
+        sample_from_bits(scramble(sobol_sample_bits(index, 1, 0), seeds.1)),
+    );
+
+    for d in 0..data.samples_1d().len() {
+        let (index_seed, seed) = data.get_rng().gen::<(u64, u32)>();
+        for i in 0..samples_per_pixel {
+            let index = scramble_index(i, index_bits, index_seed);
+            data.samples_1d_mut()[d][i as usize] = value_1d(index, seed);
+        }
+    }
+
+    for d in 0..data.samples_2d().len() {
+        let (index_seed, seeds) = data.get_rng().gen::<(u64, (u32, u32))>();
+        for i in 0..samples_per_pixel {
+            let index = scramble_index(i, index_bits, index_seed);
+            data.samples_2d_mut()[d][i as usize] = value_2d(index, seeds);
+        }
+    }
+
+    // each pixel sample's array is an aligned block of the sequence,
+    // which is stratified when the array size is a power of two
+    for a in 0..data.samples_array_1d_sizes().len() {
+        let n = u64::from(data.samples_array_1d_sizes()[a]);
+        let (index_seed, seed) = data.get_rng().gen::<(u64, u32)>();
+        for i in 0..samples_per_pixel {
+            let block = scramble_index(i, index_bits, index_seed) * n;
+            let start = (i * n) as usize;
+            let mut values = (0..n).map(|j| value_1d(block + j, seed)).collect::<Vec<_>>();
+            shuffle(&mut values, data.get_rng());
+            data.samples_array_1d_mut()[a][start..start + n as usize].copy_from_slice(&values);
+        }
+    }
+
+    for a in 0..data.samples_array_2d_sizes().len() {
+        let n = u64::from(data.samples_array_2d_sizes()[a]);
+        let (index_seed, seeds) = data.get_rng().gen::<(u64, (u32, u32))>();
+        for i in 0..samples_per_pixel {
+            let block = scramble_index(i, index_bits, index_seed) * n;
+            let start = (i * n) as usize;
+            let mut values = (0..n).map(|j| value_2d(block + j, seeds)).collect::<Vec<_>>();
+            shuffle(&mut values, data.get_rng());
+            data.samples_array_2d_mut()[a][start..start + n as usize].copy_from_slice(&values);
+        }
+    }
+}
+
+fn shuffle<T>(slice: &mut [T], rng: &mut impl Rng) {
+    for i in 0..slice.len() {
+        let other = rng.gen_range(i, slice.len());
+        slice.swap(i, other);
+    }
+}
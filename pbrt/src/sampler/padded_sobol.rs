@@ -0,0 +1,79 @@
+use crate::prelude::*;
+use crate::sampling::low_discrepancy::owen_scramble;
+use super::*;
+use super::padded::generate_padded_samples;
+
+/// Generates samples from the first two dimensions of the Sobol sequence, with each dimension
+/// (or pair of dimensions) randomly Owen scrambled for each pixel.
+///
+/// Owen scrambling is slower than random digit scrambling, but removes more of the structure
+/// of the sequence, which converges faster.
+pub struct PaddedSobolSampler {
+    pixel: PixelSamplerData,
+    dimensions: u32,
+}
+
+impl PaddedSobolSampler {
+    pub fn new(samples_per_pixel: u64, dimensions: u32, seed: i32) -> Self {
+        Self {
+            pixel: PixelSamplerData::new(samples_per_pixel, dimensions, seed),
+            dimensions,
+        }
+    }
+}
+
+impl Sampler for PaddedSobolSampler {
+    fn create_new(&self, seed: i32) -> Box<dyn Sampler + Send + 'static> {
+        let sampler = Self::new(
+            self.pixel.samples_per_pixel(),
+            self.dimensions,
+            seed,
+        );
+        Box::new(sampler)
+    }
+
+    fn samples_per_pixel(&self) -> u64 {
+        self.pixel.samples_per_pixel()
+    }
+
+    fn start_pixel(&mut self, pixel: Point2i) {
+        generate_padded_samples(&mut self.pixel, owen_scramble);
+        self.pixel.start_pixel(pixel)
+    }
+
+    fn set_sample_number(&mut self, n: u64) -> bool {
+        self.pixel.set_sample_number(n)
+    }
+
+    fn start_next_sample(&mut self) -> bool {
+        self.pixel.start_next_sample()
+    }
+
+    fn get_1d(&mut self) -> Float {
+        self.pixel.get_1d()
+    }
+
+    fn get_2d(&mut self) -> Point2f {
+        self.pixel.get_2d()
+    }
+
+    fn request_1d_vec(&mut self, n: u32) {
+        self.pixel.request_1d_vec(n)
+    }
+
+    fn request_2d_vec(&mut self, n: u32) {
+        self.pixel.request_2d_vec(n)
+    }
+
+    fn get_1d_vec(&mut self, n: u32) -> Option<Vec<Float>> {
+        self.pixel.get_1d_vec(n)
+    }
+
+    fn get_2d_vec(&mut self, n: u32) -> Option<Vec<Point2f>> {
+        self.pixel.get_2d_vec(n)
+    }
+
+    fn round_count(&self, n: u32) -> u32 {
+        n.next_power_of_two()
+    }
+}
@@ -0,0 +1,119 @@
+use std::cmp::max;
+use cgmath::prelude::*;
+use num;
+use crate::prelude::*;
+use crate::sampling::low_discrepancy::{ sobol_sample, SobolPixelIndex, NUM_SOBOL_DIMENSIONS };
+use super::*;
+
+/// Generates samples from the Sobol sequence, with the first two dimensions
+/// scaled up to cover the whole image.
+pub struct SobolSampler {
+    global: GlobalSamplerData,
+    sequence: SobolSequence,
+}
+
+impl SobolSampler {
+    /// `samples_per_pixel` is rounded up to a power of two.
+    pub fn new(samples_per_pixel: u64, sample_bounds: Bounds2i) -> Self {
+        Self {
+            global: GlobalSamplerData::new(samples_per_pixel.next_power_of_two()),
+            sequence: SobolSequence::new(sample_bounds),
+        }
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn create_new(&self, _seed: i32) -> Box<dyn Sampler + Send + 'static> {
+        let sampler = Self {
+            global: GlobalSamplerData::new(self.global.samples_per_pixel()),
+            sequence: self.sequence.clone(),
+        };
+        Box::new(sampler)
+    }
+
+    fn samples_per_pixel(&self) -> u64 {
+        self.global.samples_per_pixel()
+    }
+
+    fn start_pixel(&mut self, pixel: Point2i) {
+        self.global.start_pixel(&mut self.sequence, pixel)
+    }
+
+    fn set_sample_number(&mut self, n: u64) -> bool {
+        self.global.set_sample_number(&mut self.sequence, n)
+    }
+
+    fn start_next_sample(&mut self) -> bool {
+        self.global.start_next_sample(&mut self.sequence)
+    }
+
+    fn get_1d(&mut self) -> Float {
+        self.global.get_1d(&mut self.sequence)
+    }
+
+    fn get_2d(&mut self) -> Point2f {
+        self.global.get_2d(&mut self.sequence)
+    }
+
+    fn request_1d_vec(&mut self, n: u32) {
+        self.global.request_1d_vec(n)
+    }
+
+    fn request_2d_vec(&mut self, n: u32) {
+        self.global.request_2d_vec(n)
+    }
+
+    fn get_1d_vec(&mut self, n: u32) -> Option<Vec<Float>> {
+        self.global.get_1d_vec(n)
+    }
+
+    fn get_2d_vec(&mut self, n: u32) -> Option<Vec<Point2f>> {
+        self.global.get_2d_vec(n)
+    }
+
+    fn round_count(&self, n: u32) -> u32 {
+        n.next_power_of_two()
+    }
+}
+
+#[derive(Clone)]
+struct SobolSequence {
+    sample_bounds: Bounds2i,
+    /// the power of two that the first two dimensions are scaled up to
+    resolution: i32,
+    pixel_index: SobolPixelIndex,
+}
+
+impl SobolSequence {
+    fn new(sample_bounds: Bounds2i) -> Self {
+        let diagonal = sample_bounds.diagonal();
+        let resolution = (max(max(diagonal.x, diagonal.y), 1) as u32).next_power_of_two();
+
+        Self {
+            sample_bounds,
+            resolution: resolution as i32,
+            pixel_index: SobolPixelIndex::new(resolution.trailing_zeros()),
+        }
+    }
+}
+
+impl GlobalSampler for SobolSequence {
+    fn get_index_for_sample(&mut self, pixel: Point2i, num: u64) -> u64 {
+        let p = Point2i::from_vec(pixel - self.sample_bounds.min);
+        self.pixel_index.index(num, p)
+    }
+
+    fn sample_dimension(&self, pixel: Point2i, index: u64, dimension: u32) -> Float {
+        // dimensions past the generator matrices wrap around, rather than panicking
+        let s = sobol_sample(index, dimension as usize % NUM_SOBOL_DIMENSIONS, 0);
+
+        // remap the dimensions used for the film position to an offset within the pixel
+        if dimension < 2 {
+            let d = dimension as usize;
+            let s = s * float(self.resolution) + float(self.sample_bounds.min[d]);
+            num::clamp(s - float(pixel[d]), float(0.0), float(ONE_MINUS_EPSILON))
+        } else {
+            s
+        }
+    }
+}
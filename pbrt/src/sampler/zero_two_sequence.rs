@@ -0,0 +1,75 @@
+use crate::prelude::*;
+use super::*;
+use super::padded::generate_padded_samples;
+
+/// Generates samples from a (0, 2)-sequence, with the bits of each dimension
+/// flipped by a random value for each pixel (random digit scrambling).
+pub struct ZeroTwoSequenceSampler {
+    pixel: PixelSamplerData,
+    dimensions: u32,
+}
+
+impl ZeroTwoSequenceSampler {
+    pub fn new(samples_per_pixel: u64, dimensions: u32, seed: i32) -> Self {
+        Self {
+            pixel: PixelSamplerData::new(samples_per_pixel, dimensions, seed),
+            dimensions,
+        }
+    }
+}
+
+impl Sampler for ZeroTwoSequenceSampler {
+    fn create_new(&self, seed: i32) -> Box<dyn Sampler + Send + 'static> {
+        let sampler = Self::new(
+            self.pixel.samples_per_pixel(),
+            self.dimensions,
+            seed,
+        );
+        Box::new(sampler)
+    }
+
+    fn samples_per_pixel(&self) -> u64 {
+        self.pixel.samples_per_pixel()
+    }
+
+    fn start_pixel(&mut self, pixel: Point2i) {
+        generate_padded_samples(&mut self.pixel, |v, seed| v ^ seed);
+        self.pixel.start_pixel(pixel)
+    }
+
+    fn set_sample_number(&mut self, n: u64) -> bool {
+        self.pixel.set_sample_number(n)
+    }
+
+    fn start_next_sample(&mut self) -> bool {
+        self.pixel.start_next_sample()
+    }
+
+    fn get_1d(&mut self) -> Float {
+        self.pixel.get_1d()
+    }
+
+    fn get_2d(&mut self) -> Point2f {
+        self.pixel.get_2d()
+    }
+
+    fn request_1d_vec(&mut self, n: u32) {
+        self.pixel.request_1d_vec(n)
+    }
+
+    fn request_2d_vec(&mut self, n: u32) {
+        self.pixel.request_2d_vec(n)
+    }
+
+    fn get_1d_vec(&mut self, n: u32) -> Option<Vec<Float>> {
+        self.pixel.get_1d_vec(n)
+    }
+
+    fn get_2d_vec(&mut self, n: u32) -> Option<Vec<Point2f>> {
+        self.pixel.get_2d_vec(n)
+    }
+
+    fn round_count(&self, n: u32) -> u32 {
+        n.next_power_of_two()
+    }
+}
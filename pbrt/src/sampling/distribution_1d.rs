@@ -7,6 +7,7 @@ pub struct Distribution1dSample {
     pub offset: usize,
 }
 
+#[derive(Debug)]
 pub struct Distribution1d {
     pub(super) function: Vec<Float>,
     cdf: Vec<Float>,
@@ -8,6 +8,7 @@ pub struct Distribution2dSample {
     pub pdf: Float,
 }
 
+#[derive(Debug)]
 pub struct Distribution2d {
     p_conditional_v: Vec<Distribution1d>,
     p_marginal: Distribution1d,
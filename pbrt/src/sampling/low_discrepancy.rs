@@ -0,0 +1,424 @@
+use std::cmp::min;
+use lazy_static::lazy_static;
+use rand::Rng;
+use crate::prelude::*;
+use crate::sampler::ONE_MINUS_EPSILON;
+
+/// The number of prime bases that radical inverses can be computed in.
+pub const PRIME_TABLE_SIZE: usize = 1000;
+
+/// The number of dimensions that Sobol generator matrices are computed for,
+/// the first one and one for each row of `JOE_KUO`.
+pub const NUM_SOBOL_DIMENSIONS: usize = 53;
+
+/// Each Sobol generator matrix has a column for each bit of a 32-bit sample index.
+pub const SOBOL_MATRIX_SIZE: usize = 32;
+
+lazy_static! {
+    pub static ref PRIMES: Vec<u64> = {
+        let mut primes = Vec::with_capacity(PRIME_TABLE_SIZE);
+        let mut n = 2;
+        while primes.len() < PRIME_TABLE_SIZE {
+            if primes.iter().take_while(|&&p| p * p <= n).all(|&p| n % p != 0) {
+                primes.push(n);
+            }
+            n += 1;
+        }
+        primes
+    };
+
+    /// The sum of all of the primes before each prime, which is where its digit permutation starts.
+    pub static ref PRIME_SUMS: Vec<usize> = PRIMES.iter()
+        .scan(0, |sum, &p| {
+            let start = *sum;
+            *sum += p as usize;
+            Some(start)
+        })
+        .collect();
+
+    pub static ref SOBOL_MATRICES: Vec<[u32; SOBOL_MATRIX_SIZE]> = sobol_matrices();
+}
+
+/// Reflects the digits of `a` in the base of the `base_index`th prime about the decimal point.
+pub fn radical_inverse(base_index: usize, mut a: u64) -> Float {
+    let base = PRIMES[base_index];
+    let inv_base = 1.0 / base as f64;
+
+    let mut reversed_digits = 0u64;
+    let mut inv_base_n = 1.0;
+    while a > 0 {
+        let next = a / base;
+        let digit = a - next * base;
+        reversed_digits = reversed_digits * base + digit;
+        inv_base_n *= inv_base;
+        a = next;
+    }
+
+    min(float(reversed_digits as f64 * inv_base_n), float(ONE_MINUS_EPSILON))
+}
+
+/// The inverse of `radical_inverse`, for a value with `n_digits` digits in `base`,
+/// given as an integer.
+pub fn inverse_radical_inverse(base: u64, mut inverse: u64, n_digits: u32) -> u64 {
+    let mut index = 0;
+    for _ in 0..n_digits {
+        let digit = inverse % base;
+        inverse /= base;
+        index = index * base + digit;
+    }
+    index
+}
+
+/// Like `radical_inverse`, but with each digit remapped by `perm`, which includes the
+/// infinite tail of zero digits.
+pub fn scrambled_radical_inverse(base_index: usize, mut a: u64, perm: &[u16]) -> Float {
+    let base = PRIMES[base_index];
+    let inv_base = 1.0 / base as f64;
+
+    let mut reversed_digits = 0u64;
+    let mut inv_base_n = 1.0;
+    while a > 0 {
+        let next = a / base;
+        let digit = a - next * base;
+        reversed_digits = reversed_digits * base + u64::from(perm[digit as usize]);
+        inv_base_n *= inv_base;
+        a = next;
+    }
+
+    let tail = inv_base * f64::from(perm[0]) / (1.0 - inv_base);
+    min(float(inv_base_n * (reversed_digits as f64 + tail)), float(ONE_MINUS_EPSILON))
+}
+
+/// A random permutation of the digits of each prime base, one after another.
+/// The permutation for a base starts at `PRIME_SUMS[base_index]`.
+pub fn compute_radical_inverse_permutations(rng: &mut impl Rng) -> Vec<u16> {
+    let total = PRIME_SUMS[PRIME_TABLE_SIZE - 1] + PRIMES[PRIME_TABLE_SIZE - 1] as usize;
+    let mut perms = Vec::with_capacity(total);
+
+    for &base in PRIMES.iter() {
+        let start = perms.len();
+        perms.extend(0..base as u16);
+
+        let perm = &mut perms[start..];
+        for i in 0..perm.len() {
+            let other = rng.gen_range(i, perm.len());
+            perm.swap(i, other);
+        }
+    }
+
+    perms
+}
+
+/// The `dimension`th value of the Sobol sample at `index`,
+/// with its bits flipped by `scramble`.
+pub fn sobol_sample(index: u64, dimension: usize, scramble: u32) -> Float {
+    sample_from_bits(sobol_sample_bits(index, dimension, scramble))
+}
+
+/// `sobol_sample` as a 32-bit fixed point value.
+pub fn sobol_sample_bits(mut index: u64, dimension: usize, scramble: u32) -> u32 {
+    let matrix = &SOBOL_MATRICES[dimension];
+
+    let mut v = scramble;
+    let mut i = 0;
+    while index != 0 && i < SOBOL_MATRIX_SIZE {
+        if index & 1 != 0 {
+            v ^= matrix[i];
+        }
+        index >>= 1;
+        i += 1;
+    }
+    v
+}
+
+/// Converts a 32-bit fixed point value to a sample in `[0, 1)`.
+pub fn sample_from_bits(v: u32) -> Float {
+    min(float(f64::from(v) * 2f64.powi(-32)), float(ONE_MINUS_EPSILON))
+}
+
+/// Randomly permutes the digits of a 32-bit fixed point sample, where each digit
+/// is flipped depending on the digits above it (Owen scrambling).
+/// This keeps the stratification of a `(t, m, s)`-net, but removes its regular structure.
+pub fn owen_scramble(mut v: u32, seed: u32) -> u32 {
+    if seed & 1 != 0 {
+        v ^= 1 << 31;
+    }
+    for b in 1..32 {
+        let mask = !0u32 << (32 - b);
+        if mix_bits(u64::from((v & mask) ^ seed)) & (1 << b) != 0 {
+            v ^= 1 << (31 - b);
+        }
+    }
+    v
+}
+
+/// Permutes the sample indices below `2^bits`, with each bit flipped depending
+/// on the bits above it.
+///
+/// The first `2^k` indices are sent to an aligned block of `2^k` indices,
+/// so a prefix of a permuted `(t, s)`-sequence is as well distributed as the unpermuted prefix.
+pub fn scramble_index(index: u64, bits: u32, seed: u64) -> u64 {
+    let mut result = index;
+    for b in 0..bits {
+        let higher = index.checked_shr(b + 1).unwrap_or(0);
+        if mix_bits(higher ^ seed ^ u64::from(b).wrapping_mul(0x9e37_79b9_7f4a_7c15)) & 1 != 0 {
+            result ^= 1 << b;
+        }
+    }
+    result
+}
+
+/// A hash that mixes every bit of `v` into every bit of the result.
+pub fn mix_bits(mut v: u64) -> u64 {
+    v ^= v >> 31;
+    v = v.wrapping_mul(0x7fb5_d329_728e_a185);
+    v ^= v >> 27;
+    v = v.wrapping_mul(0x81da_def4_bc2d_d44d);
+    v ^= v >> 33;
+    v
+}
+
+/// Finds which Sobol samples land in a pixel, when the first two dimensions
+/// are scaled up to cover `2^m` by `2^m` pixels.
+///
+/// Every block of `2^2m` samples puts exactly one sample in each pixel,
+/// so the `frame`th sample in a pixel is in the `frame`th block.
+#[derive(Clone, Debug)]
+pub struct SobolPixelIndex {
+    m: u32,
+    /// the pixel that each bit of the frame number moves a sample by, packed as `x << m | y`
+    frame_columns: Vec<u64>,
+    /// the low index bits needed to move a sample by each bit of a packed pixel
+    inverse_columns: Vec<u64>,
+}
+
+impl SobolPixelIndex {
+    pub fn new(m: u32) -> Self {
+        assert!(2 * m as usize <= SOBOL_MATRIX_SIZE, "resolution is too large for the Sobol matrices");
+
+        let n = 2 * m as usize;
+        let pixel_bits = |column: usize| {
+            let x = u64::from(SOBOL_MATRICES[0][column]) >> (32 - m);
+            let y = u64::from(SOBOL_MATRICES[1][column]) >> (32 - m);
+            if m == 0 { 0 } else { (x << m) | y }
+        };
+
+        // invert the matrix that maps the low `2m` bits of the index to a pixel,
+        // by Gauss-Jordan elimination on its columns over GF(2)
+        let mut columns = (0..n)
+            .map(|c| (pixel_bits(c), 1u64 << c))
+            .collect::<Vec<_>>();
+
+        for bit in 0..n {
+            let pivot = (bit..n)
+                .find(|&c| columns[c].0 & (1 << bit) != 0)
+                .expect("the first two Sobol dimensions should form a (0, 2)-sequence");
+            columns.swap(bit, pivot);
+
+            for c in 0..n {
+                if c != bit && columns[c].0 & (1 << bit) != 0 {
+                    columns[c].0 ^= columns[bit].0;
+                    columns[c].1 ^= columns[bit].1;
+                }
+            }
+        }
+
+        Self {
+            m,
+            frame_columns: (n..SOBOL_MATRIX_SIZE).map(pixel_bits).collect(),
+            inverse_columns: columns.into_iter().map(|(_, index)| index).collect(),
+        }
+    }
+
+    /// The global index of the `frame`th sample in `pixel`,
+    /// which is relative to the corner of the sampled area.
+    pub fn index(&self, mut frame: u64, pixel: Point2i) -> u64 {
+        let mut index = frame << (2 * self.m);
+
+        // the pixel the sample would be in with the low bits of the index all zero
+        let mut delta = 0;
+        let mut c = 0;
+        while frame != 0 && c < self.frame_columns.len() {
+            if frame & 1 != 0 {
+                delta ^= self.frame_columns[c];
+            }
+            frame >>= 1;
+            c += 1;
+        }
+
+        // then pick the low bits that move it to `pixel`
+        let mut b = (((pixel.x as u64) << self.m) | pixel.y as u64) ^ delta;
+        let mut c = 0;
+        while b != 0 && c < self.inverse_columns.len() {
+            if b & 1 != 0 {
+                index ^= self.inverse_columns[c];
+            }
+            b >>= 1;
+            c += 1;
+        }
+
+        index
+    }
+}
+
+/// Primitive polynomial degrees, coefficients and initial direction numbers
+/// for the Sobol dimensions after the first, from Joe and Kuo's `new-joe-kuo-6.21201`.
+/// These are all of the primitive polynomials up to degree 8, in order.
+const JOE_KUO: &[(u32, u32, &[u32])] = &[
+    (1, 0, &[1]),
+    (2, 1, &[1, 3]),
+    (3, 1, &[1, 3, 1]),
+    (3, 2, &[1, 1, 1]),
+    (4, 1, &[1, 1, 3, 3]),
+    (4, 4, &[1, 3, 5, 13]),
+    (5, 2, &[1, 1, 5, 5, 17]),
+    (5, 4, &[1, 1, 5, 5, 5]),
+    (5, 7, &[1, 1, 7, 11, 19]),
+    (5, 11, &[1, 1, 5, 1, 1]),
+    (5, 13, &[1, 1, 1, 3, 11]),
+    (5, 14, &[1, 3, 5, 5, 31]),
+    (6, 1, &[1, 3, 3, 9, 7, 49]),
+    (6, 13, &[1, 1, 1, 15, 21, 21]),
+    (6, 16, &[1, 3, 1, 13, 27, 49]),
+    (6, 19, &[1, 1, 1, 15, 7, 5]),
+    (6, 22, &[1, 3, 1, 15, 13, 25]),
+    (6, 25, &[1, 1, 5, 5, 19, 61]),
+    (7, 1, &[1, 3, 7, 11, 23, 15, 103]),
+    (7, 4, &[1, 3, 7, 13, 13, 15, 69]),
+    (7, 7, &[1, 1, 3, 13, 7, 35, 63]),
+    (7, 8, &[1, 3, 5, 9, 1, 25, 53]),
+    (7, 14, &[1, 3, 1, 13, 9, 35, 107]),
+    (7, 19, &[1, 3, 1, 5, 27, 61, 31]),
+    (7, 21, &[1, 1, 5, 11, 19, 41, 61]),
+    (7, 28, &[1, 3, 5, 3, 3, 13, 69]),
+    (7, 31, &[1, 1, 7, 13, 1, 19, 1]),
+    (7, 32, &[1, 3, 7, 5, 13, 19, 59]),
+    (7, 37, &[1, 1, 3, 9, 25, 29, 41]),
+    (7, 41, &[1, 3, 5, 13, 23, 1, 55]),
+    (7, 42, &[1, 3, 7, 3, 13, 59, 17]),
+    (7, 50, &[1, 3, 1, 3, 5, 53, 69]),
+    (7, 55, &[1, 1, 5, 5, 23, 33, 13]),
+    (7, 56, &[1, 1, 7, 7, 1, 61, 123]),
+    (7, 59, &[1, 1, 7, 9, 13, 61, 49]),
+    (7, 62, &[1, 3, 3, 5, 3, 55, 33]),
+    (8, 14, &[1, 3, 1, 15, 31, 13, 49, 245]),
+    (8, 21, &[1, 3, 5, 15, 31, 59, 63, 97]),
+    (8, 22, &[1, 3, 1, 11, 11, 11, 77, 249]),
+    (8, 38, &[1, 3, 1, 11, 27, 43, 71, 9]),
+    (8, 47, &[1, 1, 7, 15, 21, 11, 81, 45]),
+    (8, 49, &[1, 3, 7, 3, 25, 31, 65, 79]),
+    (8, 50, &[1, 3, 1, 1, 19, 11, 3, 205]),
+    (8, 52, &[1, 1, 5, 9, 19, 21, 29, 157]),
+    (8, 56, &[1, 3, 7, 11, 1, 33, 89, 185]),
+    (8, 67, &[1, 3, 3, 3, 15, 9, 79, 71]),
+    (8, 70, &[1, 3, 7, 11, 15, 39, 119, 27]),
+    (8, 84, &[1, 1, 3, 1, 11, 31, 97, 225]),
+    (8, 97, &[1, 1, 1, 3, 23, 43, 57, 177]),
+    (8, 103, &[1, 3, 7, 7, 17, 17, 37, 71]),
+    (8, 115, &[1, 3, 1, 5, 27, 63, 123, 213]),
+    (8, 122, &[1, 1, 3, 5, 11, 43, 53, 133]),
+];
+
+fn sobol_matrices() -> Vec<[u32; SOBOL_MATRIX_SIZE]> {
+    let mut matrices = Vec::with_capacity(NUM_SOBOL_DIMENSIONS);
+
+    // the first dimension is the van der Corput sequence
+    let mut first = [0; SOBOL_MATRIX_SIZE];
+    for (i, v) in first.iter_mut().enumerate() {
+        *v = 1 << (31 - i);
+    }
+    matrices.push(first);
+
+    for &(s, a, m) in JOE_KUO {
+        let s = s as usize;
+
+        // v[i] is m[i] shifted up to the top bits, so it's a binary fraction
+        let mut v = [0u32; SOBOL_MATRIX_SIZE];
+        for i in 0..min(s, SOBOL_MATRIX_SIZE) {
+            v[i] = m[i] << (31 - i);
+        }
+        for i in s..SOBOL_MATRIX_SIZE {
+            v[i] = v[i - s] ^ (v[i - s] >> s);
+            for k in 1..s {
+                if (a >> (s - 1 - k)) & 1 != 0 {
+                    v[i] ^= v[i - k];
+                }
+            }
+        }
+
+        matrices.push(v);
+    }
+
+    matrices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether `x` generates every non-zero element modulo the polynomial with degree `degree`
+    /// and bits `poly`.
+    fn is_primitive(degree: u32, poly: u32) -> bool {
+        let order = (1u32 << degree) - 1;
+        let mut r = 1u32;
+        for k in 1..=order {
+            r <<= 1;
+            if r & (1 << degree) != 0 {
+                r ^= poly;
+            }
+            if r == 1 {
+                return k == order;
+            }
+        }
+        false
+    }
+
+    /// The primitive polynomials over GF(2), in order of degree and then coefficients.
+    /// Each is given as its degree and its middle coefficients, from highest to lowest.
+    fn primitive_polynomials() -> impl Iterator<Item = (u32, u32)> {
+        (1..32).flat_map(|degree| {
+            (0..1u32 << (degree - 1))
+                .filter(move |&a| is_primitive(degree, (1 << degree) | (a << 1) | 1))
+                .map(move |a| (degree, a))
+        })
+    }
+
+    #[test]
+    fn joe_kuo_table() {
+        assert_eq!(JOE_KUO.len() + 1, NUM_SOBOL_DIMENSIONS);
+        assert_eq!(SOBOL_MATRICES.len(), NUM_SOBOL_DIMENSIONS);
+
+        // every primitive polynomial up to degree 8
+        let polynomials = primitive_polynomials().take_while(|&(degree, _)| degree <= 8);
+        let table = JOE_KUO.iter().map(|&(s, a, _)| (s, a));
+        assert!(polynomials.eq(table));
+
+        // initial direction numbers have to be odd, and m[k] < 2^(k + 1)
+        for &(s, _, m) in JOE_KUO {
+            assert_eq!(m.len(), s as usize);
+            for (k, m) in m.iter().enumerate() {
+                assert!(m % 2 == 1 && *m < 1 << (k + 1), "invalid direction number {}", m);
+            }
+        }
+    }
+
+    #[test]
+    fn sobol_points() {
+        // the first points of the 2D Sobol sequence
+        let points = [(0.0, 0.0), (0.5, 0.5), (0.25, 0.75), (0.75, 0.25), (0.125, 0.625), (0.625, 0.125)];
+        for (i, &(x, y)) in points.iter().enumerate() {
+            assert_eq!(sobol_sample(i as u64, 0, 0), x);
+            assert_eq!(sobol_sample(i as u64, 1, 0), y);
+        }
+
+        // every dimension is a (0, 1)-sequence, so each 2^k points are stratified into 2^k intervals
+        for dimension in 0..NUM_SOBOL_DIMENSIONS {
+            let mut strata = [false; 64];
+            for i in 0..64 {
+                let s = sobol_sample(i, dimension, 0);
+                strata[(s.raw() * 64.0) as usize] = true;
+            }
+            assert!(strata.iter().all(|s| *s), "dimension {} isn't stratified", dimension);
+        }
+    }
+}
@@ -5,3 +5,5 @@ mod distribution_2d;
 pub use self::distribution_2d::{ Distribution2d, Distribution2dSample };
 
 pub mod utils;
+
+pub mod low_discrepancy;
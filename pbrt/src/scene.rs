@@ -2,33 +2,43 @@ use std::sync::Arc;
 use crate::prelude::*;
 use crate::light::Light;
 use crate::math::*;
+use crate::medium::{ Medium, MediumId };
 use crate::primitive::Primitive;
+use crate::sampler::Sampler;
 use crate::interaction::SurfaceInteraction;
 
 pub struct Scene {
     pub lights: Vec<Arc<dyn Light + Send + Sync>>,
+    /// `MediumId`s are indices into this
+    pub media: Vec<Arc<dyn Medium + Send + Sync>>,
     aggregate: Arc<dyn Primitive + Send + Sync>,
     world_bound: Bounds3<Float>,
 }
 
 impl Scene {
-    pub fn new(aggregate: Arc<dyn Primitive + Send + Sync>, mut lights: Vec<Box<dyn Light + Send + Sync>>) -> Self {
-        let mut scene = Self {
+    /// Area lights need to share their `Arc` with the `GeometricPrimitive`
+    /// for the shape that they're attached to.
+    pub fn new(aggregate: Arc<dyn Primitive + Send + Sync>, lights: Vec<Arc<dyn Light + Send + Sync>>) -> Self {
+        Self::with_media(aggregate, lights, vec![])
+    }
+
+    /// The `MediumId`s used by the primitives and camera are indices into `media`.
+    pub fn with_media(
+        aggregate: Arc<dyn Primitive + Send + Sync>,
+        lights: Vec<Arc<dyn Light + Send + Sync>>,
+        media: Vec<Arc<dyn Medium + Send + Sync>>,
+    ) -> Self {
+        let scene = Self {
             world_bound: aggregate.world_bound(),
-            lights: vec![],
+            lights,
+            media,
             aggregate,
         };
 
-        for light in &mut lights {
+        for light in &scene.lights {
             light.preprocess(&scene);
         }
 
-        let lights = lights.into_iter()
-            .map(|l| l.into())
-            .collect();
-
-        scene.lights = lights;
-
         scene
     }
 
@@ -36,6 +46,10 @@ impl Scene {
         &self.world_bound
     }
 
+    pub fn medium(&self, id: MediumId) -> &(dyn Medium + Send + Sync) {
+        &*self.media[id.0]
+    }
+
     pub fn intersect(&self, ray: &mut Ray) -> Option<SurfaceInteraction<'_>> {
         assert_ne!(ray.direction, Vector3f::new(float(0), float(0), float(0)));
         self.aggregate.intersect(ray)
@@ -44,4 +58,32 @@ impl Scene {
     pub fn intersect_p(&self, ray: &Ray) -> bool {
         self.aggregate.intersect_p(ray)
     }
+
+    /// Finds the first surface with a material along `ray`, passing through the surfaces
+    /// without one, which only mark the boundaries of media.
+    ///
+    /// Also returns the transmittance of the media up to the surface, or along the whole ray
+    /// if there isn't one.
+    pub fn intersect_tr(&self, mut ray: Ray, sampler: &mut dyn Sampler) -> (Option<SurfaceInteraction<'_>>, Spectrum) {
+        let mut tr = Spectrum::new(1.0);
+
+        loop {
+            let isect = self.intersect(&mut ray);
+
+            // transmittance for current ray segment in medium
+            if let Some(medium) = ray.medium {
+                tr *= self.medium(medium).tr(&ray, sampler);
+            }
+
+            match isect {
+                Some(isect) => {
+                    if isect.primitive.map_or(false, |p| p.get_material().is_some()) {
+                        return (Some(isect), tr);
+                    }
+                    ray = isect.spawn_ray(&ray.direction);
+                },
+                None => return (None, tr),
+            }
+        }
+    }
 }
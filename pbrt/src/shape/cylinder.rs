@@ -6,7 +6,8 @@ use num;
 use crate::prelude::*;
 use crate::math::*;
 use crate::math::Transform;
-use crate::interaction::SurfaceInteraction;
+use crate::interaction::{ BaseInteraction, SurfaceInteraction };
+use crate::medium::MediumInterface;
 
 use super::{ Shape, ShapeData };
 
@@ -187,4 +188,34 @@ impl Shape for Cylinder {
     fn area(&self) -> Float {
         (self.z_max - self.z_min) * self.radius * self.phi_max
     }
+
+    fn sample(&self, u: Point2f) -> (BaseInteraction, Float) {
+        let z = self.z_min + (self.z_max - self.z_min) * u[0];
+        let phi = u[1] * self.phi_max;
+        let mut p_obj = Point3f::new(self.radius * phi.cos(), self.radius * phi.sin(), z);
+
+        let object_to_world = &self.shape_data.object_to_world;
+        let mut n = object_to_world.transform_normal(Normal::new(p_obj.x, p_obj.y, float(0.0))).normalize();
+        if self.shape_data.reverse_orientation {
+            n = (-*n).into();
+        }
+
+        // reproject p_obj to the cylinder surface and compute its error
+        let hit_radius = (p_obj.x.powi(2) + p_obj.y.powi(2)).sqrt();
+        p_obj.x *= self.radius / hit_radius;
+        p_obj.y *= self.radius / hit_radius;
+        let p_obj_err = Vector3f::new(p_obj.x, p_obj.y, float(0.0)).abs() * gammaf(3);
+        let (p, p_err) = object_to_world.transform_point_with_abs_error(p_obj, p_obj_err);
+
+        let isect = BaseInteraction {
+            p,
+            time: float(0.0),
+            p_err,
+            wo: Vector3f::zero(),
+            n: Some(n),
+            medium_interface: MediumInterface::default(),
+        };
+
+        (isect, float(1.0) / self.area())
+    }
 }
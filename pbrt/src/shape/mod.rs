@@ -1,9 +1,10 @@
 use std::fmt::Debug;
 use std::sync::Arc;
+use cgmath::prelude::*;
 use crate::prelude::*;
 use crate::math::*;
 use crate::math::Transform;
-use crate::interaction::SurfaceInteraction;
+use crate::interaction::{ BaseInteraction, SurfaceInteraction };
 
 mod cylinder;
 pub use self::cylinder::Cylinder;
@@ -11,6 +12,9 @@ pub use self::cylinder::Cylinder;
 mod sphere;
 pub use self::sphere::Sphere;
 
+mod triangle;
+pub use self::triangle::{ create_triangle_mesh, Triangle, TriangleMesh };
+
 #[derive(Clone, Debug)]
 pub struct ShapeData {
     pub object_to_world: Arc<Transform>,
@@ -61,4 +65,73 @@ pub trait Shape: Debug {
     }
 
     fn area(&self) -> Float;
+
+    /// Samples a point uniformly by area on the surface of the shape,
+    /// returning it along with the pdf with respect to area.
+    fn sample(&self, u: Point2f) -> (BaseInteraction, Float);
+
+    /// The pdf with respect to area of sampling `isect` with `sample`.
+    fn pdf(&self, _isect: &BaseInteraction) -> Float {
+        float(1.0) / self.area()
+    }
+
+    /// Samples a point on the shape as seen from `reference`,
+    /// returning it along with the pdf with respect to solid angle at `reference`.
+    fn sample_solid_angle(&self, reference: &BaseInteraction, u: Point2f) -> (BaseInteraction, Float) {
+        sample_area_as_solid_angle(self, reference, u)
+    }
+
+    /// The pdf with respect to solid angle of sampling the direction `wi` from `reference`
+    /// with `sample_solid_angle`.
+    fn pdf_solid_angle(&self, reference: &BaseInteraction, wi: Vector3f) -> Float {
+        pdf_area_as_solid_angle(self, reference, wi)
+    }
+}
+
+/// Samples `shape` uniformly by area,
+/// and converts the pdf to be with respect to solid angle at `reference`.
+pub fn sample_area_as_solid_angle<S: Shape + ?Sized>(shape: &S, reference: &BaseInteraction, u: Point2f) -> (BaseInteraction, Float) {
+    let (isect, pdf) = shape.sample(u);
+
+    let wi = isect.p - reference.p;
+    if wi.magnitude2() == 0.0 {
+        return (isect, float(0.0));
+    }
+    let wi = wi.normalize();
+
+    // convert from area measure to solid angle measure
+    let cos_theta = (*isect.n.unwrap()).dot(-wi).abs();
+    if cos_theta == 0.0 {
+        return (isect, float(0.0));
+    }
+
+    let pdf = pdf * reference.p.distance2(isect.p) / cos_theta;
+    if pdf.is_infinite() {
+        (isect, float(0.0))
+    } else {
+        (isect, pdf)
+    }
+}
+
+/// The pdf with respect to solid angle of `sample_area_as_solid_angle`.
+pub fn pdf_area_as_solid_angle<S: Shape + ?Sized>(shape: &S, reference: &BaseInteraction, wi: Vector3f) -> Float {
+    // intersect sample ray with the shape
+    let ray = reference.spawn_ray(&wi);
+    let isect = match shape.intersect(&ray, false) {
+        Some((_, isect)) => isect,
+        None => return float(0.0),
+    };
+
+    // convert from area measure to solid angle measure
+    let cos_theta = (*isect.n.unwrap()).dot(-wi).abs();
+    if cos_theta == 0.0 {
+        return float(0.0);
+    }
+
+    let pdf = reference.p.distance2(isect.p) / (cos_theta * shape.area());
+    if pdf.is_infinite() {
+        float(0.0)
+    } else {
+        pdf
+    }
 }
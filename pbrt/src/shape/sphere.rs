@@ -5,9 +5,11 @@ use cgmath::{ Rad, Deg };
 use num;
 use crate::prelude::*;
 use crate::math::*;
-use crate::interaction::SurfaceInteraction;
+use crate::interaction::{ BaseInteraction, SurfaceInteraction };
+use crate::medium::MediumInterface;
+use crate::sampling::utils::{ uniform_cone_pdf, uniform_sample_sphere };
 
-use super::{ Shape, ShapeData };
+use super::{ pdf_area_as_solid_angle, sample_area_as_solid_angle, Shape, ShapeData };
 
 #[derive(Clone, Debug)]
 pub struct Sphere {
@@ -198,4 +200,95 @@ impl Shape for Sphere {
     fn area(&self) -> Float {
         self.phi_max * self.radius * (self.z_max - self.z_min)
     }
+
+    fn sample(&self, u: Point2f) -> (BaseInteraction, Float) {
+        let mut p_obj = Point3f::zero() + uniform_sample_sphere(u) * self.radius;
+
+        let object_to_world = &self.shape_data.object_to_world;
+        let mut n = object_to_world.transform_normal(p_obj.into_vector().into()).normalize();
+        if self.shape_data.reverse_orientation {
+            n = (-*n).into();
+        }
+
+        // reproject p_obj to the sphere surface and compute its error
+        p_obj *= self.radius / p_obj.distance(Point3f::zero());
+        let p_obj_err = p_obj.abs().into_vector() * gammaf(5);
+        let (p, p_err) = object_to_world.transform_point_with_abs_error(p_obj, p_obj_err);
+
+        let isect = BaseInteraction {
+            p,
+            time: float(0.0),
+            p_err,
+            wo: Vector3f::zero(),
+            n: Some(n),
+            medium_interface: MediumInterface::default(),
+        };
+
+        (isect, float(1.0) / self.area())
+    }
+
+    fn sample_solid_angle(&self, reference: &BaseInteraction, u: Point2f) -> (BaseInteraction, Float) {
+        let p_center = self.shape_data.object_to_world.transform_point(Point3f::zero());
+
+        // sample uniformly by area if the reference point is inside the sphere
+        let ref_n = reference.n.unwrap_or_else(Normal::zero);
+        let p_origin = offset_ray_origin(&reference.p, &reference.p_err, &ref_n, &(p_center - reference.p));
+        if p_origin.distance2(p_center) <= self.radius.powi(2) {
+            return sample_area_as_solid_angle(self, reference, u);
+        }
+
+        // compute the cone that the sphere subtends from the reference point
+        let dc = reference.p.distance(p_center);
+        let sin_theta_max_2 = self.radius.powi(2) / dc.powi(2);
+        let cos_theta_max = max(float(0.0), float(1.0) - sin_theta_max_2).sqrt();
+
+        // sample a direction inside the cone
+        let cos_theta = (float(1.0) - u[0]) + u[0] * cos_theta_max;
+        let sin_theta = max(float(0.0), float(1.0) - cos_theta.powi(2)).sqrt();
+        let phi = u[1] * float(2.0) * Float::pi();
+
+        // compute the angle alpha from the center of the sphere to the sampled point
+        let ds = dc * cos_theta - max(float(0.0), self.radius.powi(2) - dc.powi(2) * sin_theta.powi(2)).sqrt();
+        let cos_alpha = (dc.powi(2) + self.radius.powi(2) - ds.powi(2)) / (float(2.0) * dc * self.radius);
+        let sin_alpha = max(float(0.0), float(1.0) - cos_alpha.powi(2)).sqrt();
+
+        // compute the point on the sphere, and its normal
+        let wc = (p_center - reference.p).normalize();
+        let (_, wc_x, wc_y) = wc.coord_system();
+        let n_world = spherical_direction_from_axis(sin_alpha, cos_alpha, phi, -wc_x, -wc_y, -wc);
+        let p_world = p_center + n_world * self.radius;
+
+        let mut n: Normal = n_world.into();
+        if self.shape_data.reverse_orientation {
+            n = (-*n).into();
+        }
+
+        let isect = BaseInteraction {
+            p: p_world,
+            time: float(0.0),
+            p_err: p_world.abs().into_vector() * gammaf(5),
+            wo: Vector3f::zero(),
+            n: Some(n),
+            medium_interface: MediumInterface::default(),
+        };
+
+        (isect, uniform_cone_pdf(cos_theta_max))
+    }
+
+    fn pdf_solid_angle(&self, reference: &BaseInteraction, wi: Vector3f) -> Float {
+        let p_center = self.shape_data.object_to_world.transform_point(Point3f::zero());
+
+        // return the area based pdf if the reference point is inside the sphere
+        let ref_n = reference.n.unwrap_or_else(Normal::zero);
+        let p_origin = offset_ray_origin(&reference.p, &reference.p_err, &ref_n, &(p_center - reference.p));
+        if p_origin.distance2(p_center) <= self.radius.powi(2) {
+            return pdf_area_as_solid_angle(self, reference, wi);
+        }
+
+        // otherwise it's the pdf of uniformly sampling the subtended cone
+        let sin_theta_max_2 = self.radius.powi(2) / reference.p.distance2(p_center);
+        let cos_theta_max = max(float(0.0), float(1.0) - sin_theta_max_2).sqrt();
+
+        uniform_cone_pdf(cos_theta_max)
+    }
 }
@@ -0,0 +1,472 @@
+use std::sync::Arc;
+use cgmath::prelude::*;
+use crate::prelude::*;
+use crate::math::*;
+use crate::math::Transform;
+use crate::interaction::{ BaseInteraction, SurfaceInteraction };
+use crate::medium::MediumInterface;
+use crate::sampling::utils::uniform_sample_triangle;
+
+use super::{ Shape, ShapeData };
+
+/// The vertex data that is shared between all of the `Triangle`s in a mesh.
+///
+/// All of the vertex attributes are stored in world space,
+/// so that each `Triangle` doesn't have to transform the ray into object space.
+#[derive(Debug)]
+pub struct TriangleMesh {
+    pub n_triangles: usize,
+    pub vertex_indices: Vec<usize>,
+    pub p: Vec<Point3f>,
+    pub n: Option<Vec<Normal>>,
+    pub s: Option<Vec<Vector3f>>,
+    pub uv: Option<Vec<Point2f>>,
+    shape_data: ShapeData,
+}
+
+impl TriangleMesh {
+    #[cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
+    pub fn new(
+        object_to_world: Arc<Transform>,
+        reverse_orientation: bool,
+        vertex_indices: Vec<usize>,
+        p: Vec<Point3f>,
+        n: Option<Vec<Normal>>,
+        s: Option<Vec<Vector3f>>,
+        uv: Option<Vec<Point2f>>,
+    ) -> Self {
+        assert_eq!(vertex_indices.len() % 3, 0);
+
+        // transform mesh vertices to world space
+        let p = p.into_iter()
+            .map(|p| object_to_world.transform_point(p))
+            .collect();
+
+        let n = n.map(|n| n.into_iter()
+            .map(|n| object_to_world.transform_normal(n))
+            .collect());
+
+        let s = s.map(|s| s.into_iter()
+            .map(|s| object_to_world.transform_vector(s))
+            .collect());
+
+        Self {
+            n_triangles: vertex_indices.len() / 3,
+            vertex_indices,
+            p,
+            n,
+            s,
+            uv,
+            shape_data: ShapeData::new(object_to_world, reverse_orientation),
+        }
+    }
+
+    /// Creates a `Triangle` for every face in the mesh.
+    pub fn triangles(mesh: &Arc<TriangleMesh>) -> Vec<Triangle> {
+        (0..mesh.n_triangles)
+            .map(|i| Triangle::new(mesh.clone(), i))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Triangle {
+    mesh: Arc<TriangleMesh>,
+    /// The offset of this triangle's first vertex index in `mesh.vertex_indices`.
+    v: usize,
+    shape_data: ShapeData,
+}
+
+/// The result of the watertight ray-triangle test,
+/// before any of the surface parameterisation has been calculated.
+#[derive(Copy, Clone, Debug)]
+struct TriangleHit {
+    t: Float,
+    b0: Float,
+    b1: Float,
+    b2: Float,
+}
+
+impl Triangle {
+    pub fn new(mesh: Arc<TriangleMesh>, triangle: usize) -> Self {
+        assert!(triangle < mesh.n_triangles);
+
+        Self {
+            shape_data: mesh.shape_data.clone(),
+            v: triangle * 3,
+            mesh,
+        }
+    }
+
+    #[inline(always)]
+    fn indices(&self) -> [usize; 3] {
+        let v = &self.mesh.vertex_indices;
+        [v[self.v], v[self.v + 1], v[self.v + 2]]
+    }
+
+    #[inline(always)]
+    fn vertices(&self) -> [Point3f; 3] {
+        let [v0, v1, v2] = self.indices();
+        let p = &self.mesh.p;
+        [p[v0], p[v1], p[v2]]
+    }
+
+    fn uvs(&self) -> [Point2f; 3] {
+        match &self.mesh.uv {
+            Some(uv) => {
+                let [v0, v1, v2] = self.indices();
+                [uv[v0], uv[v1], uv[v2]]
+            },
+            None => [
+                Point2f::new(float(0.0), float(0.0)),
+                Point2f::new(float(1.0), float(0.0)),
+                Point2f::new(float(1.0), float(1.0)),
+            ],
+        }
+    }
+
+    #[cfg_attr(feature = "cargo-clippy", allow(many_single_char_names))]
+    fn intersect_watertight(&self, ray: &Ray) -> Option<TriangleHit> {
+        let [p0, p1, p2] = self.vertices();
+
+        // translate vertices based on ray origin
+        let mut p0t = p0 - ray.origin;
+        let mut p1t = p1 - ray.origin;
+        let mut p2t = p2 - ray.origin;
+
+        // permute components of the triangle vertices and ray direction
+        // so that the ray direction's largest component is along z
+        let kz: usize = ray.direction.abs().max_dimension().into();
+        let kx = (kz + 1) % 3;
+        let ky = (kx + 1) % 3;
+
+        let permute = |v: Vector3f| Vector3f::new(v[kx], v[ky], v[kz]);
+        let d = permute(ray.direction);
+        p0t = permute(p0t);
+        p1t = permute(p1t);
+        p2t = permute(p2t);
+
+        // apply shear transformation to the translated vertex positions
+        // so that the ray direction becomes +z
+        let sx = -d.x / d.z;
+        let sy = -d.y / d.z;
+        let sz = float(1.0) / d.z;
+
+        p0t.x += sx * p0t.z;
+        p0t.y += sy * p0t.z;
+        p1t.x += sx * p1t.z;
+        p1t.y += sy * p1t.z;
+        p2t.x += sx * p2t.z;
+        p2t.y += sy * p2t.z;
+
+        // compute edge function coefficients
+        let mut e0 = p1t.x * p2t.y - p1t.y * p2t.x;
+        let mut e1 = p2t.x * p0t.y - p2t.y * p0t.x;
+        let mut e2 = p0t.x * p1t.y - p0t.y * p1t.x;
+
+        // fall back to double precision test at the triangle's edges
+        #[cfg(not(feature = "double"))] {
+            if e0 == 0.0 || e1 == 0.0 || e2 == 0.0 {
+                let edge = |a: Vector3f, b: Vector3f| {
+                    let ax = f64::from(a.x.raw());
+                    let ay = f64::from(a.y.raw());
+                    let bx = f64::from(b.x.raw());
+                    let by = f64::from(b.y.raw());
+                    float((ax * by - ay * bx) as FloatPrim)
+                };
+
+                e0 = edge(p1t, p2t);
+                e1 = edge(p2t, p0t);
+                e2 = edge(p0t, p1t);
+            }
+        }
+
+        // perform triangle edge and determinant tests
+        if (e0 < 0.0 || e1 < 0.0 || e2 < 0.0) && (e0 > 0.0 || e1 > 0.0 || e2 > 0.0) {
+            return None;
+        }
+
+        let det = e0 + e1 + e2;
+        if det == 0.0 {
+            return None;
+        }
+
+        // compute scaled hit distance to triangle and test against ray t range
+        p0t.z *= sz;
+        p1t.z *= sz;
+        p2t.z *= sz;
+
+        let t_scaled = e0 * p0t.z + e1 * p1t.z + e2 * p2t.z;
+        if det < 0.0 && (t_scaled >= 0.0 || t_scaled < ray.max * det) {
+            return None;
+        } else if det > 0.0 && (t_scaled <= 0.0 || t_scaled > ray.max * det) {
+            return None;
+        }
+
+        // compute barycentric coordinates and t value for triangle intersection
+        let inv_det = float(1.0) / det;
+        let b0 = e0 * inv_det;
+        let b1 = e1 * inv_det;
+        let b2 = e2 * inv_det;
+        let t = t_scaled * inv_det;
+
+        // ensure that computed triangle t is conservatively greater than zero
+        // compute delta_z term for triangle t error bounds
+        let max_zt = Vector3f::new(p0t.z, p1t.z, p2t.z).abs().max_component();
+        let delta_z = gammaf(3) * max_zt;
+
+        // compute delta_x and delta_y terms for triangle t error bounds
+        let max_xt = Vector3f::new(p0t.x, p1t.x, p2t.x).abs().max_component();
+        let max_yt = Vector3f::new(p0t.y, p1t.y, p2t.y).abs().max_component();
+        let delta_x = gammaf(5) * (max_xt + max_zt);
+        let delta_y = gammaf(5) * (max_yt + max_zt);
+
+        // compute delta_e term for triangle t error bounds
+        let delta_e = float(2.0) * (gammaf(2) * max_xt * max_yt + delta_y * max_xt + delta_x * max_yt);
+
+        // compute delta_t term for triangle t error bounds and check t
+        let max_e = Vector3f::new(e0, e1, e2).abs().max_component();
+        let delta_t = float(3.0) *
+            (gammaf(3) * max_e * max_zt + delta_e * max_zt + delta_z * max_e) *
+            inv_det.abs();
+
+        if t <= delta_t {
+            return None;
+        }
+
+        Some(TriangleHit { t, b0, b1, b2 })
+    }
+}
+
+impl Shape for Triangle {
+    fn data(&self) -> &ShapeData { &self.shape_data }
+
+    fn object_bounds(&self) -> Bounds3f {
+        let [p0, p1, p2] = self.vertices();
+        let world_to_object = self.world_to_object();
+
+        Bounds3f::new(
+            world_to_object.transform_point(p0),
+            world_to_object.transform_point(p1),
+        ).union_p(world_to_object.transform_point(p2))
+    }
+
+    fn world_bound(&self) -> Bounds3f {
+        let [p0, p1, p2] = self.vertices();
+
+        Bounds3f::new(p0, p1).union_p(p2)
+    }
+
+    #[cfg_attr(feature = "cargo-clippy", allow(many_single_char_names))]
+    fn intersect(&'a self, ray: &Ray, _: bool) -> Option<(Float, SurfaceInteraction<'a>)> {
+        let TriangleHit { t, b0, b1, b2 } = self.intersect_watertight(ray)?;
+
+        let [p0, p1, p2] = self.vertices();
+        let [v0, v1, v2] = self.indices();
+
+        // compute triangle partial derivatives
+        let uv = self.uvs();
+
+        // compute deltas for triangle partial derivatives
+        let duv02 = uv[0] - uv[2];
+        let duv12 = uv[1] - uv[2];
+        let dp02 = p0 - p2;
+        let dp12 = p1 - p2;
+
+        let determinant = duv02.x * duv12.y - duv02.y * duv12.x;
+        let degenerate_uv = determinant.abs() < 1e-8;
+
+        let (mut dpdu, mut dpdv) = (Vector3f::zero(), Vector3f::zero());
+        if !degenerate_uv {
+            let inv_det = float(1.0) / determinant;
+            dpdu = (dp02 * duv12.y - dp12 * duv02.y) * inv_det;
+            dpdv = (dp12 * duv02.x - dp02 * duv12.x) * inv_det;
+        }
+
+        if degenerate_uv || dpdu.cross(dpdv).length_squared() == 0.0 {
+            // handle zero determinant for triangle partial derivative matrix
+            // by choosing an arbitrary coordinate system about the triangle's normal
+            let ng = (p2 - p0).cross(p1 - p0);
+            if ng.length_squared() == 0.0 {
+                // the triangle is actually degenerate
+                // so the intersection is bogus
+                return None;
+            }
+
+            let (_, u, v) = ng.normalize().coord_system();
+            dpdu = u;
+            dpdv = v;
+        }
+
+        // compute error bounds for triangle intersection
+        let x_abs_sum = (b0 * p0.x).abs() + (b1 * p1.x).abs() + (b2 * p2.x).abs();
+        let y_abs_sum = (b0 * p0.y).abs() + (b1 * p1.y).abs() + (b2 * p2.y).abs();
+        let z_abs_sum = (b0 * p0.z).abs() + (b1 * p1.z).abs() + (b2 * p2.z).abs();
+        let p_err = Vector3f::new(x_abs_sum, y_abs_sum, z_abs_sum) * gammaf(7);
+
+        // interpolate (u, v) parametric coordinates and hit point
+        let p_hit = (p0.into_vector() * b0 + p1.into_vector() * b1 + p2.into_vector() * b2).into_point();
+        let uv_hit = (uv[0].into_vector() * b0 + uv[1].into_vector() * b1 + uv[2].into_vector() * b2).into_point();
+
+        let mut isect = SurfaceInteraction::new(
+            p_hit,
+            p_err,
+            uv_hit,
+            -ray.direction,
+            dpdu,
+            dpdv,
+            Normal::zero(),
+            Normal::zero(),
+            ray.time,
+            Some(self),
+            None,
+        );
+
+        // override surface normal in `isect` for triangle
+        let mut n: Normal = dp02.cross(dp12).normalize().into();
+        if self.reverse_orientation() ^ self.transform_swaps_handedness() {
+            n = (-*n).into();
+        }
+        isect.n = Some(n);
+        isect.shading.n = n;
+
+        if self.mesh.n.is_some() || self.mesh.s.is_some() {
+            // initialise triangle shading geometry
+            // compute shading normal `ns` for triangle
+            let ns = match &self.mesh.n {
+                Some(normals) => {
+                    let ns = *normals[v0] * b0 + *normals[v1] * b1 + *normals[v2] * b2;
+                    if ns.length_squared() > 0.0 {
+                        ns.normalize()
+                    } else {
+                        *n
+                    }
+                },
+                None => *n,
+            };
+
+            // compute shading tangent `ss` for triangle
+            let mut ss = match &self.mesh.s {
+                Some(tangents) => {
+                    let ss = tangents[v0] * b0 + tangents[v1] * b1 + tangents[v2] * b2;
+                    if ss.length_squared() > 0.0 {
+                        ss
+                    } else {
+                        isect.dpdu
+                    }
+                },
+                None => isect.dpdu,
+            };
+
+            // compute shading bitangent `ts` for triangle and adjust `ss`
+            let mut ts = ss.cross(ns);
+            if ts.length_squared() > 0.0 {
+                ts = ts.normalize();
+                ss = ts.cross(ns);
+            } else {
+                let (_, s, t) = ns.coord_system();
+                ss = s;
+                ts = t;
+            }
+
+            // compute dndu and dndv for triangle shading geometry
+            let (dndu, dndv) = match &self.mesh.n {
+                Some(normals) => {
+                    // compute deltas for triangle partial derivatives of normal
+                    let dn1 = *normals[v0] - *normals[v2];
+                    let dn2 = *normals[v1] - *normals[v2];
+
+                    if degenerate_uv {
+                        // we can still compute dndu and dndv, with respect to the
+                        // same arbitrary coordinate system we use to compute dpdu
+                        // and dpdv when this happens
+                        let dn = (*normals[v2] - *normals[v0]).cross(*normals[v1] - *normals[v0]);
+                        if dn.length_squared() == 0.0 {
+                            (Normal::zero(), Normal::zero())
+                        } else {
+                            let (_, dnu, dnv) = dn.coord_system();
+                            (dnu.into(), dnv.into())
+                        }
+                    } else {
+                        let inv_det = float(1.0) / determinant;
+                        (
+                            ((dn1 * duv12.y - dn2 * duv02.y) * inv_det).into(),
+                            ((dn2 * duv02.x - dn1 * duv12.x) * inv_det).into(),
+                        )
+                    }
+                },
+                None => (Normal::zero(), Normal::zero()),
+            };
+
+            isect.set_shading_geometry(ss, ts, dndu, dndv, true);
+        }
+
+        Some((t, isect))
+    }
+
+    fn intersect_p(&self, ray: &Ray, _: bool) -> bool {
+        self.intersect_watertight(ray).is_some()
+    }
+
+    fn area(&self) -> Float {
+        let [p0, p1, p2] = self.vertices();
+
+        float(0.5) * (p1 - p0).cross(p2 - p0).magnitude()
+    }
+
+    fn sample(&self, u: Point2f) -> (BaseInteraction, Float) {
+        let [p0, p1, p2] = self.vertices();
+        let b = uniform_sample_triangle(u);
+        let b2 = float(1.0) - b[0] - b[1];
+
+        let p = p0 * b[0] + p1.into_vector() * b[1] + p2.into_vector() * b2;
+
+        // compute the surface normal for the sampled point
+        let mut n: Normal = (p1 - p0).cross(p2 - p0).normalize().into();
+        n = match &self.mesh.n {
+            Some(normals) => {
+                let [v0, v1, v2] = self.indices();
+                let ns = *normals[v0] * b[0] + *normals[v1] * b[1] + *normals[v2] * b2;
+                n.face_forward(ns)
+            },
+            None if self.shape_data.reverse_orientation ^ self.transform_swaps_handedness() => (-*n).into(),
+            None => n,
+        };
+
+        // compute the error bounds for the sampled point
+        let p_abs_sum = (p0.into_vector() * b[0]).abs() + (p1.into_vector() * b[1]).abs() + (p2.into_vector() * b2).abs();
+        let p_err = p_abs_sum * gammaf(6);
+
+        let isect = BaseInteraction {
+            p,
+            time: float(0.0),
+            p_err,
+            wo: Vector3f::zero(),
+            n: Some(n),
+            medium_interface: MediumInterface::default(),
+        };
+
+        (isect, float(1.0) / self.area())
+    }
+}
+
+/// Creates the shared `TriangleMesh` and returns a `Shape` for each of its faces,
+/// ready to be wrapped in `GeometricPrimitive`s.
+#[cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
+pub fn create_triangle_mesh(
+    object_to_world: Arc<Transform>,
+    reverse_orientation: bool,
+    vertex_indices: Vec<usize>,
+    p: Vec<Point3f>,
+    n: Option<Vec<Normal>>,
+    s: Option<Vec<Vector3f>>,
+    uv: Option<Vec<Point2f>>,
+) -> Vec<Arc<dyn Shape + Send + Sync>> {
+    let mesh = TriangleMesh::new(object_to_world, reverse_orientation, vertex_indices, p, n, s, uv);
+    let mesh = Arc::new(mesh);
+
+    TriangleMesh::triangles(&mesh)
+        .into_iter()
+        .map(|t| Arc::new(t) as Arc<dyn Shape + Send + Sync>)
+        .collect()
+}
@@ -69,6 +69,10 @@ pub trait Spectrum: Deref<Target = [Float]> + DerefMut {
         true
     }
 
+    fn max_component_value(&self) -> Float {
+        self.deref().iter().cloned().max().unwrap_or_else(|| float(0.0))
+    }
+
     fn iter(&self) -> SpectrumIter<'_> {
         SpectrumIter(self.deref().into_iter())
     }
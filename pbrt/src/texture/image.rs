@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use std::hash::{ Hash, Hasher };
+use std::path::{ Path, PathBuf };
+use std::sync::{ Arc, Mutex };
+use lazy_static::lazy_static;
+use crate::prelude::*;
+use crate::interaction::SurfaceInteraction;
+use crate::loader::{ read_image, ImageError };
+use super::{ ImageWrap, MipMap, Texel, Texture, TextureMapping2d };
+
+/// Everything that affects how an image file is turned into a `MipMap`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TexInfo {
+    filename: PathBuf,
+    do_trilinear: bool,
+    max_anisotropy: Float,
+    wrap: ImageWrap,
+    scale: Float,
+    gamma: bool,
+}
+
+impl Hash for TexInfo {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.filename.hash(state);
+        self.do_trilinear.hash(state);
+        self.max_anisotropy.raw().to_bits().hash(state);
+        self.wrap.hash(state);
+        self.scale.raw().to_bits().hash(state);
+        self.gamma.hash(state);
+    }
+}
+
+type MipMapCache<T> = Mutex<HashMap<TexInfo, Arc<MipMap<T>>>>;
+
+lazy_static! {
+    static ref FLOAT_MIPMAPS: MipMapCache<Float> = Mutex::new(HashMap::new());
+    static ref SPECTRUM_MIPMAPS: MipMapCache<Spectrum> = Mutex::new(HashMap::new());
+}
+
+/// A `Texel` that an `ImageTexture` can be made of.
+pub trait ImageTexel: Texel + 'static {
+    fn from_spectrum(s: Spectrum) -> Self;
+
+    /// Each texel type keeps its own cache of loaded images.
+    fn cache() -> &'static MipMapCache<Self>;
+}
+
+impl ImageTexel for Float {
+    fn from_spectrum(s: Spectrum) -> Self {
+        s.y()
+    }
+
+    fn cache() -> &'static MipMapCache<Self> {
+        &FLOAT_MIPMAPS
+    }
+}
+
+impl ImageTexel for Spectrum {
+    fn from_spectrum(s: Spectrum) -> Self {
+        s
+    }
+
+    fn cache() -> &'static MipMapCache<Self> {
+        &SPECTRUM_MIPMAPS
+    }
+}
+
+/// A texture that looks up values in an image file.
+///
+/// Images are cached, so a file that is used by several textures is only read once.
+#[derive(Debug)]
+pub struct ImageTexture<T: ImageTexel> {
+    mapping: Box<dyn TextureMapping2d + Send + Sync>,
+    mipmap: Arc<MipMap<T>>,
+    gamma: bool,
+}
+
+impl<T: ImageTexel> ImageTexture<T> {
+    /// Each texel is multiplied by `scale`, after being converted from sRGB to linear if `gamma` is set.
+    pub fn new(
+        mapping: Box<dyn TextureMapping2d + Send + Sync>,
+        filename: impl AsRef<Path>,
+        do_trilinear: bool,
+        max_anisotropy: Float,
+        wrap: ImageWrap,
+        scale: Float,
+        gamma: bool,
+    ) -> Result<Self, ImageError> {
+        let info = TexInfo {
+            filename: filename.as_ref().to_path_buf(),
+            do_trilinear,
+            max_anisotropy,
+            wrap,
+            scale,
+            gamma,
+        };
+
+        let mipmap = Self::get_texture(info)?;
+        Ok(Self { mapping, mipmap, gamma })
+    }
+
+    fn get_texture(info: TexInfo) -> Result<Arc<MipMap<T>>, ImageError> {
+        // the lock is held while reading, so that two threads don't read the same file
+        let mut cache = T::cache().lock().unwrap();
+        if let Some(mipmap) = cache.get(&info) {
+            return Ok(mipmap.clone());
+        }
+
+        let (resolution, pixels) = read_image(&info.filename)?;
+
+        // flip image in y, texture coordinate space has (0, 0) at the lower left corner
+        let width = resolution.x as usize;
+        let texels = pixels.chunks(width)
+            .rev()
+            .flat_map(|row| row.iter())
+            .map(|texel| {
+                let texel = if info.gamma {
+                    let mut texel = *texel;
+                    for c in texel.iter_mut() {
+                        *c = inverse_gamma_correct(*c);
+                    }
+                    texel
+                } else {
+                    *texel
+                };
+
+                T::from_spectrum(texel * info.scale)
+            })
+            .collect();
+
+        let mipmap = Arc::new(MipMap::new(resolution, texels, info.do_trilinear, info.max_anisotropy, info.wrap));
+        cache.insert(info, mipmap.clone());
+        Ok(mipmap)
+    }
+
+    /// Empties the cache, the images are freed once all of their textures have been dropped.
+    pub fn clear_cache() {
+        T::cache().lock().unwrap().clear();
+    }
+}
+
+impl<T: ImageTexel> Texture<T> for ImageTexture<T> {
+    fn evaluate(&self, si: &SurfaceInteraction<'_>) -> T {
+        let st = self.mapping.map(si);
+        self.mipmap.lookup(st.point, st.dstdx, st.dstdy)
+    }
+
+    fn is_gamma_corrected(&self) -> bool {
+        self.gamma
+    }
+}
@@ -0,0 +1,391 @@
+use std::cmp::{ max, min };
+use std::fmt::Debug;
+use std::ops::{ Add, Mul };
+use cgmath::prelude::*;
+use lazy_static::lazy_static;
+use num;
+use crate::prelude::*;
+
+/// What happens to lookups outside of `[0, 1]`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ImageWrap {
+    Repeat,
+    Black,
+    Clamp,
+}
+
+/// A value that can be stored in a `MipMap`.
+pub trait Texel: Copy + Debug + Send + Sync + Add<Output = Self> + Mul<Float, Output = Self> {
+    fn zero() -> Self;
+
+    /// Resampling can give negative values, which are clamped to `0`.
+    fn clamp_positive(self) -> Self;
+}
+
+impl Texel for Float {
+    fn zero() -> Self {
+        float(0.0)
+    }
+
+    fn clamp_positive(self) -> Self {
+        max(self, float(0.0))
+    }
+}
+
+impl Texel for Spectrum {
+    fn zero() -> Self {
+        Spectrum::new(0.0)
+    }
+
+    fn clamp_positive(self) -> Self {
+        self.clamp(None, None)
+    }
+}
+
+fn lerp<T: Texel>(t: Float, a: T, b: T) -> T {
+    a * (float(1.0) - t) + b * t
+}
+
+const WEIGHT_LUT_SIZE: usize = 128;
+
+lazy_static! {
+    /// Gaussian filter weights for EWA lookups, indexed by the squared distance from the ellipse's center.
+    static ref WEIGHT_LUT: Vec<Float> = {
+        let alpha = float(2.0);
+        (0..WEIGHT_LUT_SIZE)
+            .map(|i| {
+                let r2 = float(i as f32) / float((WEIGHT_LUT_SIZE - 1) as f32);
+                (-alpha * r2).exp() - (-alpha).exp()
+            })
+            .collect()
+    };
+}
+
+struct ResampleWeight {
+    first_texel: i32,
+    weight: [Float; 4],
+}
+
+fn lanczos(x: Float, tau: Float) -> Float {
+    let x = x.abs();
+    if x < 1e-5 {
+        return float(1.0);
+    }
+    if x > 1.0 {
+        return float(0.0);
+    }
+
+    let x = x * Float::pi();
+    let s = (x * tau).sin() / (x * tau);
+    let lanczos = x.sin() / x;
+    s * lanczos
+}
+
+fn resample_weights(old_res: usize, new_res: usize) -> Vec<ResampleWeight> {
+    assert!(new_res >= old_res);
+
+    let filter_width = float(2.0);
+    (0..new_res)
+        .map(|i| {
+            // compute image resampling weights for the i-th texel
+            let center = (float(i as f32) + float(0.5)) * float(old_res as f32) / float(new_res as f32);
+            let first_texel = (center - filter_width + float(0.5)).floor().raw() as i32;
+
+            let mut weight = [float(0.0); 4];
+            for (j, w) in weight.iter_mut().enumerate() {
+                let pos = float((first_texel + j as i32) as f32) + float(0.5);
+                *w = lanczos((pos - center) / filter_width, float(2.0));
+            }
+
+            // normalize filter weights for texel resampling
+            let inv_sum_weights = float(1.0) / weight.iter().fold(float(0.0), |a, b| a + *b);
+            for w in &mut weight {
+                *w *= inv_sum_weights;
+            }
+
+            ResampleWeight { first_texel, weight }
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+struct Level<T> {
+    width: usize,
+    height: usize,
+    texels: Vec<T>,
+}
+
+/// An image pyramid, where each level is half the resolution of the one before.
+///
+/// Images that don't have a power of 2 resolution are resampled up to one.
+#[derive(Debug)]
+pub struct MipMap<T: Texel> {
+    do_trilinear: bool,
+    max_anisotropy: Float,
+    wrap: ImageWrap,
+    pyramid: Vec<Level<T>>,
+}
+
+impl<T: Texel> MipMap<T> {
+    /// `image` is in scanline order, with `resolution.x * resolution.y` texels.
+    pub fn new(resolution: Point2i, image: Vec<T>, do_trilinear: bool, max_anisotropy: Float, wrap: ImageWrap) -> Self {
+        assert!(resolution.x > 0 && resolution.y > 0, "MipMap images can't be empty");
+        let (width, height) = (resolution.x as usize, resolution.y as usize);
+        assert_eq!(image.len(), width * height);
+
+        let (width, height, image) = if width.is_power_of_two() && height.is_power_of_two() {
+            (width, height, image)
+        } else {
+            Self::resample(width, height, &image, wrap)
+        };
+
+        // initialize levels of the mipmap from the image
+        let n_levels = 1 + (max(width, height) as f32).log2() as usize;
+        let mut pyramid = Vec::with_capacity(n_levels);
+        pyramid.push(Level { width, height, texels: image });
+
+        let mut mipmap = Self {
+            do_trilinear,
+            max_anisotropy,
+            wrap,
+            pyramid,
+        };
+
+        for i in 1..n_levels {
+            // initialize the i-th level from the level before it
+            let width = max(1, mipmap.pyramid[i - 1].width / 2);
+            let height = max(1, mipmap.pyramid[i - 1].height / 2);
+
+            let mut texels = Vec::with_capacity(width * height);
+            for t in 0..height as i32 {
+                for s in 0..width as i32 {
+                    let sum = mipmap.texel(i - 1, 2 * s, 2 * t) +
+                        mipmap.texel(i - 1, 2 * s + 1, 2 * t) +
+                        mipmap.texel(i - 1, 2 * s, 2 * t + 1) +
+                        mipmap.texel(i - 1, 2 * s + 1, 2 * t + 1);
+                    texels.push(sum * float(0.25));
+                }
+            }
+
+            mipmap.pyramid.push(Level { width, height, texels });
+        }
+
+        mipmap
+    }
+
+    /// Resamples the image up to the next power of 2 resolution, using a Lanczos filter.
+    fn resample(width: usize, height: usize, image: &[T], wrap: ImageWrap) -> (usize, usize, Vec<T>) {
+        let new_width = width.next_power_of_two();
+        let new_height = height.next_power_of_two();
+
+        // finds the texel that `i` refers to, if there is one
+        let wrapped = |i: i32, res: usize| -> Option<usize> {
+            let i = match wrap {
+                ImageWrap::Repeat => modulo(i, res as i32),
+                ImageWrap::Clamp => num::clamp(i, 0, res as i32 - 1),
+                ImageWrap::Black => i,
+            };
+
+            if i >= 0 && i < res as i32 { Some(i as usize) } else { None }
+        };
+
+        // resample image in s direction
+        let s_weights = resample_weights(width, new_width);
+        let mut resampled = vec![T::zero(); new_width * new_height];
+        for t in 0..height {
+            for (s, weights) in s_weights.iter().enumerate() {
+                let mut sum = T::zero();
+                for (j, w) in weights.weight.iter().enumerate() {
+                    if let Some(orig_s) = wrapped(weights.first_texel + j as i32, width) {
+                        sum = sum + image[t * width + orig_s] * *w;
+                    }
+                }
+                resampled[t * new_width + s] = sum;
+            }
+        }
+
+        // resample image in t direction
+        let t_weights = resample_weights(height, new_height);
+        let mut column = vec![T::zero(); new_height];
+        for s in 0..new_width {
+            for (t, weights) in t_weights.iter().enumerate() {
+                let mut sum = T::zero();
+                for (j, w) in weights.weight.iter().enumerate() {
+                    if let Some(orig_t) = wrapped(weights.first_texel + j as i32, height) {
+                        sum = sum + resampled[orig_t * new_width + s] * *w;
+                    }
+                }
+                column[t] = sum;
+            }
+
+            for (t, texel) in column.iter().enumerate() {
+                resampled[t * new_width + s] = texel.clamp_positive();
+            }
+        }
+
+        (new_width, new_height, resampled)
+    }
+
+    pub fn width(&self) -> usize {
+        self.pyramid[0].width
+    }
+
+    pub fn height(&self) -> usize {
+        self.pyramid[0].height
+    }
+
+    pub fn levels(&self) -> usize {
+        self.pyramid.len()
+    }
+
+    pub fn texel(&self, level: usize, s: i32, t: i32) -> T {
+        let l = &self.pyramid[level];
+        let (width, height) = (l.width as i32, l.height as i32);
+
+        let (s, t) = match self.wrap {
+            ImageWrap::Repeat => (modulo(s, width), modulo(t, height)),
+            ImageWrap::Clamp => (num::clamp(s, 0, width - 1), num::clamp(t, 0, height - 1)),
+            ImageWrap::Black => {
+                if s < 0 || s >= width || t < 0 || t >= height {
+                    return T::zero();
+                }
+                (s, t)
+            },
+        };
+
+        l.texels[(t * width + s) as usize]
+    }
+
+    /// Filters with a box of side `width`, interpolating between the two nearest levels.
+    pub fn lookup_trilinear(&self, st: Point2f, width: Float) -> T {
+        // compute mipmap level for trilinear filtering
+        let n_levels = float(self.levels() as f32);
+        let level = n_levels - float(1.0) + max(width, float(1e-8)).log2();
+
+        // perform trilinear interpolation at the appropriate level
+        if level < 0.0 {
+            self.triangle(0, st)
+        } else if level >= n_levels - float(1.0) {
+            self.texel(self.levels() - 1, 0, 0)
+        } else {
+            let i_level = level.floor();
+            let delta = level - i_level;
+            let i_level = i_level.raw() as usize;
+            lerp(delta, self.triangle(i_level, st), self.triangle(i_level + 1, st))
+        }
+    }
+
+    /// Filters over the footprint given by the differentials `dst0` and `dst1`,
+    /// using either trilinear or EWA filtering.
+    pub fn lookup(&self, st: Point2f, dst0: Vector2f, dst1: Vector2f) -> T {
+        if self.do_trilinear {
+            let width = float(2.0) * max(
+                max(dst0.x.abs(), dst0.y.abs()),
+                max(dst1.x.abs(), dst1.y.abs()),
+            );
+            return self.lookup_trilinear(st, width);
+        }
+
+        // compute ellipse minor and major axes
+        let (dst0, mut dst1) = if dst0.magnitude2() < dst1.magnitude2() {
+            (dst1, dst0)
+        } else {
+            (dst0, dst1)
+        };
+        let major_length = dst0.magnitude();
+        let mut minor_length = dst1.magnitude();
+
+        // clamp ellipse eccentricity if too large
+        if minor_length * self.max_anisotropy < major_length && minor_length > 0.0 {
+            let scale = major_length / (minor_length * self.max_anisotropy);
+            dst1 *= scale;
+            minor_length *= scale;
+        }
+        if minor_length == 0.0 {
+            return self.triangle(0, st);
+        }
+
+        // choose level of detail for EWA lookup and perform EWA filtering
+        let lod = max(float(0.0), float(self.levels() as f32) - float(1.0) + minor_length.log2());
+        let i_lod = lod.floor();
+        let delta = lod - i_lod;
+        let i_lod = i_lod.raw() as usize;
+        lerp(delta, self.ewa(i_lod, st, dst0, dst1), self.ewa(i_lod + 1, st, dst0, dst1))
+    }
+
+    /// Bilinearly interpolates the 4 texels around `st`.
+    fn triangle(&self, level: usize, st: Point2f) -> T {
+        let level = min(level, self.levels() - 1);
+        let l = &self.pyramid[level];
+
+        let s = st.x * float(l.width as f32) - float(0.5);
+        let t = st.y * float(l.height as f32) - float(0.5);
+        let (s0, t0) = (s.floor(), t.floor());
+        let (ds, dt) = (s - s0, t - t0);
+        let (s0, t0) = (s0.raw() as i32, t0.raw() as i32);
+
+        let one = float(1.0);
+        self.texel(level, s0, t0) * ((one - ds) * (one - dt)) +
+            self.texel(level, s0, t0 + 1) * ((one - ds) * dt) +
+            self.texel(level, s0 + 1, t0) * (ds * (one - dt)) +
+            self.texel(level, s0 + 1, t0 + 1) * (ds * dt)
+    }
+
+    fn ewa(&self, level: usize, st: Point2f, dst0: Vector2f, dst1: Vector2f) -> T {
+        if level >= self.levels() {
+            return self.texel(self.levels() - 1, 0, 0);
+        }
+
+        // convert EWA coordinates to appropriate scale for level
+        let l = &self.pyramid[level];
+        let (width, height) = (float(l.width as f32), float(l.height as f32));
+        let s = st.x * width - float(0.5);
+        let t = st.y * height - float(0.5);
+        let dst0 = Vector2f::new(dst0.x * width, dst0.y * height);
+        let dst1 = Vector2f::new(dst1.x * width, dst1.y * height);
+
+        // compute ellipse coefficients to bound EWA filter region
+        let mut a = dst0.y * dst0.y + dst1.y * dst1.y + float(1.0);
+        let mut b = float(-2.0) * (dst0.x * dst0.y + dst1.x * dst1.y);
+        let mut c = dst0.x * dst0.x + dst1.x * dst1.x + float(1.0);
+        let inv_f = float(1.0) / (a * c - b * b * float(0.25));
+        a *= inv_f;
+        b *= inv_f;
+        c *= inv_f;
+
+        // compute the ellipse's (s, t) bounding box in texture space
+        let det = -b * b + float(4.0) * a * c;
+        let inv_det = float(1.0) / det;
+        let u_sqrt = (det * c).sqrt();
+        let v_sqrt = (a * det).sqrt();
+        let s0 = (s - float(2.0) * inv_det * u_sqrt).ceil().raw() as i32;
+        let s1 = (s + float(2.0) * inv_det * u_sqrt).floor().raw() as i32;
+        let t0 = (t - float(2.0) * inv_det * v_sqrt).ceil().raw() as i32;
+        let t1 = (t + float(2.0) * inv_det * v_sqrt).floor().raw() as i32;
+
+        // scan over ellipse bound and compute quadratic equation
+        let mut sum = T::zero();
+        let mut sum_weights = float(0.0);
+        for it in t0..=t1 {
+            let tt = float(it as f32) - t;
+            for is in s0..=s1 {
+                let ss = float(is as f32) - s;
+
+                // compute squared radius and filter texel if inside ellipse
+                let r2 = a * ss * ss + b * ss * tt + c * tt * tt;
+                if r2 < 1.0 {
+                    let index = min((r2 * float(WEIGHT_LUT_SIZE as f32)).raw() as usize, WEIGHT_LUT_SIZE - 1);
+                    let weight = WEIGHT_LUT[index];
+                    sum = sum + self.texel(level, is, it) * weight;
+                    sum_weights += weight;
+                }
+            }
+        }
+
+        if sum_weights == 0.0 {
+            return self.triangle(level, st);
+        }
+
+        sum * (float(1.0) / sum_weights)
+    }
+}
@@ -6,11 +6,22 @@ use crate::interaction::SurfaceInteraction;
 mod constant;
 pub use self::constant::ConstantTexture;
 
+mod image;
+pub use self::image::{ ImageTexel, ImageTexture };
+
+mod mipmap;
+pub use self::mipmap::{ ImageWrap, MipMap, Texel };
+
 mod uv_mapping;
 pub use self::uv_mapping::UvMapping2d;
 
 pub trait Texture<T>: Debug {
     fn evaluate(&self, si: &SurfaceInteraction<'_>) -> T;
+
+    /// Whether the values were converted from sRGB, which is wrong for data like normal maps.
+    fn is_gamma_corrected(&self) -> bool {
+        false
+    }
 }
 
 pub struct Mapping2d {
@@ -19,6 +30,6 @@ pub struct Mapping2d {
     pub dstdy: Vector2f,
 }
 
-pub trait TextureMapping2d {
+pub trait TextureMapping2d: Debug {
     fn map(&self, si: &SurfaceInteraction<'_>) -> Mapping2d;
 }
@@ -2,6 +2,7 @@ use crate::prelude::*;
 use super::{ TextureMapping2d, Mapping2d };
 use crate::interaction::SurfaceInteraction;
 
+#[derive(Debug)]
 pub struct UvMapping2d {
     pub su: Float,
     pub sv: Float,
@@ -9,6 +10,18 @@ pub struct UvMapping2d {
     pub dv: Float,
 }
 
+impl UvMapping2d {
+    pub fn new(su: Float, sv: Float, du: Float, dv: Float) -> Self {
+        Self { su, sv, du, dv }
+    }
+}
+
+impl Default for UvMapping2d {
+    fn default() -> Self {
+        Self::new(float(1.0), float(1.0), float(0.0), float(0.0))
+    }
+}
+
 impl TextureMapping2d for UvMapping2d {
     fn map(&self, si: &SurfaceInteraction<'_>) -> Mapping2d {
         let dstdx = Vector2f::new(self.su * si.dudx, self.sv * si.dvdx);